@@ -1,12 +1,84 @@
 use cosmwasm_std::Addr;
 use cosmwasm_std::{
-    entry_point, to_binary, Deps, DepsMut, Env, MessageInfo, QueryResponse, Response, StdError, StdResult
+    entry_point, from_binary, to_binary, to_vec, Binary, Decimal, Deps, DepsMut, Env, Event, MessageInfo, QueryResponse,
+    Response, StdError, StdResult, Storage, Uint128,
 };
+use std::ops::RangeInclusive;
+use std::str::FromStr;
 use secret_toolkit::permit::validate;
 use secret_toolkit::permit::Permit;
-use crate::msg::{CountResponse, ExecuteMsg, InstantiateMsg, QueryMsg, InvoiceListResponse};
-use crate::state::{config, config_invoice, config_invoice_read, config_read, Invoice, State};
+use secret_toolkit::snip20::{register_receive_msg, transfer_msg};
+use sha2::{Digest, Sha256};
+use crate::msg::{
+    AccountBalance, AccountBalanceResponse, AccountListResponse, AgedPayable, AgedPayablesResponse,
+    AnomalyStatisticsResponse, AuditDecision, AuditOpinionResponse, AuditSampleResponse, BudgetLine,
+    BudgetReportResponse, CountResponse, CredibilityThresholdsResponse, CurrencyConfigResponse, DisclosedField,
+    DisclosedInvoice, DisclosedInvoicesResponse, ExchangeRateResponse, ExecuteMsg, FiscalPeriodListResponse,
+    InclusionProofResponse, InstantiateMsg, InvoiceListResponse, LedgerCommitmentResponse, LedgerRootStatusResponse,
+    MerkleProof, MerkleRootsResponse, PaymentsResponse, PendingApprovalsResponse, PeriodRoot, PurchaseOrderResponse,
+    QueryMsg, ReceiveMsg, SupplierInvoicesResponse, SupplierListResponse, TaxCodeListResponse, TrialBalanceResponse,
+    VatReturnResponse,
+};
+use crate::state::{
+    account_codes, account_codes_read, account_month_key, account_month_totals, account_month_totals_read,
+    account_totals, account_totals_read, accounts, accounts_read, anomaly_months, anomaly_months_read, approval_rules,
+    approval_rules_read, approvers, approvers_read, audit_opinions, audit_opinions_read, audit_samples,
+    audit_samples_read, auditors, auditors_read, category_budget_key, category_budgets, category_budgets_read, config,
+    config_invoice, config_invoice_read, config_read, credibility_thresholds, credibility_thresholds_read,
+    currency_config, currency_config_read, disclosure_grants, disclosure_grants_read, disclosure_salts,
+    disclosure_salts_read, exchange_rate_key, exchange_rates, exchange_rates_read, expense_categories,
+    expense_categories_read, expense_category_codes, expense_category_codes_read, fiscal_period_names,
+    fiscal_period_names_read, fiscal_periods, fiscal_periods_read, goods_receipt_key, goods_receipts,
+    goods_receipts_read, invoice_key, invoice_version_key, invoice_versions, invoice_versions_read, ledger_roots,
+    ledger_roots_read, ledger_span, ledger_span_read, match_tolerance, match_tolerance_read, merkle_node_key,
+    merkle_nodes, merkle_nodes_read, merkle_root_key, merkle_roots, merkle_roots_read, merkle_trees, merkle_trees_read,
+    month_invoices, month_invoices_read, open_approvals, open_approvals_read, open_payables, open_payables_read,
+    payment_key, payments, payments_read, pending_invoices, pending_invoices_read, purchase_orders,
+    purchase_orders_read, split_rule, split_rule_read, supplier_ids, supplier_ids_read, supplier_invoice_key,
+    supplier_invoices, supplier_invoices_read, supplier_totals, supplier_totals_read, suppliers, suppliers_read,
+    tax_code_index, tax_code_index_read, tax_codes, tax_codes_read, tax_tolerance, tax_tolerance_read, tokens,
+    tokens_read, vat_months, vat_months_read, AcceptedToken, Account, AccountTotals, AnomalyCounters, ApprovalRule,
+    AuditOpinion, AuditSample, CredibilityScore, CredibilityThresholds, CurrencyConfig, DisclosureField,
+    DisclosureGrant, DocumentKind, ExchangeRate, ExpenseCategory, FiscalPeriod, GoodsReceipt, Invoice, ItemLine,
+    ItemQuantity, JournalLine, LedgerLeaf, LedgerSpan, MatchResult, MatchTolerance, OpinionType, Payment, PaymentMethod,
+    PaymentStatus, PendingInvoice, PeriodReopen, PeriodStatus, PurchaseOrder, SplitRule, State, Supplier,
+    SupplierStatus, SupplierTotals, TaxBreakdown, TaxCode, VatTotals, AUDIT_FLAG_BUDGET_OVERRUN, AUDIT_FLAG_PO_MISMATCH,
+    AUDIT_FLAG_SPLIT_INVOICE, AUDIT_FLAG_TAX_MISMATCH, AUDIT_STATE_APPROVED, AUDIT_STATE_CORRECTION_REQUESTED,
+    AUDIT_STATE_FLAGGED, AUDIT_STATE_MUST_REVIEW, AUDIT_STATE_PENDING, AUDIT_STATE_SEALED, LEDGER_TREE, MAX_CREDIBILITY,
+};
 use crate::state::PREFIX_REVOKED_PERMITS;
+
+/// The block size used to pad the messages sent to SNIP-20 token contracts.
+const BLOCK_SIZE: usize = 256;
+
+/// The maximum number of invoices returned by one page of the `GetAll` query.
+const PAGE_SIZE: u32 = 20;
+
+/// The maximum length of an invoice number.
+const MAX_INVOICE_NUMBER_LENGTH: usize = 64;
+/// The maximum length of a client name, an auditor address or an audit state.
+const MAX_NAME_LENGTH: usize = 128;
+/// The maximum length of an invoice description.
+const MAX_DESCRIPTION_LENGTH: usize = 1024;
+/// The maximum length of an amount.
+const MAX_AMOUNT_LENGTH: usize = 40;
+/// The maximum number of journal lines of an invoice.
+const MAX_JOURNAL_LINES: usize = 50;
+/// The maximum length of an account code, a tax code or a cost centre.
+const MAX_CODE_LENGTH: usize = 32;
+
+/// How far below the threshold of an approval rule, in percent of the threshold, an amount counts as just below it.
+const NEAR_THRESHOLD_PERCENT: u64 = 10;
+
+/// The number of consecutive indexes whose open payables, or open approvals, are stored together.
+const OPEN_INDEX_PAGE: u32 = 100;
+
+/// The maximum number of the latest invoices of a supplier combined by the split-invoice detection.
+const SPLIT_SCAN_LIMIT: u32 = 50;
+
+/// The share of each first significant digit, from 1 to 9, expected by Benford's law, in thousandths.
+const BENFORD_PERMILLE: [u64; 9] = [301, 176, 125, 97, 79, 67, 58, 51, 46];
+
 /// Initializes the contract with a given count and sets the owner of the contract.
 ///
 /// # Arguments
@@ -29,6 +101,14 @@ pub fn instantiate(
     let state = State {
         count: msg.count,
         owner: info.sender.clone(),
+        invoice_count: 0,
+        account_count: 0,
+        period_count: 0,
+        tax_code_count: 0,
+        supplier_count: 0,
+        pending_count: 0,
+        category_count: 0,
+        last_sealed_at: None,
     };
 
     deps.api
@@ -55,8 +135,75 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
     match msg {
         ExecuteMsg::Increment {} => try_increment(deps, env),
         ExecuteMsg::Reset { count } => try_reset(deps, info, count),
-        ExecuteMsg::Add { invoice } => try_add(deps, info, invoice),
+        ExecuteMsg::Add { invoice } => try_add(deps, env, info, invoice),
         ExecuteMsg::UpdateAuditor { invoice_index, auditor } => try_update_auditor(deps, info, invoice_index, auditor),
+        ExecuteMsg::AmendInvoice { invoice_index, invoice } => {
+            try_amend_invoice(deps, env, info, invoice_index, invoice)
+        }
+        ExecuteMsg::VoidInvoice { invoice_index, reason } => try_void_invoice(deps, info, invoice_index, reason),
+        ExecuteMsg::SetCredibilityThresholds { review_below, seal_from } => {
+            try_set_credibility_thresholds(deps, info, review_below, seal_from)
+        }
+        ExecuteMsg::AuditInvoice { invoice_index, decision } => try_audit_invoice(deps, info, invoice_index, decision),
+        ExecuteMsg::SetAccount { account } => try_set_account(deps, info, account),
+        ExecuteMsg::SetTaxCode { tax_code } => try_set_tax_code(deps, info, tax_code),
+        ExecuteMsg::SetTaxTolerance { tolerance } => try_set_tax_tolerance(deps, info, tolerance),
+        ExecuteMsg::RecordPayment {
+            invoice_index,
+            amount,
+            date,
+            method,
+            reference_hash,
+        } => try_record_payment(deps, env, info, invoice_index, amount, date, method, reference_hash),
+        ExecuteMsg::RegisterToken {
+            contract,
+            code_hash,
+            currency,
+            decimals,
+        } => try_register_token(deps, env, info, contract, code_hash, currency, decimals),
+        ExecuteMsg::Receive { from, amount, msg, .. } => try_receive(deps, env, info, from, amount, msg),
+        ExecuteMsg::CreatePurchaseOrder { order } => try_create_purchase_order(deps, info, order),
+        ExecuteMsg::RecordGoodsReceipt { order, date, lines } => {
+            try_record_goods_receipt(deps, info, order, date, lines)
+        }
+        ExecuteMsg::SetMatchTolerance { tolerance } => try_set_match_tolerance(deps, info, tolerance),
+        ExecuteMsg::SetExpenseCategory { category } => try_set_expense_category(deps, info, category),
+        ExecuteMsg::SetBudget {
+            category,
+            period,
+            amount,
+        } => try_set_budget(deps, info, category, period, amount),
+        ExecuteMsg::SetSupplier { supplier } => try_set_supplier(deps, info, supplier),
+        ExecuteMsg::SetCurrencyConfig { base_currency, oracle } => {
+            try_set_currency_config(deps, info, base_currency, oracle)
+        }
+        ExecuteMsg::SetExchangeRate { from, to, date, rate } => try_set_exchange_rate(deps, info, from, to, date, rate),
+        ExecuteMsg::SetApprover { approver, active } => try_set_approver(deps, info, approver, active),
+        ExecuteMsg::SetApprovalRules { rules } => try_set_approval_rules(deps, info, rules),
+        ExecuteMsg::SetSplitRule { rule } => try_set_split_rule(deps, info, rule),
+        ExecuteMsg::SetDisclosureGrant { grantee, grant } => try_set_disclosure_grant(deps, info, grantee, grant),
+        ExecuteMsg::ApproveInvoice { pending_index } => try_approve_invoice(deps, env, info, pending_index),
+        ExecuteMsg::RejectInvoice { pending_index, reason } => try_reject_invoice(deps, info, pending_index, reason),
+        ExecuteMsg::DrawSample {
+            period,
+            size,
+            strata,
+            monetary_unit,
+        } => try_draw_sample(deps, env, info, period, size, strata, monetary_unit),
+        ExecuteMsg::AddFiscalPeriod {
+            name,
+            start,
+            end,
+            require_auditor,
+        } => try_add_fiscal_period(deps, info, name, start, end, require_auditor),
+        ExecuteMsg::ClosePeriod { name } => try_close_period(deps, env, info, name),
+        ExecuteMsg::ReopenPeriod { name, reason } => try_reopen_period(deps, env, info, name, reason),
+        ExecuteMsg::IssueAuditOpinion {
+            period,
+            opinion,
+            report_hash,
+            public,
+        } => try_issue_audit_opinion(deps, env, info, period, opinion, report_hash, public),
     }
 }
 
@@ -107,547 +254,7012 @@ pub fn try_reset(deps: DepsMut, info: MessageInfo, count: i32) -> StdResult<Resp
 
 /// Attempts to add a new invoice to the contract's state.
 ///
+/// The contract seals the `line_hash` over the accounting line and its journal lines, and stamps the invoice with
+/// the block height, the block time and the address that sealed it, so that back-dated entries and late booking can
+/// be told apart from the document `date`. An invoice above the threshold of an approval rule is validated and
+/// submitted for approval instead, and sealed by the `ApproveInvoice` message that completes its approvals.
+///
 /// # Arguments
 ///
 /// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
 /// * `info` - Information about the message sender and other metadata.
 /// * `invoice` - The invoice to be added to the contract.
 ///
 /// # Returns
 ///
 /// A `StdResult<Response>` indicating the success or failure of the add operation.
-pub fn try_add(deps: DepsMut, info: MessageInfo, invoice: Invoice) -> Result<Response, StdError> {
+pub fn try_add(deps: DepsMut, env: Env, info: MessageInfo, invoice: Invoice) -> Result<Response, StdError> {
     let sender_address = info.sender.clone();
     let state = config_read(deps.storage).load()?;
     if sender_address != state.owner {
-        return Err(StdError::generic_err("Only the owner can add Invoice"))?;
+        return Err(StdError::generic_err("Only the owner can add Invoice"));
     }
-    //let invoice_index: u8 = 0;
-    //let index = invoice_index.to_be_bytes();
-    let index_conf = b"0"; // Convert the integer to a byte slice
-    //let index = b"0"; // Convert the integer to a byte slice
-    config_invoice(deps.storage, index_conf).save(&invoice)?;
+
+    let required_approvals = required_approvals(deps.storage, &state, &invoice)?;
+    if required_approvals > 0 {
+        let pending_index = submit_for_approval(deps.storage, &env, info.sender, invoice, required_approvals, None)?;
+        deps.api.debug("invoice submitted for approval");
+        return Ok(Response::new()
+            .add_attribute("pending_index", pending_index.to_string())
+            .add_attribute("required_approvals", required_approvals.to_string()));
+    }
+
+    let (invoice_index, events) = seal_invoice(deps.storage, &env, info.sender, invoice, vec![])?;
     deps.api.debug("invoice added successfully");
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attribute("invoice_index", invoice_index.to_string())
+        .add_events(events))
 }
 
-/// Attempts to update the auditor field of an existing invoice.
+/// Submits an invoice, or the amendment of a sealed invoice, for approval and returns its pending index.
+fn submit_for_approval(
+    storage: &mut dyn Storage,
+    env: &Env,
+    submitted_by: Addr,
+    invoice: Invoice,
+    required_approvals: u32,
+    amends: Option<u32>,
+) -> StdResult<u32> {
+    let mut state = config_read(storage).load()?;
+    let pending_index = state.pending_count;
+    let pending = PendingInvoice {
+        invoice,
+        required_approvals,
+        approvals: vec![],
+        submitted_by,
+        submitted_at_height: env.block.height,
+        invoice_index: None,
+        rejected_by: None,
+        rejection_reason: None,
+        amends,
+    };
+    pending_invoices(storage).save(&pending_index.to_be_bytes(), &pending)?;
+    sync_open_approval(storage, pending_index, true)?;
+    state.pending_count += 1;
+    config(storage).save(&state)?;
+    Ok(pending_index)
+}
+
+/// Seals an invoice submitted by the owner, directly or once approved, and returns its index.
+///
+/// # Arguments
+///
+/// * `storage` - The contract's storage.
+/// * `env` - The environment of the sealing block.
+/// * `sealed_by` - The address that submitted the invoice.
+/// * `invoice` - The invoice to seal.
+/// * `approvals` - The approvers who approved the invoice, when an approval rule applied.
+///
+/// # Returns
+///
+/// A `StdResult` containing the index of the sealed invoice and the `budget_overrun` and `split_invoice` events it
+/// raised.
+fn seal_invoice(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sealed_by: Addr,
+    invoice: Invoice,
+    approvals: Vec<Addr>,
+) -> StdResult<(u32, Vec<Event>)> {
+    let mut state = config_read(storage).load()?;
+    validate_invoice(&invoice)?;
+    check_period_open(storage, &state, &invoice.date)?;
+    let invoice_index = state.invoice_count;
+    let mut invoice = invoice;
+    invoice.line_hash = compute_line_hash(&invoice)?;
+    invoice.version = 1;
+    invoice.supersedes = None;
+    invoice.credited_amount = Decimal::zero();
+    invoice.paid_amount = Decimal::zero();
+    invoice.payment_count = 0;
+    invoice.voided = false;
+    invoice.void_reason = None;
+    invoice.sealed_at_height = env.block.height;
+    invoice.sealed_at_time = env.block.time;
+    invoice.sealed_by = Some(sealed_by);
+    invoice.approvals = approvals;
+    check_supplier(storage, &invoice)?;
+    check_category(storage, &invoice)?;
+    convert_to_base(storage, &mut invoice)?;
+    check_accounts(storage, &invoice.lines)?;
+    invoice.tax_breakdown = tax_breakdown(storage, &invoice.lines)?;
+    invoice.near_threshold = near_threshold(storage, &invoice)?;
+    invoice.match_result = match_purchase_order(storage, invoice_index, &invoice)?;
+    invoice.audit_flags = audit_flags(storage, &invoice)?;
+    invoice.budget_period = match &invoice.category {
+        Some(_) => fiscal_period_of(storage, &state, &invoice.date)?,
+        None => None,
+    };
+    let overrun = post_category(storage, invoice_index, &invoice, false)?;
+    if overrun.is_some() {
+        invoice.audit_flags.push(AUDIT_FLAG_BUDGET_OVERRUN.to_string());
+    }
+    let cluster = split_cluster(storage, invoice_index, &invoice)?;
+    if !cluster.is_empty() {
+        invoice.audit_flags.push(AUDIT_FLAG_SPLIT_INVOICE.to_string());
+    }
+    invoice.audit_state = initial_audit_state(storage, &invoice)?;
+    invoice.field_commitment = seal_field_commitment(storage, env, invoice_index, &invoice)?;
+    invoice.ledger_leaf = Some(append_ledger_leaf(storage, &state, &invoice)?);
+    apply_reference(storage, &invoice, None)?;
+    config_invoice(storage, &invoice_key(invoice_index)).save(&invoice)?;
+    let split = flag_split_cluster(storage, &state, invoice_index, &invoice, &cluster)?;
+    post_lines(storage, &invoice, false)?;
+    post_vat(storage, &invoice, invoice_index, false)?;
+    if let Some(supplier) = &invoice.supplier {
+        let mut totals = supplier_totals_read(storage).may_load(supplier.as_bytes())?.unwrap_or_default();
+        let key = supplier_invoice_key(supplier, totals.invoice_count);
+        supplier_invoices(storage).save(&key, &invoice_index)?;
+        totals.invoice_count += 1;
+        supplier_totals(storage).save(supplier.as_bytes(), &totals)?;
+    }
+    post_supplier(storage, &invoice, false)?;
+    sync_open_payable(storage, invoice_index, &invoice)?;
+    index_month(storage, invoice_index, &invoice.date, false)?;
+    post_anomalies(storage, &invoice, false)?;
+    count_auditor(storage, &invoice.auditors, false)?;
+    if let Some(number) = &invoice.purchase_order {
+        purchase_orders(storage).update(number.as_bytes(), |order| -> StdResult<PurchaseOrder> {
+            let mut order = order.ok_or_else(|| StdError::generic_err(format!("Unknown purchase order: {}", number)))?;
+            order.invoices.push(invoice_index);
+            Ok(order)
+        })?;
+    }
+    state.invoice_count += 1;
+    state.last_sealed_at = Some(env.block.time);
+    config(storage).save(&state)?;
+    Ok((invoice_index, overrun.into_iter().chain(split).collect()))
+}
+
+/// Attempts to amend an existing invoice with a corrected version.
+///
+/// The current version is moved to the versions bucket, where it stays readable, and the corrected invoice becomes
+/// the latest version. Its `line_hash` is recomputed, its audit state is decided again like that of a new invoice,
+/// and it keeps the auditor, the document kind, the reference, the supplier, the purchase order and the expense
+/// category of the version it supersedes. Like a new invoice, it is stamped with the block and the address that sealed
+/// it. An amendment that needs more approvals than the current version recorded is submitted for approval instead,
+/// and applied by the `ApproveInvoice` message that completes its approvals.
 ///
 /// # Arguments
 ///
 /// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
 /// * `info` - Information about the message sender and other metadata.
-/// * `invoice_number` - The invoice number of the invoice to be updated.
-/// * `auditor_name` - The new auditor name to set.
+/// * `invoice_index` - The index of the invoice to amend.
+/// * `invoice` - The corrected invoice.
 ///
 /// # Returns
 ///
-/// A `StdResult<Response>` indicating the success or failure of the update operation.
-pub fn try_update_auditor(
+/// A `StdResult<Response>` indicating the success or failure of the amend operation.
+pub fn try_amend_invoice(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    invoice_index: u8,
-    auditor: Addr,
+    invoice_index: u32,
+    invoice: Invoice,
 ) -> Result<Response, StdError> {
     let sender_address = info.sender.clone();
     let state = config_read(deps.storage).load()?;
     if sender_address != state.owner {
-        return Err(StdError::generic_err("Only the owner can update the auditor"))?;
+        return Err(StdError::generic_err("Only the owner can amend Invoice"));
     }
 
-    //let index = invoice_index.to_be_bytes();
-    let index_conf = b"0"; // Convert the integer to a byte slice
-    config_invoice(deps.storage, index_conf).update(|mut invoice| -> Result<_, StdError> {
-
-        invoice.auditors = auditor.to_string();
-        Ok(invoice)
-    })?;
+    let previous = config_invoice_read(deps.storage, &invoice_key(invoice_index)).load()?;
+    if previous.voided {
+        return Err(StdError::generic_err("A voided Invoice cannot be amended"));
+    }
+    check_period_open(deps.storage, &state, &previous.date)?;
+    let mut pinned = invoice.clone();
+    pinned.supplier = previous.supplier.clone();
+    pinned.category = previous.category.clone();
+    let required_approvals = required_approvals(deps.storage, &state, &pinned)?;
+    if required_approvals as usize > previous.approvals.len() {
+        let pending_index =
+            submit_for_approval(deps.storage, &env, info.sender, invoice, required_approvals, Some(invoice_index))?;
+        deps.api.debug("invoice amendment submitted for approval");
+        return Ok(Response::new()
+            .add_attribute("invoice_index", invoice_index.to_string())
+            .add_attribute("pending_index", pending_index.to_string())
+            .add_attribute("required_approvals", required_approvals.to_string()));
+    }
 
-    deps.api.debug("auditor updated successfully");
-    Ok(Response::default())
+    let (version, events) = amend_invoice(deps.storage, &env, info.sender, invoice_index, invoice, previous.approvals)?;
+    deps.api.debug("invoice amended successfully");
+    Ok(Response::new()
+        .add_attribute("invoice_index", invoice_index.to_string())
+        .add_attribute("version", version.to_string())
+        .add_events(events))
 }
-/// Handles query messages to retrieve data from the contract's state.
+
+/// Amends a sealed invoice, directly or once the amendment is approved, and returns its new version.
 ///
 /// # Arguments
 ///
-/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
-/// * `env` - The environment object containing information about the current block, transaction, etc.
-/// * `msg` - Message containing the query operation to be executed (get count, get all credentials).
+/// * `storage` - The contract's storage.
+/// * `env` - The environment of the sealing block.
+/// * `sealed_by` - The address that submitted the amendment.
+/// * `invoice_index` - The index of the invoice to amend.
+/// * `invoice` - The corrected invoice.
+/// * `approvals` - The approvers who approved the amended amount, when an approval rule applies.
 ///
 /// # Returns
 ///
-/// A `StdResult<QueryResponse>` indicating the success or failure of the query operation.
-#[entry_point]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<QueryResponse> {
-    match msg {
-        QueryMsg::GetCount {} => to_binary(&query_count(deps)?),
-        QueryMsg::GetAll {
-            wallet,
-            permit,
-            index,
-        } => to_binary(&get_all(deps, env, wallet, permit, index)?),
+/// A `StdResult` containing the version of the amended invoice and the `budget_overrun` and `split_invoice` events it
+/// raised.
+fn amend_invoice(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sealed_by: Addr,
+    invoice_index: u32,
+    invoice: Invoice,
+    approvals: Vec<Addr>,
+) -> StdResult<(u32, Vec<Event>)> {
+    let state = config_read(storage).load()?;
+    let key = invoice_key(invoice_index);
+    let previous = config_invoice_read(storage, &key).load()?;
+    if previous.voided {
+        return Err(StdError::generic_err("A voided Invoice cannot be amended"));
+    }
+    invoice_versions(storage).save(&invoice_version_key(invoice_index, previous.version), &previous)?;
+
+    validate_invoice(&invoice)?;
+    check_period_open(storage, &state, &previous.date)?;
+    check_period_open(storage, &state, &invoice.date)?;
+    let mut amended = invoice;
+    amended.line_hash = compute_line_hash(&amended)?;
+    amended.auditors = previous.auditors.clone();
+    amended.version = previous.version + 1;
+    amended.supersedes = Some(previous.line_hash.clone());
+    amended.kind = previous.kind;
+    amended.reference = previous.reference;
+    amended.supplier = previous.supplier.clone();
+    amended.purchase_order = previous.purchase_order.clone();
+    amended.category = previous.category.clone();
+    amended.approvals = approvals;
+    amended.credited_amount = previous.credited_amount;
+    amended.paid_amount = previous.paid_amount;
+    amended.payment_count = previous.payment_count;
+    amended.voided = false;
+    amended.void_reason = None;
+    amended.sealed_at_height = env.block.height;
+    amended.sealed_at_time = env.block.time;
+    amended.sealed_by = Some(sealed_by);
+    if amended.kind == DocumentKind::Invoice
+        && parse_amount("total_amount", &amended.total_amount)? < amended.credited_amount
+    {
+        return Err(StdError::generic_err("The amended amount is below the amount already credited"));
+    }
+    if parse_amount("total_amount", &amended.total_amount)? < amended.credited_amount + amended.paid_amount {
+        return Err(StdError::generic_err("The amended amount is below the amount already credited and paid"));
+    }
+    check_supplier(storage, &amended)?;
+    check_category(storage, &amended)?;
+    convert_to_base(storage, &mut amended)?;
+    check_accounts(storage, &amended.lines)?;
+    amended.tax_breakdown = tax_breakdown(storage, &amended.lines)?;
+    amended.near_threshold = near_threshold(storage, &amended)?;
+    amended.match_result = match_purchase_order(storage, invoice_index, &amended)?;
+    amended.audit_flags = audit_flags(storage, &amended)?;
+    amended.budget_period = match &amended.category {
+        Some(_) => fiscal_period_of(storage, &state, &amended.date)?,
+        None => None,
+    };
+    post_category(storage, invoice_index, &previous, true)?;
+    let overrun = post_category(storage, invoice_index, &amended, false)?;
+    if overrun.is_some() {
+        amended.audit_flags.push(AUDIT_FLAG_BUDGET_OVERRUN.to_string());
+    }
+    let cluster = split_cluster(storage, invoice_index, &amended)?;
+    if !cluster.is_empty() {
+        amended.audit_flags.push(AUDIT_FLAG_SPLIT_INVOICE.to_string());
     }
+    amended.audit_state = initial_audit_state(storage, &amended)?;
+    amended.field_commitment = seal_field_commitment(storage, env, invoice_index, &amended)?;
+    amended.ledger_leaf = Some(append_ledger_leaf(storage, &state, &amended)?);
+    apply_reference(storage, &amended, Some(&previous))?;
+    post_lines(storage, &previous, true)?;
+    post_lines(storage, &amended, false)?;
+    post_vat(storage, &previous, invoice_index, true)?;
+    post_vat(storage, &amended, invoice_index, false)?;
+    post_supplier(storage, &previous, true)?;
+    post_supplier(storage, &amended, false)?;
+    sync_open_payable(storage, invoice_index, &amended)?;
+    index_month(storage, invoice_index, &previous.date, true)?;
+    index_month(storage, invoice_index, &amended.date, false)?;
+    post_anomalies(storage, &previous, true)?;
+    post_anomalies(storage, &amended, false)?;
+    config_invoice(storage, &key).save(&amended)?;
+    let split = flag_split_cluster(storage, &state, invoice_index, &amended, &cluster)?;
+    config(storage).update(|mut state| -> StdResult<_> {
+        state.last_sealed_at = Some(env.block.time);
+        Ok(state)
+    })?;
+    if let Some(number) = &amended.purchase_order {
+        rematch_purchase_order(storage, number)?;
+    }
+    Ok((amended.version, overrun.into_iter().chain(split).collect()))
 }
 
-/// Retrieves the current count value from the contract's state.
+/// Attempts to void an invoice or note.
+///
+/// The record is not deleted: it is marked as voided with the given reason so that it stays auditable. Voiding a
+/// credit note releases its amount on the original invoice, which cannot be voided before its credit notes.
 ///
 /// # Arguments
 ///
-/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `invoice_index` - The index of the invoice to void.
+/// * `reason` - Why the invoice is voided.
 ///
 /// # Returns
 ///
-/// A `StdResult<CountResponse>` containing the current count value.
-fn query_count(deps: Deps) -> StdResult<CountResponse> {
+/// A `StdResult<Response>` indicating the success or failure of the void operation.
+pub fn try_void_invoice(
+    deps: DepsMut,
+    info: MessageInfo,
+    invoice_index: u32,
+    reason: String,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
     let state = config_read(deps.storage).load()?;
-    Ok(CountResponse { count: state.count })
-}
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can void Invoice"));
+    }
+
+    let key = invoice_key(invoice_index);
+    let mut invoice = config_invoice_read(deps.storage, &key).load()?;
+    if invoice.voided {
+        return Err(StdError::generic_err("Invoice is already voided"));
+    }
+    if !invoice.paid_amount.is_zero() {
+        return Err(StdError::generic_err("A paid Invoice cannot be voided"));
+    }
+    if !invoice.credited_amount.is_zero() {
+        return Err(StdError::generic_err("An Invoice cannot be voided before its credit notes"));
+    }
+    check_period_open(deps.storage, &state, &invoice.date)?;
 
+    if let (DocumentKind::CreditNote, Some(reference)) = (invoice.kind, invoice.reference) {
+        let original_key = invoice_key(reference);
+        let mut original = config_invoice_read(deps.storage, &original_key).load()?;
+        original.credited_amount -= parse_amount("total_amount", &invoice.total_amount)?;
+        config_invoice(deps.storage, &original_key).save(&original)?;
+        sync_open_payable(deps.storage, reference, &original)?;
+    }
 
-/// Retrieves all invoices for a given wallet, validated by a permit.
+    post_lines(deps.storage, &invoice, true)?;
+    post_vat(deps.storage, &invoice, invoice_index, true)?;
+    post_supplier(deps.storage, &invoice, true)?;
+    post_category(deps.storage, invoice_index, &invoice, true)?;
+    post_anomalies(deps.storage, &invoice, true)?;
+    invoice.voided = true;
+    invoice.void_reason = Some(reason);
+    config_invoice(deps.storage, &key).save(&invoice)?;
+    sync_open_payable(deps.storage, invoice_index, &invoice)?;
+    if let Some(number) = &invoice.purchase_order {
+        rematch_purchase_order(deps.storage, number)?;
+    }
+
+    deps.api.debug("invoice voided successfully");
+    Ok(Response::new().add_attribute("invoice_index", invoice_index.to_string()))
+}
+
+/// Attempts to set the credibility thresholds used to decide the initial audit state of an invoice.
 ///
 /// # Arguments
 ///
-/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
-/// * `env` - The environment object containing information about the current block, transaction, etc.
-/// * `wallet` - The address of the wallet for which invoices are being retrieved.
-/// * `permit` - The permit used to validate access to the invoices.
-/// * `index` - The index at which to start retrieving invoices.
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `review_below` - Invoices scoring below this value must be approved by their auditor.
+/// * `seal_from` - Invoices scoring at least this value are sealed without review, if set.
 ///
 /// # Returns
 ///
-/// A `StdResult<InvoiceListResponse>` containing the list of invoices.
-fn get_all(
-    deps: Deps,
-    env: Env,
-    wallet: Addr,
-    permit: Permit,
-    _index: u8,
-) -> StdResult<InvoiceListResponse> {
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_set_credibility_thresholds(
+    deps: DepsMut,
+    info: MessageInfo,
+    review_below: CredibilityScore,
+    seal_from: Option<CredibilityScore>,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can set the credibility thresholds"));
+    }
+    validate_credibility(review_below)?;
+    if let Some(seal_from) = seal_from {
+        validate_credibility(seal_from)?;
+        if seal_from < review_below {
+            return Err(StdError::generic_err(
+                "The sealing threshold cannot be below the review threshold",
+            ));
+        }
+    }
 
-    let contract_address = env.contract.address;
-    let viewer = validate(
-        deps,
-        PREFIX_REVOKED_PERMITS,
-        &permit,
-        contract_address.to_string(),
-        None,
-    )?;
+    credibility_thresholds(deps.storage).save(&CredibilityThresholds { review_below, seal_from })?;
+    deps.api.debug("credibility thresholds updated successfully");
+    Ok(Response::default())
+}
 
+/// Attempts to record the decision of the auditor of an invoice.
+///
+/// Only the auditor assigned to the invoice can record a decision, and the owner can never audit its own invoices,
+/// so that invoices in the must-review state are only approved by an auditor. Invoices dated in a closed or audited
+/// fiscal period can no longer be audited.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `invoice_index` - The index of the audited invoice.
+/// * `decision` - The audit decision.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the audit operation.
+pub fn try_audit_invoice(
+    deps: DepsMut,
+    info: MessageInfo,
+    invoice_index: u32,
+    decision: AuditDecision,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
     let state = config_read(deps.storage).load()?;
+    let key = invoice_key(invoice_index);
+    let mut invoice = config_invoice_read(deps.storage, &key).load()?;
+    if sender_address == state.owner || sender_address != invoice.auditors {
+        return Err(StdError::generic_err("Only the auditor of the Invoice can audit it"));
+    }
+    if invoice.voided {
+        return Err(StdError::generic_err("A voided Invoice cannot be audited"));
+    }
+    check_period_open(deps.storage, &state, &invoice.date)?;
+    if decision == AuditDecision::Approve && invoice.match_result.as_ref().is_some_and(|result| !result.matched) {
+        return Err(StdError::generic_err("An Invoice that does not match its purchase order cannot be approved"));
+    }
 
+    invoice.audit_state = match decision {
+        AuditDecision::Approve => AUDIT_STATE_APPROVED,
+        AuditDecision::FlagIssue => AUDIT_STATE_FLAGGED,
+        AuditDecision::RequestCorrection => AUDIT_STATE_CORRECTION_REQUESTED,
+    }
+    .to_string();
+    config_invoice(deps.storage, &key).save(&invoice)?;
 
-    let index_conf = b"0"; // Convert the integer to a byte slice
-    let invoice = config_invoice_read(deps.storage, index_conf).load()?;
+    deps.api.debug("invoice audited successfully");
+    Ok(Response::new()
+        .add_attribute("invoice_index", invoice_index.to_string())
+        .add_attribute("audit_state", invoice.audit_state))
+}
 
-    let invoice_auditor = invoice.auditors.clone();
-    
-    if wallet != state.owner {
-        if viewer != invoice_auditor {
-            return Err(StdError::generic_err("Only the Owner or Auditor can add Invoice"));
-        }
+/// Attempts to create or update an account of the chart of accounts.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `account` - The account, identified by its code.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_set_account(deps: DepsMut, info: MessageInfo, account: Account) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let mut state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can manage the chart of accounts"));
     }
+    validate_text("code", &account.code, true, MAX_CODE_LENGTH)?;
+    validate_text("name", &account.name, true, MAX_NAME_LENGTH)?;
 
-    Ok(InvoiceListResponse {
-        vect_invoice: vec![invoice]
-    })
-}
+    if accounts_read(deps.storage).may_load(account.code.as_bytes())?.is_none() {
+        account_codes(deps.storage).save(&state.account_count.to_be_bytes(), &account.code)?;
+        state.account_count += 1;
+        config(deps.storage).save(&state)?;
+    }
+    accounts(deps.storage).save(account.code.as_bytes(), &account)?;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::*;
-    use cosmwasm_std::{from_binary, Coin, StdError, Uint128};
-    const PATH_PERMIT: &str = "../contract/tests/permit.json";
-    use serde::{Deserialize, Serialize};
-    use serde_json::from_str;
-    use std::fs::read_to_string;
+    deps.api.debug("account saved successfully");
+    Ok(Response::new().add_attribute("account", account.code))
+}
 
-    // Struct to deserialize data from a JSON file
-    #[derive(Serialize, Deserialize)]
-    struct GetAllData {
-        pub wallet: Addr,
-        pub index: u8,
-        pub permit: Permit,
+/// Attempts to create or update a tax code.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `tax_code` - The tax code, identified by its code.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_set_tax_code(deps: DepsMut, info: MessageInfo, tax_code: TaxCode) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let mut state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can manage the tax codes"));
+    }
+    validate_text("code", &tax_code.code, true, MAX_CODE_LENGTH)?;
+    validate_text("jurisdiction", &tax_code.jurisdiction, true, MAX_CODE_LENGTH)?;
+    if tax_code.rate > Decimal::one() {
+        return Err(StdError::generic_err(format!("Invalid tax rate: {}, expected at most 1", tax_code.rate)));
     }
 
-    // Struct to hold the deserialized JSON data
-    #[derive(Serialize, Deserialize)]
-    struct JsonData {
-        pub get_all: GetAllData,
+    if tax_codes_read(deps.storage).may_load(tax_code.code.as_bytes())?.is_none() {
+        tax_code_index(deps.storage).save(&state.tax_code_count.to_be_bytes(), &tax_code.code)?;
+        state.tax_code_count += 1;
+        config(deps.storage).save(&state)?;
     }
+    tax_codes(deps.storage).save(tax_code.code.as_bytes(), &tax_code)?;
 
-    /// Test function for retrieving all invoices.
-    #[test]
-    fn get_invoice_all() {
-        let mut deps = mock_dependencies();
-        // Load data from a permit JSON file on disk (created by Client.query_get_all)
-        let json_data_str = read_to_string(PATH_PERMIT).expect("Unable to read file");
-        let json_data: JsonData = from_str(&json_data_str).expect("Failed to deserialize JSON data");
-        let wallet = &json_data.get_all.wallet;
-        let index = json_data.get_all.index;
-        let permit = json_data.get_all.permit;
-        println!("wallet: {:?}", &wallet);
-        println!("index: {:?}", index);
-        println!("permit: {:?}", permit);
-        // Set up the mock environment and contract address
-        let info = mock_info(
-            &wallet.clone().into_string(),
-            &[Coin {
-                denom: "earth".to_string(),
-                amount: Uint128::new(1000),
-            }],
-        );
-        let mut env = mock_env();
-        let contract_address = permit.clone().params.allowed_tokens[0].clone();
-        env.contract.address = Addr::unchecked(contract_address);
+    deps.api.debug("tax code saved successfully");
+    Ok(Response::new().add_attribute("tax_code", tax_code.code))
+}
 
-        // Instantiate the contract
-        let instantiate_msg = InstantiateMsg { count: 0 };
-        let _res = instantiate(
-            deps.as_mut(),
-            env.clone(),
-            info.clone(),
-            instantiate_msg
-        ).unwrap();
+/// Attempts to set the rounding tolerance of the check between `tax_amount` and the taxable journal lines.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `tolerance` - The largest accepted difference, in currency units.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_set_tax_tolerance(deps: DepsMut, info: MessageInfo, tolerance: Decimal) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can set the tax tolerance"));
+    }
 
-        // Define an invoice to add
-        let invoice = Invoice {
-            invoice_number: "INV-001".to_string(),
-            date: "2025-02-26".to_string(),
-            client_name: "Client A".to_string(),
-            description: "Service".to_string(),
-            total_amount: "1000".to_string(),
-            tax_amount: "100".to_string(),
-            currency: "USD".to_string(),
-            doc_hash: "hash123".to_string(),
-            line_hash: "linehash123".to_string(),
-            auditors: "Auditor A".to_string(),
-            credibility: "High".to_string(),
-            audit_state: "Pending".to_string(),
-        };
+    tax_tolerance(deps.storage).save(&tolerance)?;
+    deps.api.debug("tax tolerance updated successfully");
+    Ok(Response::default())
+}
 
-        // Call the try_add function
-        let execute_msg = ExecuteMsg::Add { invoice: invoice.clone() };
-        let _res = execute(
+/// Attempts to record a full or partial payment of an invoice or debit note.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `info` - Information about the message sender and other metadata.
+/// * `invoice_index` - The index of the paid invoice.
+/// * `amount` - The amount paid, in the currency of the invoice.
+/// * `date` - The day of the payment, as `YYYY-MM-DD`.
+/// * `method` - How the payment was made.
+/// * `reference_hash` - The SHA-256 hash of the bank or transaction reference of the payment.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+#[allow(clippy::too_many_arguments)]
+pub fn try_record_payment(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    invoice_index: u32,
+    amount: Decimal,
+    date: String,
+    method: PaymentMethod,
+    reference_hash: String,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can record payments"));
+    }
+    validate_hash("reference_hash", &reference_hash)?;
+
+    let payment = Payment {
+        amount,
+        date,
+        method,
+        reference_hash,
+        recorded_by: info.sender,
+        recorded_at_height: env.block.height,
+        token: None,
+    };
+    let invoice = record_payment(deps.storage, invoice_index, payment)?;
+
+    deps.api.debug("payment recorded successfully");
+    Ok(Response::new()
+        .add_attribute("invoice_index", invoice_index.to_string())
+        .add_attribute("outstanding", outstanding(&invoice)?.to_string()))
+}
+
+/// Records a payment of an invoice and returns the updated invoice.
+///
+/// Credit notes and voided invoices cannot be paid, and a payment cannot exceed the outstanding balance. The payment
+/// cannot be dated before the invoice, nor in a closed or audited fiscal period, and an invoice dated in such a period
+/// cannot be paid, since its settlement is part of the closed books.
+fn record_payment(storage: &mut dyn Storage, invoice_index: u32, payment: Payment) -> StdResult<Invoice> {
+    let key = invoice_key(invoice_index);
+    let mut invoice = config_invoice_read(storage, &key).load()?;
+    if invoice.voided {
+        return Err(StdError::generic_err("A voided Invoice cannot be paid"));
+    }
+    if invoice.kind == DocumentKind::CreditNote {
+        return Err(StdError::generic_err("A credit note cannot be paid"));
+    }
+    parse_date(&payment.date)?;
+    if payment.date < invoice.date {
+        return Err(StdError::generic_err(format!(
+            "Payment date {} is before the invoice date {}",
+            payment.date, invoice.date
+        )));
+    }
+    let state = config_read(storage).load()?;
+    if let Some(name) = closed_period_of(storage, &state, &payment.date)? {
+        return Err(StdError::generic_err(format!(
+            "Payment date {} falls in the closed fiscal period {}",
+            payment.date, name
+        )));
+    }
+    check_period_open(storage, &state, &invoice.date)?;
+    if payment.amount.is_zero() {
+        return Err(StdError::generic_err("A payment cannot be zero"));
+    }
+    let balance = outstanding(&invoice)?;
+    if payment.amount > balance {
+        return Err(StdError::generic_err(format!(
+            "Payment of {} exceeds the outstanding balance {}",
+            payment.amount, balance
+        )));
+    }
+
+    invoice.paid_amount += payment.amount;
+    payments(storage).save(&payment_key(invoice_index, invoice.payment_count), &payment)?;
+    invoice.payment_count += 1;
+    config_invoice(storage, &key).save(&invoice)?;
+    sync_open_payable(storage, invoice_index, &invoice)?;
+    Ok(invoice)
+}
+
+/// Returns the total amount of an invoice less its credited and paid amounts, in the currency of the invoice.
+fn outstanding(invoice: &Invoice) -> StdResult<Decimal> {
+    let total_amount = parse_amount("total_amount", &invoice.total_amount)?;
+    let settled = invoice.credited_amount + invoice.paid_amount;
+    Ok(if total_amount > settled { total_amount - settled } else { Decimal::zero() })
+}
+
+/// Derives the settlement status of an invoice on the given day.
+fn payment_status(invoice: &Invoice, today: &str) -> StdResult<PaymentStatus> {
+    let status = if outstanding(invoice)?.is_zero() {
+        PaymentStatus::Paid
+    } else if invoice.due_date.as_ref().unwrap_or(&invoice.date).as_str() < today {
+        PaymentStatus::Overdue
+    } else if invoice.paid_amount.is_zero() {
+        PaymentStatus::Unpaid
+    } else {
+        PaymentStatus::PartiallyPaid
+    };
+    Ok(status)
+}
+
+/// Keeps an invoice in the open payables while it is an invoice or debit note, not voided, with a balance.
+fn sync_open_payable(storage: &mut dyn Storage, invoice_index: u32, invoice: &Invoice) -> StdResult<()> {
+    let open = !invoice.voided && invoice.kind != DocumentKind::CreditNote && !outstanding(invoice)?.is_zero();
+    let key = (invoice_index / OPEN_INDEX_PAGE).to_be_bytes();
+    let mut indexes = open_payables_read(storage).may_load(&key)?.unwrap_or_default();
+    match (open, indexes.binary_search(&invoice_index)) {
+        (true, Err(position)) => indexes.insert(position, invoice_index),
+        (false, Ok(position)) => {
+            indexes.remove(position);
+        }
+        _ => return Ok(()),
+    }
+    open_payables(storage).save(&key, &indexes)
+}
+
+/// Attempts to accept a SNIP-20 token to pay invoices.
+///
+/// The response registers the contract with the token so that the token calls `Receive` on transfers.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `info` - Information about the message sender and other metadata.
+/// * `contract` - The address of the token contract.
+/// * `code_hash` - The code hash of the token contract.
+/// * `currency` - The ISO-4217 code of the currency the token is pegged to.
+/// * `decimals` - The number of decimals of the token.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_register_token(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: Addr,
+    code_hash: String,
+    currency: String,
+    decimals: u8,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can register tokens"));
+    }
+    let contract = deps.api.addr_validate(contract.as_str())?;
+    validate_text("code_hash", &code_hash, true, MAX_NAME_LENGTH)?;
+    validate_currency(&currency)?;
+
+    let token = AcceptedToken {
+        contract,
+        code_hash,
+        currency,
+        decimals,
+    };
+    tokens(deps.storage).save(token.contract.as_bytes(), &token)?;
+    let register = register_receive_msg(
+        env.contract.code_hash,
+        None,
+        BLOCK_SIZE,
+        token.code_hash,
+        token.contract.to_string(),
+    )?;
+
+    deps.api.debug("token registered successfully");
+    Ok(Response::new().add_message(register).add_attribute("token", token.contract))
+}
+
+/// Handles the SNIP-20 `Receive` call of a token contract.
+///
+/// Only the owner can pay invoices with tokens. The tokens are forwarded to the registered address of the supplier of
+/// the invoice, and the payment is recorded with the hash of the token, block height and invoice index as reference.
+/// Any error reverts the transfer, so the tokens never stay with the contract.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `info` - Information about the message sender, which is the token contract.
+/// * `from` - The address the tokens were sent from.
+/// * `amount` - The amount of tokens received, in the smallest unit of the token.
+/// * `msg` - The `ReceiveMsg` embedded in the transfer.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    from: Addr,
+    amount: Uint128,
+    msg: Option<Binary>,
+) -> Result<Response, StdError> {
+    let token = tokens_read(deps.storage)
+        .may_load(info.sender.as_bytes())?
+        .ok_or_else(|| StdError::generic_err(format!("Unsupported token: {}", info.sender)))?;
+    let state = config_read(deps.storage).load()?;
+    if from != state.owner {
+        return Err(StdError::generic_err("Only the owner can pay invoices with tokens"));
+    }
+    let msg = msg.ok_or_else(|| StdError::generic_err("Missing receive message"))?;
+    let invoice_index = match from_binary(&msg)? {
+        ReceiveMsg::PayInvoice { invoice_index } => invoice_index,
+    };
+
+    let invoice = config_invoice_read(deps.storage, &invoice_key(invoice_index)).load()?;
+    if invoice.currency != token.currency {
+        return Err(StdError::generic_err(format!(
+            "Invoice {} is in {}, not in {}",
+            invoice_index, invoice.currency, token.currency
+        )));
+    }
+    let supplier = invoice
+        .supplier
+        .as_ref()
+        .ok_or_else(|| StdError::generic_err(format!("Invoice {} has no supplier", invoice_index)))?;
+    let recipient = suppliers_read(deps.storage)
+        .load(supplier.as_bytes())?
+        .address
+        .ok_or_else(|| StdError::generic_err(format!("Supplier {} has no registered address", supplier)))?;
+
+    let reference = format!("{}:{}:{}", token.contract, env.block.height, invoice_index);
+    let payment = Payment {
+        amount: Decimal::from_atomics(amount, token.decimals.into())
+            .map_err(|_| StdError::generic_err("The received amount is too large"))?,
+        date: block_date(&env),
+        method: PaymentMethod::Token,
+        reference_hash: Sha256::digest(reference.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect(),
+        recorded_by: from,
+        recorded_at_height: env.block.height,
+        token: Some(token.contract.clone()),
+    };
+    let invoice = record_payment(deps.storage, invoice_index, payment)?;
+    let forward = transfer_msg(
+        recipient.to_string(),
+        amount,
+        Some(format!("Invoice {}", invoice.invoice_number)),
+        None,
+        BLOCK_SIZE,
+        token.code_hash,
+        token.contract.to_string(),
+    )?;
+
+    deps.api.debug("token payment forwarded successfully");
+    Ok(Response::new()
+        .add_message(forward)
+        .add_attribute("invoice_index", invoice_index.to_string())
+        .add_attribute("outstanding", outstanding(&invoice)?.to_string()))
+}
+
+/// Attempts to create a purchase order.
+///
+/// The quantities received and the invoices of the order are kept by the contract, whatever the order provided.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `order` - The purchase order, identified by its number.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_create_purchase_order(
+    deps: DepsMut,
+    info: MessageInfo,
+    order: PurchaseOrder,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can create purchase orders"));
+    }
+    validate_text("number", &order.number, true, MAX_CODE_LENGTH)?;
+    validate_currency(&order.currency)?;
+    parse_date(&order.date)?;
+    if let Some(id) = &order.supplier {
+        if suppliers_read(deps.storage).may_load(id.as_bytes())?.is_none() {
+            return Err(StdError::generic_err(format!("Unknown supplier: {}", id)));
+        }
+    }
+    if order.lines.is_empty() {
+        return Err(StdError::generic_err("A purchase order needs at least one line"));
+    }
+    for (position, line) in order.lines.iter().enumerate() {
+        validate_item(line)?;
+        if order.lines[..position].iter().any(|earlier| earlier.item == line.item) {
+            return Err(StdError::generic_err(format!("Item {} is ordered twice", line.item)));
+        }
+    }
+    if purchase_orders_read(deps.storage).may_load(order.number.as_bytes())?.is_some() {
+        return Err(StdError::generic_err(format!("Purchase order {} already exists", order.number)));
+    }
+
+    let order = PurchaseOrder {
+        received: vec![],
+        receipt_count: 0,
+        invoices: vec![],
+        ..order
+    };
+    purchase_orders(deps.storage).save(order.number.as_bytes(), &order)?;
+
+    deps.api.debug("purchase order created successfully");
+    Ok(Response::new().add_attribute("purchase_order", order.number))
+}
+
+/// Attempts to record goods received against a purchase order.
+///
+/// The invoices of the order are matched again, since the goods received may now cover them.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `number` - The number of the purchase order.
+/// * `date` - The day the goods were received, as `YYYY-MM-DD`.
+/// * `lines` - The quantities received, of items of the order.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_record_goods_receipt(
+    deps: DepsMut,
+    info: MessageInfo,
+    number: String,
+    date: String,
+    lines: Vec<ItemQuantity>,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can record goods receipts"));
+    }
+    parse_date(&date)?;
+    let mut order = purchase_orders_read(deps.storage)
+        .may_load(number.as_bytes())?
+        .ok_or_else(|| StdError::generic_err(format!("Unknown purchase order: {}", number)))?;
+    if lines.is_empty() {
+        return Err(StdError::generic_err("A goods receipt needs at least one line"));
+    }
+    for line in &lines {
+        if !order.lines.iter().any(|ordered| ordered.item == line.item) {
+            return Err(StdError::generic_err(format!("Item {} is not on purchase order {}", line.item, number)));
+        }
+        if line.quantity.is_zero() {
+            return Err(StdError::generic_err(format!("Invalid quantity of item {}", line.item)));
+        }
+        match order.received.iter_mut().find(|received| received.item == line.item) {
+            Some(received) => received.quantity += line.quantity,
+            None => order.received.push(line.clone()),
+        }
+    }
+
+    let receipt = GoodsReceipt {
+        date,
+        lines,
+        recorded_by: info.sender,
+    };
+    goods_receipts(deps.storage).save(&goods_receipt_key(&number, order.receipt_count), &receipt)?;
+    order.receipt_count += 1;
+    purchase_orders(deps.storage).save(number.as_bytes(), &order)?;
+    rematch_purchase_order(deps.storage, &number)?;
+
+    deps.api.debug("goods receipt recorded successfully");
+    Ok(Response::new()
+        .add_attribute("purchase_order", number)
+        .add_attribute("receipt", (order.receipt_count - 1).to_string()))
+}
+
+/// Attempts to set the relative tolerances of the three-way match.
+///
+/// Matching invoices already sealed are not matched again until their purchase order receives goods.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `tolerance` - The tolerances on quantities and amounts, such as `0.02` for 2%.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_set_match_tolerance(
+    deps: DepsMut,
+    info: MessageInfo,
+    tolerance: MatchTolerance,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can set the match tolerance"));
+    }
+    if tolerance.quantity > Decimal::one() || tolerance.amount > Decimal::one() {
+        return Err(StdError::generic_err("Invalid match tolerance, expected at most 1"));
+    }
+
+    match_tolerance(deps.storage).save(&tolerance)?;
+    deps.api.debug("match tolerance updated successfully");
+    Ok(Response::default())
+}
+
+/// Attempts to create or update an expense category.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `category` - The expense category, identified by its code.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_set_expense_category(
+    deps: DepsMut,
+    info: MessageInfo,
+    category: ExpenseCategory,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let mut state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can manage the expense categories"));
+    }
+    validate_text("code", &category.code, true, MAX_CODE_LENGTH)?;
+    validate_text("name", &category.name, true, MAX_NAME_LENGTH)?;
+
+    if expense_categories_read(deps.storage).may_load(category.code.as_bytes())?.is_none() {
+        expense_category_codes(deps.storage).save(&state.category_count.to_be_bytes(), &category.code)?;
+        state.category_count += 1;
+        config(deps.storage).save(&state)?;
+    }
+    expense_categories(deps.storage).save(category.code.as_bytes(), &category)?;
+
+    deps.api.debug("expense category saved successfully");
+    Ok(Response::new().add_attribute("category", category.code))
+}
+
+/// Attempts to set the budget of an expense category over a fiscal period.
+///
+/// The spend already recorded against the category over the period is kept.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `category` - The code of the expense category.
+/// * `period` - The name of the fiscal period.
+/// * `amount` - The budget, in the base currency once set.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_set_budget(
+    deps: DepsMut,
+    info: MessageInfo,
+    category: String,
+    period: String,
+    amount: Decimal,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can set the budgets"));
+    }
+    if expense_categories_read(deps.storage).may_load(category.as_bytes())?.is_none() {
+        return Err(StdError::generic_err(format!("Unknown expense category: {}", category)));
+    }
+    if fiscal_periods_read(deps.storage).may_load(period.as_bytes())?.is_none() {
+        return Err(StdError::generic_err(format!("Unknown fiscal period: {}", period)));
+    }
+
+    let key = category_budget_key(&category, &period);
+    let mut budget = category_budgets_read(deps.storage).may_load(&key)?.unwrap_or_default();
+    budget.budget = Some(amount);
+    category_budgets(deps.storage).save(&key, &budget)?;
+
+    deps.api.debug("budget saved successfully");
+    Ok(Response::new().add_attribute("category", category).add_attribute("period", period))
+}
+
+/// Attempts to create or update a supplier of the supplier registry.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `supplier` - The supplier, identified by its id.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_set_supplier(deps: DepsMut, info: MessageInfo, supplier: Supplier) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let mut state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can manage the supplier registry"));
+    }
+    validate_text("id", &supplier.id, true, MAX_CODE_LENGTH)?;
+    validate_text("legal_name", &supplier.legal_name, true, MAX_NAME_LENGTH)?;
+    if let Some(vat_number) = &supplier.vat_number {
+        validate_text("vat_number", vat_number, true, MAX_CODE_LENGTH)?;
+    }
+    validate_country(&supplier.country)?;
+    if let Some(iban_hash) = &supplier.iban_hash {
+        validate_hash("iban_hash", iban_hash)?;
+    }
+    if let Some(address) = &supplier.address {
+        deps.api.addr_validate(address.as_str())?;
+    }
+
+    if suppliers_read(deps.storage).may_load(supplier.id.as_bytes())?.is_none() {
+        supplier_ids(deps.storage).save(&state.supplier_count.to_be_bytes(), &supplier.id)?;
+        state.supplier_count += 1;
+        config(deps.storage).save(&state)?;
+    }
+    suppliers(deps.storage).save(supplier.id.as_bytes(), &supplier)?;
+
+    deps.api.debug("supplier saved successfully");
+    Ok(Response::new().add_attribute("supplier", supplier.id))
+}
+
+/// Attempts to set the base currency of the ledger and the exchange-rate oracle.
+///
+/// The base currency cannot change once set, since the base-currency equivalents sealed with the invoices and the
+/// running totals of the ledger are expressed in it. For the same reason it must be set before the first invoice is
+/// sealed, as earlier invoices have no base-currency equivalents. The oracle can be replaced or removed.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `base_currency` - The ISO-4217 code of the base currency.
+/// * `oracle` - The address allowed to record exchange rates besides the owner, if any.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_set_currency_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    base_currency: String,
+    oracle: Option<Addr>,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can set the currency configuration"));
+    }
+    validate_currency(&base_currency)?;
+    match currency_config_read(deps.storage).may_load()? {
+        Some(current) if current.base_currency != base_currency => {
+            return Err(StdError::generic_err("The base currency cannot change once set"));
+        }
+        None if state.invoice_count > 0 => {
+            return Err(StdError::generic_err("The base currency must be set before the first invoice"));
+        }
+        _ => {}
+    }
+
+    currency_config(deps.storage).save(&CurrencyConfig { base_currency, oracle })?;
+    deps.api.debug("currency configuration updated successfully");
+    Ok(Response::default())
+}
+
+/// Attempts to record an exchange rate.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `from` - The currency converted from.
+/// * `to` - The currency converted to.
+/// * `date` - The day the rate applies to, as `YYYY-MM-DD`.
+/// * `rate` - The amount of `to` for one unit of `from`.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_set_exchange_rate(
+    deps: DepsMut,
+    info: MessageInfo,
+    from: String,
+    to: String,
+    date: String,
+    rate: Decimal,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender;
+    let state = config_read(deps.storage).load()?;
+    let oracle = currency_config_read(deps.storage).may_load()?.and_then(|config| config.oracle);
+    if sender_address != state.owner && Some(&sender_address) != oracle.as_ref() {
+        return Err(StdError::generic_err("Only the owner or the oracle can record exchange rates"));
+    }
+    validate_currency(&from)?;
+    validate_currency(&to)?;
+    if from == to {
+        return Err(StdError::generic_err("An exchange rate needs two different currencies"));
+    }
+    parse_date(&date)?;
+    if rate.is_zero() {
+        return Err(StdError::generic_err("An exchange rate cannot be zero"));
+    }
+
+    let exchange_rate = ExchangeRate {
+        from,
+        to,
+        date,
+        rate,
+        recorded_by: sender_address,
+    };
+    let key = exchange_rate_key(&exchange_rate.from, &exchange_rate.to, &exchange_rate.date);
+    exchange_rates(deps.storage).save(&key, &exchange_rate)?;
+
+    deps.api.debug("exchange rate recorded successfully");
+    Ok(Response::default())
+}
+
+/// Attempts to grant or revoke the approver role.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `approver` - The address of the approver.
+/// * `active` - Whether the address holds the approver role.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_set_approver(deps: DepsMut, info: MessageInfo, approver: Addr, active: bool) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can manage the approvers"));
+    }
+
+    if active {
+        approvers(deps.storage).save(approver.as_bytes(), &true)?;
+    } else {
+        approvers(deps.storage).remove(approver.as_bytes());
+    }
+
+    deps.api.debug("approver updated successfully");
+    Ok(Response::new().add_attribute("approver", approver))
+}
+
+/// Attempts to replace the approval rules.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `rules` - The approval rules. The rule with the most approvals among those whose threshold is exceeded applies.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_set_approval_rules(
+    deps: DepsMut,
+    info: MessageInfo,
+    rules: Vec<ApprovalRule>,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can set the approval rules"));
+    }
+    if rules.iter().any(|rule| rule.approvals == 0) {
+        return Err(StdError::generic_err("An approval rule needs at least one approval"));
+    }
+
+    approval_rules(deps.storage).save(&rules)?;
+    deps.api.debug("approval rules updated successfully");
+    Ok(Response::default())
+}
+
+/// Attempts to grant an address the disclosure of some invoice fields, or to revoke its grant.
+///
+/// A new grant replaces the previous grant of the address.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `grantee` - The address the fields are disclosed to.
+/// * `grant` - The fields revealed and the invoices they are revealed for, or `None` to revoke the grant.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_set_disclosure_grant(
+    deps: DepsMut,
+    info: MessageInfo,
+    grantee: Addr,
+    grant: Option<DisclosureGrant>,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can manage the disclosure grants"));
+    }
+
+    match grant {
+        Some(grant) => {
+            if let Some(index) = grant.invoices.iter().flatten().find(|index| **index >= state.invoice_count) {
+                return Err(StdError::generic_err(format!("Unknown invoice: {}", index)));
+            }
+            disclosure_grants(deps.storage).save(grantee.as_bytes(), &grant)?;
+        }
+        None => disclosure_grants(deps.storage).remove(grantee.as_bytes()),
+    }
+    deps.api.debug("disclosure grant updated successfully");
+    Ok(Response::new().add_attribute("grantee", grantee))
+}
+
+/// Attempts to set or remove the split-invoice detection rule.
+///
+/// The rule applies to the invoices sealed from then on; the invoices already sealed are not flagged again.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `rule` - The window and threshold of the detection, or `None` to stop it.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_set_split_rule(deps: DepsMut, info: MessageInfo, rule: Option<SplitRule>) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can set the split rule"));
+    }
+
+    match rule {
+        Some(rule) => {
+            if rule.window_days == 0 || rule.threshold.is_zero() {
+                return Err(StdError::generic_err("A split rule needs a window and a threshold above zero"));
+            }
+            split_rule(deps.storage).save(&rule)?;
+        }
+        None => split_rule(deps.storage).remove(),
+    }
+    deps.api.debug("split rule updated successfully");
+    Ok(Response::default())
+}
+
+/// Attempts to approve an invoice waiting for approval.
+///
+/// Each approver approves an invoice once, and the address that submitted it cannot approve it. Only the approvals of
+/// addresses that are still approvers count, and the approval that completes the approvals required seals the invoice,
+/// or applies the amendment, stamped with the current block and the submitting address.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `info` - Information about the message sender and other metadata.
+/// * `pending_index` - The pending index of the invoice.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_approve_invoice(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    pending_index: u32,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender;
+    if approvers_read(deps.storage).may_load(sender_address.as_bytes())?.is_none() {
+        return Err(StdError::generic_err("Only an Approver can approve an Invoice"));
+    }
+    let key = pending_index.to_be_bytes();
+    let mut pending = pending_invoices_read(deps.storage)
+        .may_load(&key)?
+        .ok_or_else(|| StdError::generic_err(format!("Unknown pending Invoice: {}", pending_index)))?;
+    if pending.invoice_index.is_some() {
+        return Err(StdError::generic_err("Invoice is already sealed"));
+    }
+    if pending.rejected_by.is_some() {
+        return Err(StdError::generic_err("Invoice is already rejected"));
+    }
+    if sender_address == pending.submitted_by {
+        return Err(StdError::generic_err("An Invoice cannot be approved by the address that submitted it"));
+    }
+    if pending.approvals.contains(&sender_address) {
+        return Err(StdError::generic_err("Invoice is already approved by this Approver"));
+    }
+
+    pending.approvals.push(sender_address);
+    let mut active = vec![];
+    for approver in &pending.approvals {
+        if approvers_read(deps.storage).may_load(approver.as_bytes())?.is_some() {
+            active.push(approver.clone());
+        }
+    }
+    let mut response = Response::new()
+        .add_attribute("pending_index", pending_index.to_string())
+        .add_attribute("approvals", active.len().to_string());
+    if active.len() as u32 >= pending.required_approvals {
+        let submitted_by = pending.submitted_by.clone();
+        let (invoice_index, events) = match pending.amends {
+            Some(invoice_index) => {
+                let (version, events) =
+                    amend_invoice(deps.storage, &env, submitted_by, invoice_index, pending.invoice.clone(), active)?;
+                response = response.add_attribute("version", version.to_string());
+                (invoice_index, events)
+            }
+            None => seal_invoice(deps.storage, &env, submitted_by, pending.invoice.clone(), active)?,
+        };
+        pending.invoice_index = Some(invoice_index);
+        sync_open_approval(deps.storage, pending_index, false)?;
+        response = response.add_attribute("invoice_index", invoice_index.to_string()).add_events(events);
+    }
+    pending_invoices(deps.storage).save(&key, &pending)?;
+
+    deps.api.debug("invoice approved successfully");
+    Ok(response)
+}
+
+/// Attempts to reject an invoice waiting for approval, or to withdraw it.
+///
+/// An approver other than the address that submitted the invoice rejects it, and the owner withdraws it. The invoice
+/// is never sealed and leaves the invoices waiting for approval, but stays readable with who rejected it and why.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `pending_index` - The pending index of the invoice.
+/// * `reason` - Why the invoice is rejected or withdrawn.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_reject_invoice(
+    deps: DepsMut,
+    info: MessageInfo,
+    pending_index: u32,
+    reason: String,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender;
+    let state = config_read(deps.storage).load()?;
+    let key = pending_index.to_be_bytes();
+    let mut pending = pending_invoices_read(deps.storage)
+        .may_load(&key)?
+        .ok_or_else(|| StdError::generic_err(format!("Unknown pending Invoice: {}", pending_index)))?;
+    let is_approver = approvers_read(deps.storage).may_load(sender_address.as_bytes())?.is_some();
+    if sender_address != state.owner && (!is_approver || sender_address == pending.submitted_by) {
+        return Err(StdError::generic_err("Only an Approver or the owner can reject an Invoice"));
+    }
+    if pending.invoice_index.is_some() {
+        return Err(StdError::generic_err("Invoice is already sealed"));
+    }
+    if pending.rejected_by.is_some() {
+        return Err(StdError::generic_err("Invoice is already rejected"));
+    }
+    validate_text("reason", &reason, true, MAX_DESCRIPTION_LENGTH)?;
+
+    pending.rejected_by = Some(sender_address);
+    pending.rejection_reason = Some(reason);
+    pending_invoices(deps.storage).save(&key, &pending)?;
+    sync_open_approval(deps.storage, pending_index, false)?;
+
+    deps.api.debug("invoice rejected successfully");
+    Ok(Response::new().add_attribute("pending_index", pending_index.to_string()))
+}
+
+/// Keeps a pending index in the open approvals while its invoice waits for approval.
+fn sync_open_approval(storage: &mut dyn Storage, pending_index: u32, open: bool) -> StdResult<()> {
+    let key = (pending_index / OPEN_INDEX_PAGE).to_be_bytes();
+    let mut indexes = open_approvals_read(storage).may_load(&key)?.unwrap_or_default();
+    match (open, indexes.binary_search(&pending_index)) {
+        (true, Err(position)) => indexes.insert(position, pending_index),
+        (false, Ok(position)) => {
+            indexes.remove(position);
+        }
+        _ => return Ok(()),
+    }
+    open_approvals(storage).save(&key, &indexes)
+}
+
+/// Attempts to draw a sample of the invoices dated in a fiscal period, for an auditor engaged on the period.
+///
+/// The population is made of the invoices and debit notes dated in the period that are not voided. Each stratum is
+/// sampled without replacement, uniformly or with a probability proportional to the amount in the base currency
+/// once set. The draw is seeded from `env.block.random`, and a period can only be sampled once so that a sample cannot
+/// be redrawn until it suits the auditee.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `info` - Information about the message sender and other metadata.
+/// * `period` - The name of the fiscal period.
+/// * `size` - The number of invoices to draw from each stratum.
+/// * `strata` - The ascending amount boundaries splitting the population into strata.
+/// * `monetary_unit` - Whether to draw invoices with a probability proportional to their amount.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+#[allow(clippy::too_many_arguments)]
+pub fn try_draw_sample(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    period: String,
+    size: u32,
+    strata: Vec<Decimal>,
+    monetary_unit: bool,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender;
+    let state = config_read(deps.storage).load()?;
+    if sender_address == state.owner || auditors_read(deps.storage).may_load(sender_address.as_bytes())?.is_none() {
+        return Err(StdError::generic_err("Only an Auditor can draw a sample"));
+    }
+    let fiscal_period = fiscal_periods_read(deps.storage)
+        .may_load(period.as_bytes())?
+        .ok_or_else(|| StdError::generic_err(format!("Unknown fiscal period: {}", period)))?;
+    if !is_period_auditor(deps.storage, &fiscal_period, &sender_address)? {
+        return Err(StdError::generic_err(format!("Only an Auditor of fiscal period {} can draw a sample", period)));
+    }
+    if audit_samples_read(deps.storage).may_load(period.as_bytes())?.is_some() {
+        return Err(StdError::generic_err(format!("Fiscal period {} is already sampled", period)));
+    }
+    if size == 0 {
+        return Err(StdError::generic_err("A sample needs at least one invoice per stratum"));
+    }
+    if strata.windows(2).any(|bounds| bounds[0] >= bounds[1]) {
+        return Err(StdError::generic_err("The strata boundaries must be ascending"));
+    }
+    let random = env
+        .block
+        .random
+        .ok_or_else(|| StdError::generic_err("The block randomness is not available"))?;
+    let seed = Sha256::digest([random.as_slice(), period.as_bytes()].concat()).to_vec();
+
+    let mut population: Vec<Vec<(u32, Decimal)>> = vec![vec![]; strata.len() + 1];
+    let mut population_size = 0;
+    for (invoice_index, invoice) in period_invoices(deps.storage, &fiscal_period)? {
+        if invoice.voided || invoice.kind == DocumentKind::CreditNote {
+            continue;
+        }
+        let amount = match invoice.base_total_amount {
+            Some(amount) => amount,
+            None => parse_amount("total_amount", &invoice.total_amount)?,
+        };
+        let stratum = strata.iter().filter(|bound| amount >= **bound).count();
+        population[stratum].push((invoice_index, amount));
+        population_size += 1;
+    }
+
+    let mut draw = 0;
+    let mut invoices = vec![];
+    for mut stratum in population {
+        for _ in 0..size.min(stratum.len() as u32) {
+            let position = if monetary_unit {
+                let total = stratum.iter().fold(0u128, |total, (_, amount)| total + amount.atomics().u128());
+                if total == 0 {
+                    break;
+                }
+                let mut point = random_below(&seed, &mut draw, total);
+                stratum
+                    .iter()
+                    .position(|(_, amount)| {
+                        let weight = amount.atomics().u128();
+                        if point < weight {
+                            return true;
+                        }
+                        point -= weight;
+                        false
+                    })
+                    .unwrap_or(0)
+            } else {
+                random_below(&seed, &mut draw, stratum.len() as u128) as usize
+            };
+            invoices.push(stratum.swap_remove(position).0);
+        }
+    }
+    invoices.sort_unstable();
+
+    let sample = AuditSample {
+        period: period.clone(),
+        size,
+        strata,
+        monetary_unit,
+        seed: seed.iter().map(|byte| format!("{:02x}", byte)).collect(),
+        population: population_size,
+        invoices,
+        drawn_by: sender_address,
+        drawn_at_height: env.block.height,
+    };
+    audit_samples(deps.storage).save(period.as_bytes(), &sample)?;
+
+    deps.api.debug("audit sample drawn successfully");
+    Ok(Response::new()
+        .add_attribute("period", period)
+        .add_attribute("sample_size", sample.invoices.len().to_string()))
+}
+
+/// Attempts to create an open fiscal period.
+///
+/// Periods are identified by their name and cannot overlap, so that every invoice date falls in at most one period.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `name` - The unique name of the period.
+/// * `start` - The first day of the period, as `YYYY-MM-DD`.
+/// * `end` - The last day of the period, as `YYYY-MM-DD`.
+/// * `require_auditor` - Whether closing the period also requires the sign-off of an auditor.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_add_fiscal_period(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+    start: String,
+    end: String,
+    require_auditor: bool,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let mut state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can add a fiscal period"));
+    }
+    validate_text("name", &name, true, MAX_CODE_LENGTH)?;
+    parse_date(&start)?;
+    parse_date(&end)?;
+    if start > end {
+        return Err(StdError::generic_err("A fiscal period cannot end before it starts"));
+    }
+    if fiscal_periods_read(deps.storage).may_load(name.as_bytes())?.is_some() {
+        return Err(StdError::generic_err(format!("Fiscal period {} already exists", name)));
+    }
+    for period_index in 0..state.period_count {
+        let other_name = fiscal_period_names_read(deps.storage).load(&period_index.to_be_bytes())?;
+        let other = fiscal_periods_read(deps.storage).load(other_name.as_bytes())?;
+        if start <= other.end && other.start <= end {
+            return Err(StdError::generic_err(format!("Fiscal period overlaps {}", other.name)));
+        }
+    }
+
+    let period = FiscalPeriod {
+        name,
+        start,
+        end,
+        status: PeriodStatus::Open,
+        require_auditor,
+        approver_signoff: None,
+        auditor_signoff: None,
+        closed_at: None,
+        reopens: vec![],
+    };
+    fiscal_periods(deps.storage).save(period.name.as_bytes(), &period)?;
+    fiscal_period_names(deps.storage).save(&state.period_count.to_be_bytes(), &period.name)?;
+    state.period_count += 1;
+    config(deps.storage).save(&state)?;
+
+    deps.api.debug("fiscal period added successfully");
+    Ok(Response::new().add_attribute("period", period.name))
+}
+
+/// Attempts to sign off the closing of a fiscal period.
+///
+/// An approver signs off first; an auditor engaged on the period other than that approver can sign off as well, which
+/// is required when the period was created with `require_auditor`, and must then be engaged before the approver signs
+/// off. When invoices of the period were approved, only one of their approvers can sign off. The period is closed once
+/// every required sign-off is recorded.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `info` - Information about the message sender and other metadata.
+/// * `name` - The name of the period.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_close_period(deps: DepsMut, env: Env, info: MessageInfo, name: String) -> Result<Response, StdError> {
+    let sender_address = info.sender;
+    let mut period = fiscal_periods_read(deps.storage).load(name.as_bytes())?;
+    if period.status != PeriodStatus::Open {
+        return Err(StdError::generic_err(format!("Fiscal period {} is already closed", name)));
+    }
+
+    let is_approver = approvers_read(deps.storage).may_load(sender_address.as_bytes())?.is_some();
+    let is_auditor = is_period_auditor(deps.storage, &period, &sender_address)?;
+    if is_approver && period.approver_signoff.is_none() {
+        let invoices = period_invoices(deps.storage, &period)?;
+        if invoices.iter().any(|(_, invoice)| !invoice.approvals.is_empty())
+            && !invoices.iter().any(|(_, invoice)| invoice.approvals.contains(&sender_address))
+        {
+            return Err(StdError::generic_err(format!(
+                "Only an Approver of the invoices of fiscal period {} can sign off its closing",
+                name
+            )));
+        }
+        let mut engaged = false;
+        for (_, invoice) in invoices.iter().filter(|(_, invoice)| !invoice.auditors.is_empty()) {
+            engaged |= auditors_read(deps.storage).may_load(invoice.auditors.as_bytes())?.is_some();
+        }
+        if period.require_auditor && !engaged {
+            return Err(StdError::generic_err(format!(
+                "An Auditor must be engaged on fiscal period {} before its closing is signed off",
+                name
+            )));
+        }
+        period.approver_signoff = Some(sender_address);
+    } else if is_auditor && period.auditor_signoff.is_none() && period.approver_signoff != Some(sender_address.clone())
+    {
+        period.auditor_signoff = Some(sender_address);
+    } else if is_approver || is_auditor {
+        return Err(StdError::generic_err("The closing of this period is already signed off"));
+    } else {
+        return Err(StdError::generic_err("Only an Approver or an Auditor can sign off a period"));
+    }
+
+    if period.approver_signoff.is_some() && (!period.require_auditor || period.auditor_signoff.is_some()) {
+        period.status = PeriodStatus::Closed;
+        period.closed_at = Some(env.block.time);
+    }
+    fiscal_periods(deps.storage).save(name.as_bytes(), &period)?;
+
+    deps.api.debug("fiscal period signed off successfully");
+    Ok(Response::new()
+        .add_attribute("period", name)
+        .add_attribute("status", if period.status == PeriodStatus::Closed { "closed" } else { "open" }))
+}
+
+/// Attempts to reopen a closed fiscal period.
+///
+/// The reopening is recorded on the period, and the sign-offs are cleared so that closing it again needs new ones.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `info` - Information about the message sender and other metadata.
+/// * `name` - The name of the period.
+/// * `reason` - Why the period is reopened.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+pub fn try_reopen_period(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+    reason: String,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender;
+    if approvers_read(deps.storage).may_load(sender_address.as_bytes())?.is_none() {
+        return Err(StdError::generic_err("Only an Approver can reopen a period"));
+    }
+    validate_text("reason", &reason, true, MAX_DESCRIPTION_LENGTH)?;
+    let mut period = fiscal_periods_read(deps.storage).load(name.as_bytes())?;
+    if period.status == PeriodStatus::Audited {
+        return Err(StdError::generic_err(format!("Fiscal period {} is audited and cannot be reopened", name)));
+    }
+    if period.status != PeriodStatus::Closed {
+        return Err(StdError::generic_err(format!("Fiscal period {} is not closed", name)));
+    }
+
+    period.status = PeriodStatus::Open;
+    period.approver_signoff = None;
+    period.auditor_signoff = None;
+    period.closed_at = None;
+    period.reopens.push(PeriodReopen {
+        reopened_by: sender_address,
+        reason,
+        reopened_at: env.block.time,
+    });
+    fiscal_periods(deps.storage).save(name.as_bytes(), &period)?;
+
+    deps.api.debug("fiscal period reopened successfully");
+    Ok(Response::new().add_attribute("period", name))
+}
+
+/// Attempts to issue the opinion of an auditor engaged on a closed fiscal period.
+///
+/// The opinion commits to every invoice and note dated in the period, voided ones included, through their
+/// `line_hash`. The period is then audited and can no longer be reopened, so that the invoices the opinion covers
+/// cannot change. A period receives a single opinion.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `info` - Information about the message sender and other metadata.
+/// * `period` - The name of the fiscal period.
+/// * `opinion` - The type of the opinion.
+/// * `report_hash` - The SHA-256 hash of the audit report document, hex-encoded.
+/// * `public` - Whether anyone can retrieve the opinion without a permit.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the operation.
+#[allow(clippy::too_many_arguments)]
+pub fn try_issue_audit_opinion(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    period: String,
+    opinion: OpinionType,
+    report_hash: String,
+    public: bool,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender;
+    let state = config_read(deps.storage).load()?;
+    if sender_address == state.owner || auditors_read(deps.storage).may_load(sender_address.as_bytes())?.is_none() {
+        return Err(StdError::generic_err("Only an Auditor can issue an audit opinion"));
+    }
+    let mut fiscal_period = fiscal_periods_read(deps.storage)
+        .may_load(period.as_bytes())?
+        .ok_or_else(|| StdError::generic_err(format!("Unknown fiscal period: {}", period)))?;
+    if fiscal_period.status == PeriodStatus::Audited {
+        return Err(StdError::generic_err(format!("Fiscal period {} already has an audit opinion", period)));
+    }
+    if fiscal_period.status != PeriodStatus::Closed {
+        return Err(StdError::generic_err(format!("Fiscal period {} must be closed before its audit opinion", period)));
+    }
+    if !is_period_auditor(deps.storage, &fiscal_period, &sender_address)? {
+        return Err(StdError::generic_err(format!(
+            "Only an Auditor of fiscal period {} can issue an audit opinion",
+            period
+        )));
+    }
+    validate_hash("report_hash", &report_hash)?;
+
+    let mut invoices = period_invoices(deps.storage, &fiscal_period)?;
+    invoices.sort_by_key(|(invoice_index, _)| *invoice_index);
+    let mut hasher = Sha256::new();
+    for (invoice_index, invoice) in &invoices {
+        hasher.update(invoice_index.to_be_bytes());
+        hasher.update(invoice.line_hash.as_bytes());
+        hasher.update([invoice.voided as u8]);
+    }
+    let commitment: String = hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect();
+
+    let audit_opinion = AuditOpinion {
+        period: period.clone(),
+        opinion,
+        report_hash,
+        commitment: commitment.clone(),
+        invoices: invoices.into_iter().map(|(invoice_index, _)| invoice_index).collect(),
+        public,
+        issued_by: sender_address,
+        issued_at_height: env.block.height,
+        issued_at: env.block.time,
+    };
+    audit_opinions(deps.storage).save(period.as_bytes(), &audit_opinion)?;
+    fiscal_period.status = PeriodStatus::Audited;
+    fiscal_periods(deps.storage).save(period.as_bytes(), &fiscal_period)?;
+
+    deps.api.debug("audit opinion issued successfully");
+    Ok(Response::new()
+        .add_attribute("period", period)
+        .add_attribute("commitment", commitment)
+        .add_attribute("invoice_count", audit_opinion.invoices.len().to_string()))
+}
+
+/// Checks that a date does not fall in a closed fiscal period.
+fn check_period_open(storage: &dyn Storage, state: &State, date: &str) -> StdResult<()> {
+    if let Some(name) = closed_period_of(storage, state, date)? {
+        return Err(StdError::generic_err(format!(
+            "Invoice date {} falls in the closed fiscal period {}",
+            date, name
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the name of the closed or audited fiscal period a date falls in, if any.
+fn closed_period_of(storage: &dyn Storage, state: &State, date: &str) -> StdResult<Option<String>> {
+    for period_index in 0..state.period_count {
+        let name = fiscal_period_names_read(storage).load(&period_index.to_be_bytes())?;
+        let period = fiscal_periods_read(storage).load(name.as_bytes())?;
+        if period.status != PeriodStatus::Open && period.start.as_str() <= date && date <= period.end.as_str() {
+            return Ok(Some(period.name));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the number of distinct approvers an invoice needs before it is sealed, zero when no approval rule applies.
+///
+/// When rules are set, the invoice is checked like an invoice being sealed, so that a submission that could never be
+/// sealed fails early, and its total amount is compared to the thresholds in the base currency once set.
+fn required_approvals(storage: &dyn Storage, state: &State, invoice: &Invoice) -> StdResult<u32> {
+    let rules = approval_rules_read(storage).may_load()?.unwrap_or_default();
+    if rules.is_empty() {
+        return Ok(0);
+    }
+    validate_invoice(invoice)?;
+    check_period_open(storage, state, &invoice.date)?;
+    check_supplier(storage, invoice)?;
+    check_category(storage, invoice)?;
+    check_accounts(storage, &invoice.lines)?;
+    let mut converted = invoice.clone();
+    convert_to_base(storage, &mut converted)?;
+    let total_amount = match converted.base_total_amount {
+        Some(amount) => amount,
+        None => parse_amount("total_amount", &invoice.total_amount)?,
+    };
+    Ok(rules
+        .iter()
+        .filter(|rule| total_amount > rule.threshold)
+        .map(|rule| rule.approvals)
+        .max()
+        .unwrap_or(0))
+}
+
+/// Checks that the supplier referenced by an invoice, if any, is known and active.
+fn check_supplier(storage: &dyn Storage, invoice: &Invoice) -> StdResult<()> {
+    if let Some(id) = &invoice.supplier {
+        match suppliers_read(storage).may_load(id.as_bytes())? {
+            None => return Err(StdError::generic_err(format!("Unknown supplier: {}", id))),
+            Some(supplier) if supplier.status != SupplierStatus::Active => {
+                return Err(StdError::generic_err(format!("Inactive supplier: {}", id)));
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Matches an invoice with its purchase order and the goods received so far, if it references one.
+///
+/// The purchase order must exist, be in the currency of the invoice and be for the same supplier when both name one;
+/// otherwise the invoice is rejected. Within the match tolerances, the quantities invoiced by the invoice and the
+/// earlier invoices of the order must not exceed the quantities ordered and received, the unit prices must be those
+/// of the order and the items must add up to the net amount. The net amounts invoiced must not exceed the value of
+/// the goods received.
+fn match_purchase_order(
+    storage: &dyn Storage,
+    invoice_index: u32,
+    invoice: &Invoice,
+) -> StdResult<Option<MatchResult>> {
+    let number = match &invoice.purchase_order {
+        Some(number) => number,
+        None if invoice.items.is_empty() => return Ok(None),
+        None => return Err(StdError::generic_err("Only an Invoice referencing a purchase order can list items")),
+    };
+    if invoice.kind != DocumentKind::Invoice {
+        return Err(StdError::generic_err("Only an Invoice can reference a purchase order"));
+    }
+    let order = purchase_orders_read(storage)
+        .may_load(number.as_bytes())?
+        .ok_or_else(|| StdError::generic_err(format!("Unknown purchase order: {}", number)))?;
+    if order.currency != invoice.currency {
+        return Err(StdError::generic_err(format!("Purchase order {} is in {}", number, order.currency)));
+    }
+    if let (Some(ordered_from), Some(supplier)) = (&order.supplier, &invoice.supplier) {
+        if ordered_from != supplier {
+            return Err(StdError::generic_err(format!("Purchase order {} is for supplier {}", number, ordered_from)));
+        }
+    }
+    let tolerance = match_tolerance_read(storage).may_load()?.unwrap_or_default();
+    let exceeds = |value: Decimal, limit: Decimal, tolerance: Decimal| value > limit + limit * tolerance;
+    let differs = |value: Decimal, expected: Decimal, tolerance: Decimal| {
+        let difference = if value > expected { value - expected } else { expected - value };
+        difference > expected * tolerance
+    };
+
+    // The earlier invoices of the order are covered by the goods received first.
+    let mut earlier_items: Vec<ItemLine> = vec![];
+    let mut invoiced_amount = net_amount(invoice)?;
+    for index in order.invoices.iter().filter(|index| **index < invoice_index) {
+        let earlier = config_invoice_read(storage, &invoice_key(*index)).load()?;
+        if !earlier.voided {
+            invoiced_amount += net_amount(&earlier)?;
+            earlier_items.extend(earlier.items);
+        }
+    }
+
+    let mut reasons = vec![];
+    for item in &invoice.items {
+        let ordered = match order.lines.iter().find(|line| line.item == item.item) {
+            Some(ordered) => ordered,
+            None => {
+                reasons.push(format!("item {} was not ordered", item.item));
+                continue;
+            }
+        };
+        let quantity = earlier_items
+            .iter()
+            .filter(|earlier| earlier.item == item.item)
+            .fold(item.quantity, |total, earlier| total + earlier.quantity);
+        let received = received_quantity(&order, &item.item);
+        if exceeds(quantity, ordered.quantity, tolerance.quantity) {
+            reasons.push(format!("item {}: {} invoiced, {} ordered", item.item, quantity, ordered.quantity));
+        }
+        if exceeds(quantity, received, tolerance.quantity) {
+            reasons.push(format!("item {}: {} invoiced, {} received", item.item, quantity, received));
+        }
+        if differs(item.unit_price, ordered.unit_price, tolerance.amount) {
+            reasons.push(format!(
+                "item {}: unit price {}, ordered at {}",
+                item.item, item.unit_price, ordered.unit_price
+            ));
+        }
+    }
+    if !invoice.items.is_empty() {
+        let items_amount = invoice
+            .items
+            .iter()
+            .fold(Decimal::zero(), |total, item| total + item.quantity * item.unit_price);
+        if differs(net_amount(invoice)?, items_amount, tolerance.amount) {
+            reasons.push(format!("net amount {}, items {}", net_amount(invoice)?, items_amount));
+        }
+    }
+    let received_amount = order.lines.iter().fold(Decimal::zero(), |total, line| {
+        total + received_quantity(&order, &line.item) * line.unit_price
+    });
+    if exceeds(invoiced_amount, received_amount, tolerance.amount) {
+        reasons.push(format!("{} invoiced, {} received", invoiced_amount, received_amount));
+    }
+
+    Ok(Some(MatchResult {
+        matched: reasons.is_empty(),
+        reasons,
+    }))
+}
+
+/// Returns the quantity of an item received against a purchase order.
+fn received_quantity(order: &PurchaseOrder, item: &str) -> Decimal {
+    order
+        .received
+        .iter()
+        .find(|received| received.item == item)
+        .map_or_else(Decimal::zero, |received| received.quantity)
+}
+
+/// Returns the amount of an invoice net of tax.
+fn net_amount(invoice: &Invoice) -> StdResult<Decimal> {
+    let total_amount = parse_amount("total_amount", &invoice.total_amount)?;
+    let tax_amount = parse_amount("tax_amount", &invoice.tax_amount)?;
+    Ok(if total_amount > tax_amount { total_amount - tax_amount } else { Decimal::zero() })
+}
+
+/// Matches the non-voided invoices of a purchase order again, after goods are received or one of them changes.
+///
+/// Only the purchase order flag of the invoices is updated. The audit state of the invoices still waiting for review is
+/// decided again from it, and so is the state of the invoices pending, sealed or approved that no longer match, which
+/// must then be reviewed. The decisions of the auditors on flagged invoices and correction requests are kept, and
+/// invoices dated in a closed or audited fiscal period are left unchanged.
+fn rematch_purchase_order(storage: &mut dyn Storage, number: &str) -> StdResult<()> {
+    let state = config_read(storage).load()?;
+    let order = purchase_orders_read(storage).load(number.as_bytes())?;
+    for index in order.invoices {
+        let key = invoice_key(index);
+        let mut invoice = config_invoice_read(storage, &key).load()?;
+        if invoice.voided || closed_period_of(storage, &state, &invoice.date)?.is_some() {
+            continue;
+        }
+        invoice.match_result = match_purchase_order(storage, index, &invoice)?;
+        invoice.audit_flags.retain(|flag| flag != AUDIT_FLAG_PO_MISMATCH);
+        let mismatched = invoice.match_result.as_ref().is_some_and(|result| !result.matched);
+        if mismatched {
+            invoice.audit_flags.push(AUDIT_FLAG_PO_MISMATCH.to_string());
+        }
+        let reopened = mismatched
+            && [AUDIT_STATE_PENDING, AUDIT_STATE_SEALED, AUDIT_STATE_APPROVED].contains(&invoice.audit_state.as_str());
+        if invoice.audit_state == AUDIT_STATE_MUST_REVIEW || reopened {
+            invoice.audit_state = initial_audit_state(storage, &invoice)?;
+        }
+        config_invoice(storage, &key).save(&invoice)?;
+    }
+    Ok(())
+}
+
+/// Checks that the expense category of an invoice, if any, is known and active.
+fn check_category(storage: &dyn Storage, invoice: &Invoice) -> StdResult<()> {
+    if let Some(code) = &invoice.category {
+        match expense_categories_read(storage).may_load(code.as_bytes())? {
+            None => return Err(StdError::generic_err(format!("Unknown expense category: {}", code))),
+            Some(category) if !category.active => {
+                return Err(StdError::generic_err(format!("Inactive expense category: {}", code)));
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Returns the other invoices of the supplier of a new or amended invoice that, together with it, reach the threshold
+/// of the split rule within its window, bounds included, while each stays below it, or nothing when they do not.
+///
+/// Credit notes and voided invoices are left out, and only the latest `SPLIT_SCAN_LIMIT` invoices of the supplier are
+/// combined. Amounts are taken in the base currency once it is set.
+fn split_cluster(storage: &dyn Storage, invoice_index: u32, invoice: &Invoice) -> StdResult<Vec<u32>> {
+    let (rule, supplier) = match (split_rule_read(storage).may_load()?, &invoice.supplier) {
+        (Some(rule), Some(supplier)) if invoice.kind != DocumentKind::CreditNote => (rule, supplier),
+        _ => return Ok(vec![]),
+    };
+    let base_total = |invoice: &Invoice| match invoice.base_total_amount {
+        Some(amount) => Ok(amount),
+        None => parse_amount("total_amount", &invoice.total_amount),
+    };
+    let day_number = |date: &str| parse_date(date).map(|(year, month, day)| days_from_civil(year, month, day));
+
+    let mut combined = base_total(invoice)?;
+    if combined >= rule.threshold {
+        return Ok(vec![]);
+    }
+    let day = day_number(&invoice.date)?;
+    let count = supplier_totals_read(storage).may_load(supplier.as_bytes())?.unwrap_or_default().invoice_count;
+    let mut cluster = vec![];
+    for position in (count.saturating_sub(SPLIT_SCAN_LIMIT)..count).rev() {
+        let index = supplier_invoices_read(storage).load(&supplier_invoice_key(supplier, position))?;
+        if index == invoice_index {
+            continue;
+        }
+        let other = config_invoice_read(storage, &invoice_key(index)).load()?;
+        if other.voided || other.kind == DocumentKind::CreditNote {
+            continue;
+        }
+        let total_amount = base_total(&other)?;
+        if total_amount < rule.threshold && (day_number(&other.date)? - day).abs() <= i64::from(rule.window_days) {
+            cluster.push(index);
+            combined += total_amount;
+        }
+    }
+    Ok(if combined >= rule.threshold { cluster } else { vec![] })
+}
+
+/// Flags the other invoices of a split-invoice cluster and returns the `split_invoice` event of the sealed invoice.
+///
+/// The flag is audit metadata, like the audit state: flagged invoices keep their version and seal, and the audit state
+/// of those still waiting for a regular audit is decided again from their flags. Invoices dated in a closed or audited
+/// fiscal period are left unchanged.
+fn flag_split_cluster(
+    storage: &mut dyn Storage,
+    state: &State,
+    invoice_index: u32,
+    invoice: &Invoice,
+    cluster: &[u32],
+) -> StdResult<Option<Event>> {
+    if cluster.is_empty() {
+        return Ok(None);
+    }
+    for index in cluster {
+        let key = invoice_key(*index);
+        let mut other = config_invoice_read(storage, &key).load()?;
+        if other.audit_flags.iter().any(|flag| flag == AUDIT_FLAG_SPLIT_INVOICE)
+            || closed_period_of(storage, state, &other.date)?.is_some()
+        {
+            continue;
+        }
+        other.audit_flags.push(AUDIT_FLAG_SPLIT_INVOICE.to_string());
+        if other.audit_state == AUDIT_STATE_PENDING || other.audit_state == AUDIT_STATE_MUST_REVIEW {
+            other.audit_state = initial_audit_state(storage, &other)?;
+        }
+        config_invoice(storage, &key).save(&other)?;
+    }
+    let indexes: Vec<String> = cluster.iter().map(|index| index.to_string()).collect();
+    Ok(Some(
+        Event::new("split_invoice")
+            .add_attribute("invoice_index", invoice_index.to_string())
+            .add_attribute("supplier", invoice.supplier.clone().unwrap_or_default())
+            .add_attribute("cluster", indexes.join(",")),
+    ))
+}
+
+/// Returns whether the total amount of an invoice, in the base currency once set, is just below the threshold of an
+/// approval rule.
+fn near_threshold(storage: &dyn Storage, invoice: &Invoice) -> StdResult<bool> {
+    let total_amount = match invoice.base_total_amount {
+        Some(amount) => amount,
+        None => parse_amount("total_amount", &invoice.total_amount)?,
+    };
+    let rules = approval_rules_read(storage).may_load()?.unwrap_or_default();
+    Ok(rules.iter().any(|rule| {
+        let floor = rule.threshold * Decimal::percent(100 - NEAR_THRESHOLD_PERCENT);
+        floor <= total_amount && total_amount <= rule.threshold
+    }))
+}
+
+/// Adds an invoice or debit note to the anomaly counters of its month, or removes it when `reverse` is set.
+///
+/// The counters cover the first significant digit of the total amount, round amounts, weekend dates and amounts just
+/// below an approval threshold when sealed, all in the base currency once set. Credit notes are not counted, and
+/// invoices sealed before the counters of their month existed are never taken out of them.
+fn post_anomalies(storage: &mut dyn Storage, invoice: &Invoice, reverse: bool) -> StdResult<()> {
+    if invoice.kind == DocumentKind::CreditNote {
+        return Ok(());
+    }
+    let total_amount = match invoice.base_total_amount {
+        Some(amount) => amount,
+        None => parse_amount("total_amount", &invoice.total_amount)?,
+    };
+    let (year, month, day) = parse_date(&invoice.date)?;
+    let key = month_index(year, month).to_be_bytes();
+    let mut counters = match anomaly_months_read(storage).may_load(&key)? {
+        Some(counters) => counters,
+        None if reverse => return Ok(()),
+        None => AnomalyCounters::default(),
+    };
+    counters.first_digits.resize(9, 0);
+
+    let step = |count: u32| if reverse { count.saturating_sub(1) } else { count + 1 };
+    counters.invoices = step(counters.invoices);
+    let first_digit = total_amount.to_string().chars().find(|digit| ('1'..='9').contains(digit));
+    if let Some(digit) = first_digit.and_then(|digit| digit.to_digit(10)) {
+        counters.first_digits[digit as usize - 1] = step(counters.first_digits[digit as usize - 1]);
+    }
+    let hundred = Decimal::one().atomics().u128() * 100;
+    if !total_amount.is_zero() && total_amount.atomics().u128() % hundred == 0 {
+        counters.round_amounts = step(counters.round_amounts);
+    }
+    // 1970-01-01 was a Thursday, so Sundays are 0 and Saturdays 6.
+    let weekday = (days_from_civil(year, month, day) + 4).rem_euclid(7);
+    if weekday == 0 || weekday == 6 {
+        counters.weekend_dates = step(counters.weekend_dates);
+    }
+    if invoice.near_threshold {
+        counters.near_threshold = step(counters.near_threshold);
+    }
+    anomaly_months(storage).save(&key, &counters)
+}
+
+/// Adds an invoice to the index of the invoices dated in the month of `date`, or removes it when `remove` is set.
+fn index_month(storage: &mut dyn Storage, invoice_index: u32, date: &str, remove: bool) -> StdResult<()> {
+    let (year, month, _) = parse_date(date)?;
+    let key = month_index(year, month).to_be_bytes();
+    let mut indexes = month_invoices_read(storage).may_load(&key)?.unwrap_or_default();
+    match (remove, indexes.binary_search(&invoice_index)) {
+        (false, Err(position)) => indexes.insert(position, invoice_index),
+        (true, Ok(position)) => {
+            indexes.remove(position);
+        }
+        _ => return Ok(()),
+    }
+    month_invoices(storage).save(&key, &indexes)
+}
+
+/// Loads the invoices dated in a fiscal period, with their indexes, from the index of the invoices of each month.
+fn period_invoices(storage: &dyn Storage, period: &FiscalPeriod) -> StdResult<Vec<(u32, Invoice)>> {
+    let (start_year, start_month, _) = parse_date(&period.start)?;
+    let (end_year, end_month, _) = parse_date(&period.end)?;
+    let mut invoices = vec![];
+    for month in month_index(start_year, start_month)..=month_index(end_year, end_month) {
+        for invoice_index in month_invoices_read(storage).may_load(&month.to_be_bytes())?.unwrap_or_default() {
+            let invoice = config_invoice_read(storage, &invoice_key(invoice_index)).load()?;
+            if period.start <= invoice.date && invoice.date <= period.end {
+                invoices.push((invoice_index, invoice));
+            }
+        }
+    }
+    Ok(invoices)
+}
+
+/// Returns a pseudo-random number below `bound`, the `draw`-th derived from the seed, and moves to the next draw.
+fn random_below(seed: &[u8], draw: &mut u32, bound: u128) -> u128 {
+    let digest = Sha256::digest([seed, &draw.to_be_bytes()].concat());
+    *draw += 1;
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    u128::from_be_bytes(bytes) % bound
+}
+
+/// Appends a sealed invoice version to the Merkle tree of the ledger and to the tree of its fiscal period, if any.
+///
+/// The period is the one known when the version is sealed: a period added afterwards only holds the versions sealed
+/// after it.
+fn append_ledger_leaf(storage: &mut dyn Storage, state: &State, invoice: &Invoice) -> StdResult<LedgerLeaf> {
+    let leaf_hash = merkle_leaf_hash(&invoice.line_hash, &invoice.field_commitment);
+    let leaf = append_merkle_leaf(storage, LEDGER_TREE, leaf_hash)?;
+    let root = merkle_trees_read(storage).load(LEDGER_TREE)?.root;
+    ledger_roots(storage).save(root.as_bytes(), &invoice.sealed_at_time)?;
+    let period = fiscal_period_of(storage, state, &invoice.date)?;
+    let period_leaf = match &period {
+        Some(name) => Some(append_merkle_leaf(storage, name.as_bytes(), leaf_hash)?),
+        None => None,
+    };
+    Ok(LedgerLeaf { leaf, period, period_leaf })
+}
+
+/// Returns the leaf of a sealed version: the SHA-256 of a zero byte followed by its hex-encoded `line_hash` and
+/// `field_commitment`, so that a selective disclosure can be checked against a published root.
+fn merkle_leaf_hash(line_hash: &str, field_commitment: &str) -> [u8; 32] {
+    Sha256::digest([&[0u8], line_hash.as_bytes(), field_commitment.as_bytes()].concat()).into()
+}
+
+/// Returns the parent of two nodes: the SHA-256 of a one byte followed by the left and the right node.
+fn merkle_parent(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Sha256::digest([&[1u8], &left[..], &right[..]].concat()).into()
+}
+
+/// Returns the height of a tree of `leaf_count` leaves, padded on the right to the next power of two.
+fn merkle_height(leaf_count: u32) -> u8 {
+    (u32::BITS - leaf_count.saturating_sub(1).leading_zeros()) as u8
+}
+
+/// Returns the root of an empty subtree of the given level, whose leaves are zero.
+fn merkle_empty(level: u8) -> [u8; 32] {
+    (0..level).fold([0u8; 32], |node, _| merkle_parent(&node, &node))
+}
+
+/// Loads a node of a Merkle tree. Nodes right of the last leaf are the roots of empty subtrees.
+fn merkle_node(storage: &dyn Storage, tree: &[u8], level: u8, position: u32) -> StdResult<[u8; 32]> {
+    match merkle_nodes_read(storage).may_load(&merkle_node_key(tree, level, position))? {
+        Some(node) => node
+            .as_slice()
+            .try_into()
+            .map_err(|_| StdError::generic_err("Invalid Merkle node")),
+        None => Ok(merkle_empty(level)),
+    }
+}
+
+/// Returns a node of a Merkle tree as it was when the tree had `leaf_count` leaves.
+///
+/// A stored node no longer changes once its last leaf is appended, so only the nodes over the last of the
+/// `leaf_count` leaves are hashed again from their children.
+fn merkle_node_at(
+    storage: &dyn Storage,
+    tree: &[u8],
+    level: u8,
+    position: u32,
+    leaf_count: u32,
+) -> StdResult<[u8; 32]> {
+    let first = u64::from(position) << level;
+    if first >= u64::from(leaf_count) {
+        return Ok(merkle_empty(level));
+    }
+    if first + (1 << level) <= u64::from(leaf_count) {
+        return merkle_node(storage, tree, level, position);
+    }
+    let left = merkle_node_at(storage, tree, level - 1, position * 2, leaf_count)?;
+    let right = merkle_node_at(storage, tree, level - 1, position * 2 + 1, leaf_count)?;
+    Ok(merkle_parent(&left, &right))
+}
+
+/// Appends a leaf to a Merkle tree and returns its position.
+///
+/// Only the path from the new leaf to the root is hashed again, and every node of it is stored so that proofs can be
+/// read without rebuilding the tree.
+fn append_merkle_leaf(storage: &mut dyn Storage, tree: &[u8], leaf_hash: [u8; 32]) -> StdResult<u32> {
+    let mut merkle_tree = merkle_trees_read(storage).may_load(tree)?.unwrap_or_default();
+    let leaf = merkle_tree.leaf_count;
+    let mut node = leaf_hash;
+    let mut position = leaf;
+    merkle_nodes(storage).save(&merkle_node_key(tree, 0, position), &Binary::from(node.to_vec()))?;
+    for level in 0..merkle_height(leaf + 1) {
+        let sibling = merkle_node(storage, tree, level, position ^ 1)?;
+        node = if position.is_multiple_of(2) { merkle_parent(&node, &sibling) } else { merkle_parent(&sibling, &node) };
+        position /= 2;
+        merkle_nodes(storage).save(&merkle_node_key(tree, level + 1, position), &Binary::from(node.to_vec()))?;
+    }
+    merkle_tree.leaf_count += 1;
+    merkle_tree.root = node.iter().map(|byte| format!("{:02x}", byte)).collect();
+    merkle_roots(storage).save(&merkle_root_key(tree, merkle_tree.leaf_count), &merkle_tree.root)?;
+    merkle_trees(storage).save(tree, &merkle_tree)?;
+    Ok(leaf)
+}
+
+/// Returns the proof that a leaf is included in the root a Merkle tree had with `leaf_count` leaves, or in its
+/// current root when not set, so that a root published earlier stays provable as the tree grows.
+fn merkle_proof(storage: &dyn Storage, tree: &[u8], leaf: u32, leaf_count: Option<u32>) -> StdResult<MerkleProof> {
+    let merkle_tree = merkle_trees_read(storage).may_load(tree)?.unwrap_or_default();
+    let leaf_count = leaf_count.unwrap_or(merkle_tree.leaf_count);
+    if leaf_count > merkle_tree.leaf_count {
+        return Err(StdError::generic_err(format!("The Merkle tree only has {} leaves", merkle_tree.leaf_count)));
+    }
+    if leaf >= leaf_count {
+        return Err(StdError::generic_err(format!(
+            "Leaf {} was appended after the Merkle tree had {} leaves",
+            leaf, leaf_count
+        )));
+    }
+    let siblings = (0..merkle_height(leaf_count))
+        .map(|level| {
+            let sibling = merkle_node_at(storage, tree, level, (leaf >> level) ^ 1, leaf_count)?;
+            Ok(sibling.iter().map(|byte| format!("{:02x}", byte)).collect())
+        })
+        .collect::<StdResult<Vec<String>>>()?;
+    Ok(MerkleProof {
+        leaf,
+        leaf_count,
+        siblings,
+        root: merkle_roots_read(storage).load(&merkle_root_key(tree, leaf_count))?,
+    })
+}
+
+/// Draws the secret salt of a sealed invoice version from the block randomness and returns its field commitment.
+fn seal_field_commitment(
+    storage: &mut dyn Storage,
+    env: &Env,
+    invoice_index: u32,
+    invoice: &Invoice,
+) -> StdResult<String> {
+    let random = env
+        .block
+        .random
+        .as_ref()
+        .ok_or_else(|| StdError::generic_err("The block randomness is not available"))?;
+    let key = invoice_version_key(invoice_index, invoice.version);
+    let salt = Sha256::digest([random.as_slice(), &key].concat()).to_vec();
+    disclosure_salts(storage).save(&key, &Binary::from(salt.clone()))?;
+    let hashes = disclosed_fields(invoice, &salt, &[])?.into_iter().map(|field| field.hash);
+    Ok(Sha256::digest(hashes.collect::<String>().as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Returns every field of an invoice covered by its `line_hash` with its salted hash, revealing the value and the
+/// salt of the fields listed in `revealed`.
+///
+/// The salt of a field is the SHA-256 of the salt of the invoice version followed by the position of the field, so
+/// that revealing it tells nothing about the other fields.
+fn disclosed_fields(invoice: &Invoice, salt: &[u8], revealed: &[DisclosureField]) -> StdResult<Vec<DisclosedField>> {
+    let values = [
+        (DisclosureField::InvoiceNumber, invoice.invoice_number.clone()),
+        (DisclosureField::Date, invoice.date.clone()),
+        (DisclosureField::ClientName, invoice.client_name.clone()),
+        (DisclosureField::Description, invoice.description.clone()),
+        (DisclosureField::TotalAmount, invoice.total_amount.clone()),
+        (DisclosureField::TaxAmount, invoice.tax_amount.clone()),
+        (DisclosureField::Currency, invoice.currency.clone()),
+        (DisclosureField::DocHash, invoice.doc_hash.clone()),
+        (DisclosureField::Lines, String::from_utf8(to_vec(&invoice.lines)?)?),
+        (DisclosureField::Items, String::from_utf8(to_vec(&invoice.items)?)?),
+    ];
+    Ok(values
+        .into_iter()
+        .enumerate()
+        .map(|(position, (field, value))| {
+            let field_salt: String = Sha256::digest([salt, &[position as u8]].concat())
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            let hash = Sha256::digest([field_salt.as_bytes(), value.as_bytes()].concat())
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            let reveal = revealed.contains(&field);
+            DisclosedField {
+                field,
+                value: reveal.then_some(value),
+                salt: reveal.then_some(field_salt),
+                hash,
+            }
+        })
+        .collect())
+}
+
+/// Returns the name of the fiscal period in which a date falls, if any.
+fn fiscal_period_of(storage: &dyn Storage, state: &State, date: &str) -> StdResult<Option<String>> {
+    for period_index in 0..state.period_count {
+        let name = fiscal_period_names_read(storage).load(&period_index.to_be_bytes())?;
+        let period = fiscal_periods_read(storage).load(name.as_bytes())?;
+        if period.start.as_str() <= date && date <= period.end.as_str() {
+            return Ok(Some(name));
+        }
+    }
+    Ok(None)
+}
+
+/// Adds the net amount of an invoice to the spend of its expense category over its `budget_period`, or removes it
+/// when `reverse` is set.
+///
+/// The amount is taken in the base currency once it is set, and credit notes reduce the spend. Invoices dated outside
+/// every fiscal period are not tracked. Returns a `budget_overrun` event when the invoice leaves the spend over the
+/// budget of the period, and an error when a reversal would take the spend below zero.
+fn post_category(
+    storage: &mut dyn Storage,
+    invoice_index: u32,
+    invoice: &Invoice,
+    reverse: bool,
+) -> StdResult<Option<Event>> {
+    let (category, period) = match (&invoice.category, &invoice.budget_period) {
+        (Some(category), Some(period)) => (category, period),
+        _ => return Ok(None),
+    };
+    let amount = match invoice.exchange_rate {
+        Some(rate) => net_amount(invoice)? * rate,
+        None => net_amount(invoice)?,
+    };
+
+    let key = category_budget_key(category, period);
+    let mut budget = category_budgets_read(storage).may_load(&key)?.unwrap_or_default();
+    let spends = invoice.kind != DocumentKind::CreditNote;
+    if spends == reverse {
+        budget.spent = budget.spent.checked_sub(amount).map_err(|_| {
+            let message = format!("The spend of expense category {} over {} cannot go below zero", category, period);
+            StdError::generic_err(message)
+        })?;
+    } else {
+        budget.spent += amount;
+    }
+    category_budgets(storage).save(&key, &budget)?;
+
+    match budget.budget {
+        Some(limit) if spends && !reverse && budget.spent > limit => Ok(Some(
+            Event::new("budget_overrun")
+                .add_attribute("invoice_index", invoice_index.to_string())
+                .add_attribute("category", category)
+                .add_attribute("period", period.as_str())
+                .add_attribute("budget", limit.to_string())
+                .add_attribute("spent", budget.spent.to_string()),
+        )),
+        _ => Ok(None),
+    }
+}
+
+/// Adds the amounts of an invoice to the totals of its supplier, or removes them when `reverse` is set.
+///
+/// The amounts are taken in the base currency once it is set. Credit notes are added to the credited amount.
+fn post_supplier(storage: &mut dyn Storage, invoice: &Invoice, reverse: bool) -> StdResult<()> {
+    let supplier = match &invoice.supplier {
+        Some(supplier) => supplier,
+        None => return Ok(()),
+    };
+    let total_amount = match invoice.base_total_amount {
+        Some(amount) => amount,
+        None => parse_amount("total_amount", &invoice.total_amount)?,
+    };
+    let tax_amount = match invoice.base_tax_amount {
+        Some(amount) => amount,
+        None => parse_amount("tax_amount", &invoice.tax_amount)?,
+    };
+
+    supplier_totals(storage).update(supplier.as_bytes(), |totals| -> StdResult<SupplierTotals> {
+        let mut totals = totals.unwrap_or_default();
+        match (invoice.kind, reverse) {
+            (DocumentKind::CreditNote, false) => totals.credited_amount += total_amount,
+            (DocumentKind::CreditNote, true) => totals.credited_amount -= total_amount,
+            (_, false) => {
+                totals.total_amount += total_amount;
+                totals.tax_amount += tax_amount;
+            }
+            (_, true) => {
+                totals.total_amount -= total_amount;
+                totals.tax_amount -= tax_amount;
+            }
+        }
+        Ok(totals)
+    })?;
+    Ok(())
+}
+
+/// Records the exchange rate of an invoice and its amounts in the base currency, once a base currency is set.
+///
+/// Invoices in another currency need a recorded rate to the base currency on their date.
+fn convert_to_base(storage: &dyn Storage, invoice: &mut Invoice) -> StdResult<()> {
+    let rate = match currency_config_read(storage).may_load()? {
+        None => None,
+        Some(config) if config.base_currency == invoice.currency => Some(Decimal::one()),
+        Some(config) => {
+            let key = exchange_rate_key(&invoice.currency, &config.base_currency, &invoice.date);
+            let exchange_rate = exchange_rates_read(storage).may_load(&key)?.ok_or_else(|| {
+                StdError::generic_err(format!(
+                    "No exchange rate from {} to {} on {}",
+                    invoice.currency, config.base_currency, invoice.date
+                ))
+            })?;
+            Some(exchange_rate.rate)
+        }
+    };
+
+    invoice.exchange_rate = rate;
+    invoice.base_total_amount = match rate {
+        Some(rate) => Some(parse_amount("total_amount", &invoice.total_amount)? * rate),
+        None => None,
+    };
+    invoice.base_tax_amount = match rate {
+        Some(rate) => Some(parse_amount("tax_amount", &invoice.tax_amount)? * rate),
+        None => None,
+    };
+    Ok(())
+}
+
+/// Checks that every journal line is posted to an active account of the chart of accounts.
+fn check_accounts(storage: &dyn Storage, lines: &[JournalLine]) -> StdResult<()> {
+    let accounts = accounts_read(storage);
+    for line in lines {
+        match accounts.may_load(line.account.as_bytes())? {
+            None => return Err(StdError::generic_err(format!("Unknown account: {}", line.account))),
+            Some(account) if !account.active => {
+                return Err(StdError::generic_err(format!("Inactive account: {}", line.account)));
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Checks the tax codes of the journal lines and totals their taxable base and input VAT by tax code.
+///
+/// Every tax code must be known and active.
+fn tax_breakdown(storage: &dyn Storage, lines: &[JournalLine]) -> StdResult<Vec<TaxBreakdown>> {
+    let codes = tax_codes_read(storage);
+    let mut breakdown: Vec<TaxBreakdown> = vec![];
+    for line in lines {
+        if let Some(code) = &line.tax_code {
+            let tax_code = match codes.may_load(code.as_bytes())? {
+                None => return Err(StdError::generic_err(format!("Unknown tax code: {}", code))),
+                Some(tax_code) if !tax_code.active => {
+                    return Err(StdError::generic_err(format!("Inactive tax code: {}", code)));
+                }
+                Some(tax_code) => tax_code,
+            };
+            let base = line.debit + line.credit;
+            match breakdown.iter_mut().find(|entry| entry.tax_code == *code) {
+                Some(entry) => {
+                    entry.base += base;
+                    entry.tax += base * tax_code.rate;
+                }
+                None => breakdown.push(TaxBreakdown {
+                    tax_code: code.clone(),
+                    base,
+                    tax: base * tax_code.rate,
+                }),
+            }
+        }
+    }
+    Ok(breakdown)
+}
+
+/// Returns the audit flags of an invoice whose tax breakdown and match result are computed.
+///
+/// The `tax_amount` must match the input VAT of the tax breakdown within the rounding tolerance, and the invoice
+/// must match its purchase order; otherwise the invoice is flagged rather than rejected.
+fn audit_flags(storage: &dyn Storage, invoice: &Invoice) -> StdResult<Vec<String>> {
+    let mut flags = vec![];
+    if invoice.match_result.as_ref().is_some_and(|result| !result.matched) {
+        flags.push(AUDIT_FLAG_PO_MISMATCH.to_string());
+    }
+    if invoice.lines.is_empty() {
+        return Ok(flags);
+    }
+    let expected = invoice.tax_breakdown.iter().fold(Decimal::zero(), |total, entry| total + entry.tax);
+    let tax_amount = parse_amount("tax_amount", &invoice.tax_amount)?;
+    let difference = if tax_amount > expected { tax_amount - expected } else { expected - tax_amount };
+    if difference > load_tax_tolerance(storage)? {
+        flags.push(AUDIT_FLAG_TAX_MISMATCH.to_string());
+    }
+    Ok(flags)
+}
+
+/// Loads the rounding tolerance of the tax consistency check, one cent when the owner never set it.
+fn load_tax_tolerance(storage: &dyn Storage) -> StdResult<Decimal> {
+    Ok(tax_tolerance_read(storage).may_load()?.unwrap_or_else(|| Decimal::percent(1)))
+}
+
+/// Decides the audit state of a newly sealed or amended invoice from its audit flags and credibility score.
+///
+/// Flagged invoices and invoices below the review threshold must be reviewed and are never sealed straight through,
+/// invoices reaching the sealing threshold are sealed, and the others wait for a regular audit.
+fn initial_audit_state(storage: &dyn Storage, invoice: &Invoice) -> StdResult<String> {
+    let thresholds = credibility_thresholds_read(storage).may_load()?.unwrap_or_default();
+    let credibility = invoice.credibility;
+    let audit_state = if !invoice.audit_flags.is_empty() || credibility < thresholds.review_below {
+        AUDIT_STATE_MUST_REVIEW
+    } else if thresholds.seal_from.is_some_and(|seal_from| credibility >= seal_from) {
+        AUDIT_STATE_SEALED
+    } else {
+        AUDIT_STATE_PENDING
+    };
+    Ok(audit_state.to_string())
+}
+
+/// Checks the reference of a credit or debit note and records a credit note against its original invoice.
+///
+/// Plain invoices must not reference another document. Notes must reference an existing, non-voided invoice, and
+/// the credit notes of an invoice may not exceed its total amount.
+///
+/// # Arguments
+///
+/// * `storage` - The contract's storage.
+/// * `note` - The invoice or note being sealed.
+/// * `previous` - The version being replaced when a note is amended, whose amount is released first.
+///
+/// # Returns
+///
+/// A `StdResult<()>` indicating whether the reference is valid.
+fn apply_reference(storage: &mut dyn Storage, note: &Invoice, previous: Option<&Invoice>) -> StdResult<()> {
+    let reference = match (note.kind, note.reference) {
+        (DocumentKind::Invoice, None) => return Ok(()),
+        (DocumentKind::Invoice, Some(_)) => {
+            return Err(StdError::generic_err("Only credit and debit notes can reference an Invoice"));
+        }
+        (_, None) => return Err(StdError::generic_err("A credit or debit note must reference an Invoice")),
+        (_, Some(reference)) => reference,
+    };
+
+    let original_key = invoice_key(reference);
+    let mut original = config_invoice_read(storage, &original_key)
+        .may_load()?
+        .ok_or_else(|| StdError::generic_err(format!("Referenced Invoice {} does not exist", reference)))?;
+    if original.kind != DocumentKind::Invoice {
+        return Err(StdError::generic_err("A note can only reference an Invoice"));
+    }
+    if original.voided {
+        return Err(StdError::generic_err("A voided Invoice cannot be referenced"));
+    }
+
+    if note.kind == DocumentKind::CreditNote {
+        let mut credited = original.credited_amount;
+        if let Some(previous) = previous {
+            credited -= parse_amount("total_amount", &previous.total_amount)?;
+        }
+        credited += parse_amount("total_amount", &note.total_amount)?;
+        if credited > parse_amount("total_amount", &original.total_amount)? {
+            return Err(StdError::generic_err(format!(
+                "Credit notes exceed the total amount of Invoice {}",
+                reference
+            )));
+        }
+        original.credited_amount = credited;
+        config_invoice(storage, &original_key).save(&original)?;
+        sync_open_payable(storage, reference, &original)?;
+    }
+    Ok(())
+}
+
+/// Adds the journal lines of an invoice to the running account totals, or removes them when `reverse` is set.
+///
+/// The totals are kept per account for the whole ledger and per month of the invoice date, so that balance queries
+/// do not depend on the number of invoices.
+fn post_lines(storage: &mut dyn Storage, invoice: &Invoice, reverse: bool) -> StdResult<()> {
+    if invoice.lines.is_empty() {
+        return Ok(());
+    }
+    let (year, month, _) = parse_date(&invoice.date)?;
+    let month = month_index(year, month);
+    let rate = invoice.exchange_rate.unwrap_or_else(Decimal::one);
+
+    for line in &invoice.lines {
+        let (debit, credit) = (line.debit * rate, line.credit * rate);
+        let apply = |totals: Option<AccountTotals>| -> StdResult<AccountTotals> {
+            let mut totals = totals.unwrap_or_default();
+            if reverse {
+                totals.debit -= debit;
+                totals.credit -= credit;
+            } else {
+                totals.debit += debit;
+                totals.credit += credit;
+            }
+            Ok(totals)
+        };
+        account_totals(storage).update(line.account.as_bytes(), apply)?;
+        account_month_totals(storage).update(&account_month_key(month, &line.account), apply)?;
+    }
+
+    if !reverse {
+        let span = match ledger_span_read(storage).may_load()? {
+            Some(span) => LedgerSpan {
+                first_month: span.first_month.min(month),
+                last_month: span.last_month.max(month),
+            },
+            None => LedgerSpan {
+                first_month: month,
+                last_month: month,
+            },
+        };
+        ledger_span(storage).save(&span)?;
+    }
+    Ok(())
+}
+
+/// Adds the tax breakdown of an invoice to the input VAT totals of its month, or removes it when `reverse` is set.
+///
+/// Credit notes are added to the credited totals.
+fn post_vat(storage: &mut dyn Storage, invoice: &Invoice, invoice_index: u32, reverse: bool) -> StdResult<()> {
+    if invoice.tax_breakdown.is_empty() {
+        return Ok(());
+    }
+    let (year, month, _) = parse_date(&invoice.date)?;
+    let key = month_index(year, month).to_be_bytes();
+    let credit = invoice.kind == DocumentKind::CreditNote;
+
+    let mut month_totals = vat_months_read(storage).may_load(&key)?.unwrap_or_default();
+    for entry in &invoice.tax_breakdown {
+        let position = month_totals
+            .iter()
+            .position(|totals| totals.tax_code == entry.tax_code && totals.currency == invoice.currency);
+        let totals = match position {
+            Some(position) => &mut month_totals[position],
+            None => {
+                month_totals.push(VatTotals {
+                    tax_code: entry.tax_code.clone(),
+                    currency: invoice.currency.clone(),
+                    ..VatTotals::default()
+                });
+                month_totals.last_mut().unwrap()
+            }
+        };
+        let (base, tax) = if credit {
+            (&mut totals.credited_base, &mut totals.credited_tax)
+        } else {
+            (&mut totals.base, &mut totals.tax)
+        };
+        if reverse {
+            *base -= entry.base;
+            *tax -= entry.tax;
+            totals.invoices.retain(|index| *index != invoice_index);
+        } else {
+            *base += entry.base;
+            *tax += entry.tax;
+            if !totals.invoices.contains(&invoice_index) {
+                totals.invoices.push(invoice_index);
+            }
+        }
+    }
+    month_totals.retain(|totals| !totals.invoices.is_empty());
+    vat_months(storage).save(&key, &month_totals)
+}
+
+/// Numbers the months continuously, as `year * 12 + month - 1`.
+fn month_index(year: u32, month: u32) -> u32 {
+    year * 12 + month - 1
+}
+
+/// Returns the range of `month_index` covered by a fiscal period, which must be made of whole months.
+fn whole_months(period: &FiscalPeriod) -> StdResult<RangeInclusive<u32>> {
+    let (start_year, start_month, start_day) = parse_date(&period.start)?;
+    let (end_year, end_month, end_day) = parse_date(&period.end)?;
+    if start_day != 1 || end_day != days_in_month(end_year, end_month) {
+        return Err(StdError::generic_err(format!("Fiscal period {} is not made of whole months", period.name)));
+    }
+    Ok(month_index(start_year, start_month)..=month_index(end_year, end_month))
+}
+
+/// Parses a month written as `YYYY-MM` into its `month_index`.
+fn parse_period(period: &str) -> StdResult<u32> {
+    let invalid = || StdError::generic_err(format!("Invalid period: {}, expected YYYY-MM", period));
+    if period.len() != 7 {
+        return Err(invalid());
+    }
+    let (year, month, _) = parse_date(&format!("{}-01", period)).map_err(|_| invalid())?;
+    Ok(month_index(year, month))
+}
+
+/// Parses a decimal amount field of an invoice.
+fn parse_amount(field: &str, value: &str) -> StdResult<Decimal> {
+    Decimal::from_str(value).map_err(|_| StdError::generic_err(format!("Invalid {}: {}", field, value)))
+}
+
+/// Checks the format of the fields provided by the client before an invoice is sealed.
+///
+/// # Arguments
+///
+/// * `invoice` - The invoice to validate.
+///
+/// # Returns
+///
+/// A `StdResult<()>` with an error naming the first invalid field.
+fn validate_invoice(invoice: &Invoice) -> StdResult<()> {
+    validate_text("invoice_number", &invoice.invoice_number, true, MAX_INVOICE_NUMBER_LENGTH)?;
+    parse_date(&invoice.date)?;
+    validate_text("client_name", &invoice.client_name, true, MAX_NAME_LENGTH)?;
+    validate_text("description", &invoice.description, false, MAX_DESCRIPTION_LENGTH)?;
+    validate_text("total_amount", &invoice.total_amount, true, MAX_AMOUNT_LENGTH)?;
+    parse_amount("total_amount", &invoice.total_amount)?;
+    validate_text("tax_amount", &invoice.tax_amount, true, MAX_AMOUNT_LENGTH)?;
+    parse_amount("tax_amount", &invoice.tax_amount)?;
+    validate_currency(&invoice.currency)?;
+    validate_hash("doc_hash", &invoice.doc_hash)?;
+    validate_text("auditors", &invoice.auditors, false, MAX_NAME_LENGTH)?;
+    validate_credibility(invoice.credibility)?;
+    if let Some(due_date) = &invoice.due_date {
+        parse_date(due_date)?;
+        if *due_date < invoice.date {
+            return Err(StdError::generic_err("An Invoice cannot be due before its date"));
+        }
+    }
+    validate_lines(invoice)?;
+    if let Some(purchase_order) = &invoice.purchase_order {
+        validate_text("purchase_order", purchase_order, true, MAX_CODE_LENGTH)?;
+    }
+    for item in &invoice.items {
+        validate_item(item)?;
+    }
+    Ok(())
+}
+
+/// Checks that an item line of an invoice or a purchase order has a code, a quantity and a unit price.
+fn validate_item(line: &ItemLine) -> StdResult<()> {
+    validate_text("item", &line.item, true, MAX_CODE_LENGTH)?;
+    if line.quantity.is_zero() {
+        return Err(StdError::generic_err(format!("Invalid quantity of item {}", line.item)));
+    }
+    if line.unit_price.is_zero() {
+        return Err(StdError::generic_err(format!("Invalid unit price of item {}", line.item)));
+    }
+    Ok(())
+}
+
+/// Checks the journal lines of an invoice, when it has any.
+///
+/// Each line must either debit or credit its account, debits must equal credits and their total must equal
+/// `total_amount`. The lines carrying a tax code are the taxable base: together with `tax_amount` they must add up
+/// to `total_amount`.
+fn validate_lines(invoice: &Invoice) -> StdResult<()> {
+    if invoice.lines.is_empty() {
+        return Ok(());
+    }
+    if invoice.lines.len() > MAX_JOURNAL_LINES {
+        return Err(StdError::generic_err(format!(
+            "Too many journal lines: {}, the maximum is {}",
+            invoice.lines.len(),
+            MAX_JOURNAL_LINES
+        )));
+    }
+
+    let mut debits = Decimal::zero();
+    let mut credits = Decimal::zero();
+    let mut taxable_base = Decimal::zero();
+    for line in &invoice.lines {
+        validate_line(line)?;
+        debits += line.debit;
+        credits += line.credit;
+        if line.tax_code.is_some() {
+            taxable_base += line.debit + line.credit;
+        }
+    }
+
+    if debits != credits {
+        return Err(StdError::generic_err(format!(
+            "Journal lines are not balanced: debits {} and credits {}",
+            debits, credits
+        )));
+    }
+    let total_amount = parse_amount("total_amount", &invoice.total_amount)?;
+    if debits != total_amount {
+        return Err(StdError::generic_err(format!(
+            "Journal lines total {} does not match total_amount {}",
+            debits, total_amount
+        )));
+    }
+    let tax_amount = parse_amount("tax_amount", &invoice.tax_amount)?;
+    if taxable_base + tax_amount != total_amount {
+        return Err(StdError::generic_err(format!(
+            "Journal lines taxable base {} and tax_amount {} do not add up to total_amount {}",
+            taxable_base, tax_amount, total_amount
+        )));
+    }
+    Ok(())
+}
+
+/// Checks the fields of a single journal line.
+fn validate_line(line: &JournalLine) -> StdResult<()> {
+    validate_text("account", &line.account, true, MAX_CODE_LENGTH)?;
+    if let Some(tax_code) = &line.tax_code {
+        validate_text("tax_code", tax_code, true, MAX_CODE_LENGTH)?;
+    }
+    if let Some(cost_centre) = &line.cost_centre {
+        validate_text("cost_centre", cost_centre, true, MAX_CODE_LENGTH)?;
+    }
+    if line.debit.is_zero() == line.credit.is_zero() {
+        return Err(StdError::generic_err(format!(
+            "Journal line on account {} must have either a debit or a credit",
+            line.account
+        )));
+    }
+    Ok(())
+}
+
+/// Checks that a text field is not empty when required and does not exceed its maximum length.
+fn validate_text(field: &str, value: &str, required: bool, max_length: usize) -> StdResult<()> {
+    if required && value.trim().is_empty() {
+        return Err(StdError::generic_err(format!("Missing {}", field)));
+    }
+    if value.len() > max_length {
+        return Err(StdError::generic_err(format!(
+            "{} is too long: {} bytes, the maximum is {}",
+            field,
+            value.len(),
+            max_length
+        )));
+    }
+    Ok(())
+}
+
+/// Parses an ISO-8601 calendar date (`YYYY-MM-DD`) into its year, month and day.
+fn parse_date(date: &str) -> StdResult<(u32, u32, u32)> {
+    let invalid = || StdError::generic_err(format!("Invalid date: {}, expected YYYY-MM-DD", date));
+    let bytes = date.as_bytes();
+    if bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return Err(invalid());
+    }
+    let number = |range: std::ops::Range<usize>| -> StdResult<u32> {
+        let digits = &date[range];
+        if !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        digits.parse().map_err(|_| invalid())
+    };
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return Err(invalid());
+    }
+    Ok((year, month, day))
+}
+
+/// Returns the number of days from 1970-01-01 to a date of the proleptic Gregorian calendar.
+fn days_from_civil(year: u32, month: u32, day: u32) -> i64 {
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Returns the day of the current block, as `YYYY-MM-DD`.
+fn block_date(env: &Env) -> String {
+    let days = (env.block.time.seconds() / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Returns the number of days of a month in the proleptic Gregorian calendar.
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Checks that a currency is an ISO-4217 code made of three uppercase letters.
+fn validate_currency(currency: &str) -> StdResult<()> {
+    if currency.len() != 3 || !currency.bytes().all(|byte| byte.is_ascii_uppercase()) {
+        return Err(StdError::generic_err(format!(
+            "Invalid currency: {}, expected an ISO-4217 code such as USD",
+            currency
+        )));
+    }
+    Ok(())
+}
+
+/// Checks that a country is an ISO-3166 alpha-2 code.
+fn validate_country(country: &str) -> StdResult<()> {
+    if country.len() != 2 || !country.bytes().all(|byte| byte.is_ascii_uppercase()) {
+        return Err(StdError::generic_err(format!(
+            "Invalid country: {}, expected an ISO-3166 alpha-2 code such as FR",
+            country
+        )));
+    }
+    Ok(())
+}
+
+/// Checks that a hash is a SHA-256 digest written as 64 lowercase hexadecimal characters.
+fn validate_hash(field: &str, value: &str) -> StdResult<()> {
+    if value.len() != 64 || !value.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f')) {
+        return Err(StdError::generic_err(format!(
+            "Invalid {}: expected a SHA-256 hash as 64 lowercase hexadecimal characters",
+            field
+        )));
+    }
+    Ok(())
+}
+
+/// Checks that a credibility score is between 0 and `MAX_CREDIBILITY`.
+fn validate_credibility(credibility: CredibilityScore) -> StdResult<()> {
+    if credibility.0 > MAX_CREDIBILITY {
+        return Err(StdError::generic_err(format!(
+            "Invalid credibility: {}, expected a score between 0 and {}",
+            credibility.0, MAX_CREDIBILITY
+        )));
+    }
+    Ok(())
+}
+
+/// Computes the `line_hash` of an invoice.
+///
+/// The hash is the hex encoded SHA-256 of the accounting line fields followed by the document hash, the same
+/// concatenation the frontend uses, and of the JSON encoded journal lines when the invoice has any.
+pub fn compute_line_hash(invoice: &Invoice) -> StdResult<String> {
+    let mut data = format!(
+        "{}{}{}{}{}{}{}{}",
+        invoice.invoice_number,
+        invoice.date,
+        invoice.client_name,
+        invoice.description,
+        invoice.total_amount,
+        invoice.tax_amount,
+        invoice.currency,
+        invoice.doc_hash,
+    )
+    .into_bytes();
+    if !invoice.lines.is_empty() {
+        data.extend(to_vec(&invoice.lines)?);
+    }
+    if !invoice.items.is_empty() {
+        data.extend(to_vec(&invoice.items)?);
+    }
+    Ok(Sha256::digest(&data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// Attempts to update the auditor field of an existing invoice.
+///
+/// The auditor of an invoice dated in a closed or audited fiscal period can no longer be changed.
+///
+/// # Arguments
+///
+/// * `deps` - A mutable reference to the dependencies required by CosmWasm contracts.
+/// * `info` - Information about the message sender and other metadata.
+/// * `invoice_number` - The invoice number of the invoice to be updated.
+/// * `auditor_name` - The new auditor name to set.
+///
+/// # Returns
+///
+/// A `StdResult<Response>` indicating the success or failure of the update operation.
+pub fn try_update_auditor(
+    deps: DepsMut,
+    info: MessageInfo,
+    invoice_index: u32,
+    auditor: Addr,
+) -> Result<Response, StdError> {
+    let sender_address = info.sender.clone();
+    let state = config_read(deps.storage).load()?;
+    if sender_address != state.owner {
+        return Err(StdError::generic_err("Only the owner can update the auditor"));
+    }
+
+    let mut invoice = config_invoice_read(deps.storage, &invoice_key(invoice_index)).load()?;
+    check_period_open(deps.storage, &state, &invoice.date)?;
+    count_auditor(deps.storage, &invoice.auditors, true)?;
+    count_auditor(deps.storage, auditor.as_str(), false)?;
+    invoice.auditors = auditor.to_string();
+    config_invoice(deps.storage, &invoice_key(invoice_index)).save(&invoice)?;
+
+    deps.api.debug("auditor updated successfully");
+    Ok(Response::default())
+}
+/// Handles query messages to retrieve data from the contract's state.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `msg` - Message containing the query operation to be executed (get count, get all credentials).
+///
+/// # Returns
+///
+/// A `StdResult<QueryResponse>` indicating the success or failure of the query operation.
+#[entry_point]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<QueryResponse> {
+    match msg {
+        QueryMsg::GetCount {} => to_binary(&query_count(deps)?),
+        QueryMsg::GetCredibilityThresholds {} => to_binary(&query_credibility_thresholds(deps)?),
+        QueryMsg::GetCurrencyConfig {} => to_binary(&query_currency_config(deps)?),
+        QueryMsg::GetExchangeRate { from, to, date } => to_binary(&query_exchange_rate(deps, from, to, date)?),
+        QueryMsg::GetPublicAuditOpinion { period } => to_binary(&query_public_audit_opinion(deps, period)?),
+        QueryMsg::GetLedgerCommitment {} => to_binary(&query_ledger_commitment(deps)?),
+        QueryMsg::GetLedgerRootStatus { root } => to_binary(&query_ledger_root_status(deps, root)?),
+        QueryMsg::GetDisclosedInvoices { wallet, permit, index } => {
+            to_binary(&get_disclosed_invoices(deps, env, wallet, permit, index)?)
+        }
+        QueryMsg::GetAll {
+            wallet,
+            permit,
+            index,
+        } => to_binary(&get_all(deps, env, wallet, permit, index)?),
+        QueryMsg::GetAccounts {
+            wallet,
+            permit,
+            index,
+        } => to_binary(&get_accounts(deps, env, wallet, permit, index)?),
+        QueryMsg::GetTrialBalance {
+            wallet,
+            permit,
+            as_of,
+            period,
+        } => to_binary(&get_trial_balance(deps, env, wallet, permit, as_of, period)?),
+        QueryMsg::GetAccountBalance {
+            wallet,
+            permit,
+            account,
+            from,
+            to,
+        } => to_binary(&get_account_balance(deps, env, wallet, permit, account, from, to)?),
+        QueryMsg::GetInvoiceVersions {
+            wallet,
+            permit,
+            invoice_index,
+        } => to_binary(&get_invoice_versions(deps, env, wallet, permit, invoice_index)?),
+        QueryMsg::GetPayments {
+            wallet,
+            permit,
+            invoice_index,
+        } => to_binary(&get_payments(deps, env, wallet, permit, invoice_index)?),
+        QueryMsg::GetAgedPayables { wallet, permit, as_of } => {
+            to_binary(&get_aged_payables(deps, env, wallet, permit, as_of)?)
+        }
+        QueryMsg::GetPurchaseOrder { wallet, permit, order } => {
+            to_binary(&get_purchase_order(deps, env, wallet, permit, order)?)
+        }
+        QueryMsg::GetSuppliers {
+            wallet,
+            permit,
+            index,
+        } => to_binary(&get_suppliers(deps, env, wallet, permit, index)?),
+        QueryMsg::GetSupplierInvoices {
+            wallet,
+            permit,
+            supplier,
+            index,
+        } => to_binary(&get_supplier_invoices(deps, env, wallet, permit, supplier, index)?),
+        QueryMsg::GetTaxCodes {
+            wallet,
+            permit,
+            index,
+        } => to_binary(&get_tax_codes(deps, env, wallet, permit, index)?),
+        QueryMsg::GetVatReturn { wallet, permit, period } => {
+            to_binary(&get_vat_return(deps, env, wallet, permit, period)?)
+        }
+        QueryMsg::GetMerkleRoots { wallet, permit } => to_binary(&get_merkle_roots(deps, env, wallet, permit)?),
+        QueryMsg::GetInclusionProof {
+            wallet,
+            permit,
+            invoice_index,
+            leaf_count,
+            period_leaf_count,
+        } => to_binary(&get_inclusion_proof(deps, env, wallet, permit, invoice_index, leaf_count, period_leaf_count)?),
+        QueryMsg::GetFiscalPeriods { wallet, permit } => to_binary(&get_fiscal_periods(deps, env, wallet, permit)?),
+        QueryMsg::GetAnomalyStatistics { wallet, permit, period } => {
+            to_binary(&get_anomaly_statistics(deps, env, wallet, permit, period)?)
+        }
+        QueryMsg::GetAuditSample { wallet, permit, period } => {
+            to_binary(&get_audit_sample(deps, env, wallet, permit, period)?)
+        }
+        QueryMsg::GetAuditOpinion { wallet, permit, period } => {
+            to_binary(&get_audit_opinion(deps, env, wallet, permit, period)?)
+        }
+        QueryMsg::GetBudgetReport { wallet, permit, period } => {
+            to_binary(&get_budget_report(deps, env, wallet, permit, period)?)
+        }
+        QueryMsg::GetPendingApprovals { wallet, permit } => {
+            to_binary(&get_pending_approvals(deps, env, wallet, permit)?)
+        }
+    }
+}
+
+/// Retrieves the current count value from the contract's state.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+///
+/// # Returns
+///
+/// A `StdResult<CountResponse>` containing the current count value.
+fn query_count(deps: Deps) -> StdResult<CountResponse> {
+    let state = config_read(deps.storage).load()?;
+    Ok(CountResponse { count: state.count })
+}
+
+
+/// Retrieves the credibility thresholds, or their defaults when the owner never set them.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+///
+/// # Returns
+///
+/// A `StdResult<CredibilityThresholdsResponse>` containing the thresholds.
+fn query_credibility_thresholds(deps: Deps) -> StdResult<CredibilityThresholdsResponse> {
+    let thresholds = credibility_thresholds_read(deps.storage).may_load()?.unwrap_or_default();
+    Ok(CredibilityThresholdsResponse { thresholds })
+}
+
+/// Retrieves the audit opinion issued on a fiscal period, when the auditor made it public.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `period` - The name of the fiscal period.
+///
+/// # Returns
+///
+/// A `StdResult<AuditOpinionResponse>` containing the opinion.
+fn query_public_audit_opinion(deps: Deps, period: String) -> StdResult<AuditOpinionResponse> {
+    match audit_opinions_read(deps.storage).may_load(period.as_bytes())? {
+        Some(opinion) if opinion.public => Ok(AuditOpinionResponse { opinion }),
+        _ => Err(StdError::generic_err(format!("No public audit opinion for fiscal period {}", period))),
+    }
+}
+
+/// Retrieves the opaque commitments to the ledger, without a permit.
+///
+/// Only the Merkle root, the number of invoices and the last sealing time are exposed, so that anyone can hold on to
+/// the state of the ledger without learning amounts, client names or how many amendments were made.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+///
+/// # Returns
+///
+/// A `StdResult<LedgerCommitmentResponse>` containing the commitments.
+fn query_ledger_commitment(deps: Deps) -> StdResult<LedgerCommitmentResponse> {
+    let state = config_read(deps.storage).load()?;
+    let ledger = merkle_trees_read(deps.storage).may_load(LEDGER_TREE)?.unwrap_or_default();
+    Ok(LedgerCommitmentResponse {
+        root: ledger.root,
+        invoice_count: state.invoice_count,
+        last_sealed_at: state.last_sealed_at,
+    })
+}
+
+/// Retrieves whether a root is one the ledger Merkle tree had, without a permit.
+///
+/// The tree is append-only, so every later root commits to the leaves of a recorded root: a root kept by a bank or a
+/// regulator that is still recorded shows that the ledger was only extended since, and never rewritten. The number of
+/// leaves behind the root is not revealed.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `root` - The hex-encoded root.
+///
+/// # Returns
+///
+/// A `StdResult<LedgerRootStatusResponse>` containing when the root was sealed, if ever, and whether it is current.
+fn query_ledger_root_status(deps: Deps, root: String) -> StdResult<LedgerRootStatusResponse> {
+    let root = root.to_lowercase();
+    let ledger = merkle_trees_read(deps.storage).may_load(LEDGER_TREE)?.unwrap_or_default();
+    Ok(LedgerRootStatusResponse {
+        sealed_at: ledger_roots_read(deps.storage).may_load(root.as_bytes())?,
+        current: !root.is_empty() && root == ledger.root,
+    })
+}
+
+/// Retrieves the base currency and the exchange-rate oracle.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+///
+/// # Returns
+///
+/// A `StdResult<CurrencyConfigResponse>` containing the currency configuration, if set.
+fn query_currency_config(deps: Deps) -> StdResult<CurrencyConfigResponse> {
+    let config = currency_config_read(deps.storage).may_load()?;
+    Ok(CurrencyConfigResponse { config })
+}
+
+/// Retrieves a recorded exchange rate.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `from` - The currency converted from.
+/// * `to` - The currency converted to.
+/// * `date` - The day the rate applies to, as `YYYY-MM-DD`.
+///
+/// # Returns
+///
+/// A `StdResult<ExchangeRateResponse>` containing the exchange rate.
+fn query_exchange_rate(deps: Deps, from: String, to: String, date: String) -> StdResult<ExchangeRateResponse> {
+    let rate = exchange_rates_read(deps.storage)
+        .may_load(&exchange_rate_key(&from, &to, &date))?
+        .ok_or_else(|| StdError::generic_err(format!("No exchange rate from {} to {} on {}", from, to, date)))?;
+    Ok(ExchangeRateResponse { rate })
+}
+
+/// Returns the base currency of the ledger, if set.
+fn base_currency(storage: &dyn Storage) -> StdResult<Option<String>> {
+    Ok(currency_config_read(storage).may_load()?.map(|config| config.base_currency))
+}
+
+/// Validates a permit and checks that it was signed by the given wallet.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet the query is made for.
+/// * `permit` - The permit used to authenticate the query.
+///
+/// # Returns
+///
+/// A `StdResult<()>` indicating whether the permit is valid for the wallet.
+fn authenticate(deps: Deps, env: &Env, wallet: &Addr, permit: &Permit) -> StdResult<()> {
+    let contract_address = env.contract.address.clone();
+    let viewer = validate(
+        deps,
+        PREFIX_REVOKED_PERMITS,
+        permit,
+        contract_address.to_string(),
+        None,
+    )?;
+
+    if viewer != wallet.as_str() {
+        return Err(StdError::generic_err("The permit was not signed by the given wallet"));
+    }
+    Ok(())
+}
+
+/// Retrieves all invoices for a given wallet, validated by a permit.
+///
+/// The owner sees every invoice, an auditor only the invoices assigned to them.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet for which invoices are being retrieved.
+/// * `permit` - The permit used to validate access to the invoices.
+/// * `index` - The index at which to start retrieving invoices.
+///
+/// # Returns
+///
+/// A `StdResult<InvoiceListResponse>` containing the list of invoices.
+fn get_all(
+    deps: Deps,
+    env: Env,
+    wallet: Addr,
+    permit: Permit,
+    index: u32,
+) -> StdResult<InvoiceListResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+
+    let state = config_read(deps.storage).load()?;
+    let end = state.invoice_count.min(index.saturating_add(PAGE_SIZE));
+
+    let mut vect_invoice = vec![];
+    for invoice_index in index..end {
+        let invoice = config_invoice_read(deps.storage, &invoice_key(invoice_index)).load()?;
+        if wallet == state.owner || wallet == invoice.auditors {
+            vect_invoice.push(invoice);
+        }
+    }
+
+    Ok(InvoiceListResponse { vect_invoice })
+}
+
+/// Retrieves a page of the invoices disclosed to a wallet by its disclosure grant, validated by a permit.
+///
+/// The fields the grant does not reveal are replaced by their salted hashes, so that the wallet can check the fields
+/// it sees against the field commitment sealed with the `line_hash`. Invoices sealed before field commitments were
+/// kept are left out.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet the invoices are disclosed to.
+/// * `permit` - The permit used to validate access to the invoices.
+/// * `index` - An index to paginate through the invoices.
+///
+/// # Returns
+///
+/// A `StdResult<DisclosedInvoicesResponse>` containing the disclosed invoices.
+fn get_disclosed_invoices(
+    deps: Deps,
+    env: Env,
+    wallet: Addr,
+    permit: Permit,
+    index: u32,
+) -> StdResult<DisclosedInvoicesResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let grant = disclosure_grants_read(deps.storage)
+        .may_load(wallet.as_bytes())?
+        .ok_or_else(|| StdError::generic_err("No disclosure grant for this address"))?;
+
+    let state = config_read(deps.storage).load()?;
+    let end = state.invoice_count.min(index.saturating_add(PAGE_SIZE));
+    let mut invoices = vec![];
+    for invoice_index in index..end {
+        if grant.invoices.as_ref().is_some_and(|granted| !granted.contains(&invoice_index)) {
+            continue;
+        }
+        let invoice = config_invoice_read(deps.storage, &invoice_key(invoice_index)).load()?;
+        let key = invoice_version_key(invoice_index, invoice.version);
+        let salt = match disclosure_salts_read(deps.storage).may_load(&key)? {
+            Some(salt) => salt,
+            None => continue,
+        };
+        invoices.push(DisclosedInvoice {
+            invoice_index,
+            version: invoice.version,
+            fields: disclosed_fields(&invoice, salt.as_slice(), &grant.fields)?,
+            line_hash: invoice.line_hash,
+            field_commitment: invoice.field_commitment,
+            voided: invoice.voided,
+        });
+    }
+    Ok(DisclosedInvoicesResponse { invoices })
+}
+
+/// Adds an invoice to, or releases it from, the count of invoices assigned to an auditor.
+///
+/// The auditor stays engaged, with read access to the whole ledger, while the count is positive.
+fn count_auditor(storage: &mut dyn Storage, auditor: &str, release: bool) -> StdResult<()> {
+    if auditor.is_empty() {
+        return Ok(());
+    }
+    let count = auditors_read(storage).may_load(auditor.as_bytes())?.unwrap_or(0);
+    if !release {
+        auditors(storage).save(auditor.as_bytes(), &(count + 1))
+    } else if count <= 1 {
+        auditors(storage).remove(auditor.as_bytes());
+        Ok(())
+    } else {
+        auditors(storage).save(auditor.as_bytes(), &(count - 1))
+    }
+}
+
+/// Checks that a wallet is the owner or one of the engaged auditors.
+fn check_owner_or_auditor(deps: Deps, state: &State, wallet: &Addr) -> StdResult<()> {
+    if *wallet != state.owner && auditors_read(deps.storage).may_load(wallet.as_bytes())?.is_none() {
+        return Err(StdError::generic_err("Only the Owner or an Auditor can run this query"));
+    }
+    Ok(())
+}
+
+/// Checks whether a wallet is an engaged auditor of a fiscal period: the auditor who signed off its closing or the
+/// current auditor of one of its invoices.
+fn is_period_auditor(storage: &dyn Storage, period: &FiscalPeriod, wallet: &Addr) -> StdResult<bool> {
+    if auditors_read(storage).may_load(wallet.as_bytes())?.is_none() {
+        return Ok(false);
+    }
+    if period.auditor_signoff.as_ref() == Some(wallet) {
+        return Ok(true);
+    }
+    Ok(period_invoices(storage, period)?.iter().any(|(_, invoice)| invoice.auditors == wallet.as_str()))
+}
+
+/// Checks that the wallet of a query is an engaged auditor other than the owner.
+fn check_auditor(deps: Deps, state: &State, wallet: &Addr) -> StdResult<()> {
+    if *wallet == state.owner || auditors_read(deps.storage).may_load(wallet.as_bytes())?.is_none() {
+        return Err(StdError::generic_err("Only an Auditor can run this query"));
+    }
+    Ok(())
+}
+
+/// Retrieves the payments and the settlement status of an invoice, validated by a permit.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the payments.
+/// * `permit` - The permit used to validate access to the invoice.
+/// * `invoice_index` - The index of the invoice.
+///
+/// # Returns
+///
+/// A `StdResult<PaymentsResponse>` containing the payments, the outstanding balance and the status of the invoice.
+fn get_payments(deps: Deps, env: Env, wallet: Addr, permit: Permit, invoice_index: u32) -> StdResult<PaymentsResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let state = config_read(deps.storage).load()?;
+    let invoice = config_invoice_read(deps.storage, &invoice_key(invoice_index)).load()?;
+    if wallet != state.owner && wallet != invoice.auditors {
+        return Err(StdError::generic_err("Only the Owner or the Auditor of the Invoice can run this query"));
+    }
+
+    let mut payments = vec![];
+    for position in 0..invoice.payment_count {
+        payments.push(payments_read(deps.storage).load(&payment_key(invoice_index, position))?);
+    }
+    Ok(PaymentsResponse {
+        payments,
+        paid_amount: invoice.paid_amount,
+        outstanding: outstanding(&invoice)?,
+        status: payment_status(&invoice, &block_date(&env))?,
+    })
+}
+
+/// Retrieves the outstanding balances totalled by age, validated by a permit.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the aged payables.
+/// * `permit` - The permit used to validate access to the ledger.
+/// * `as_of` - The day the balances are aged at, the day of the current block if not set.
+///
+/// # Returns
+///
+/// A `StdResult<AgedPayablesResponse>` containing the outstanding balances by age.
+fn get_aged_payables(
+    deps: Deps,
+    env: Env,
+    wallet: Addr,
+    permit: Permit,
+    as_of: Option<String>,
+) -> StdResult<AgedPayablesResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let state = config_read(deps.storage).load()?;
+    check_owner_or_auditor(deps, &state, &wallet)?;
+
+    let as_of = as_of.unwrap_or_else(|| block_date(&env));
+    let (year, month, day) = parse_date(&as_of)?;
+    let today = days_from_civil(year, month, day);
+    let mut response = AgedPayablesResponse {
+        as_of,
+        currency: base_currency(deps.storage)?,
+        not_due: Decimal::zero(),
+        overdue_1_to_30: Decimal::zero(),
+        overdue_31_to_60: Decimal::zero(),
+        overdue_61_to_90: Decimal::zero(),
+        overdue_over_90: Decimal::zero(),
+        total: Decimal::zero(),
+        payables: vec![],
+    };
+    let mut open = vec![];
+    for page in 0..=state.invoice_count / OPEN_INDEX_PAGE {
+        open.extend(open_payables_read(deps.storage).may_load(&page.to_be_bytes())?.unwrap_or_default());
+    }
+    for invoice_index in open {
+        let invoice = config_invoice_read(deps.storage, &invoice_key(invoice_index)).load()?;
+        let amount = outstanding(&invoice)? * invoice.exchange_rate.unwrap_or_else(Decimal::one);
+        let due_date = invoice.due_date.clone().unwrap_or_else(|| invoice.date.clone());
+        let (year, month, day) = parse_date(&due_date)?;
+        let days_overdue = (today - days_from_civil(year, month, day)).max(0) as u32;
+        let bucket = match days_overdue {
+            0 => &mut response.not_due,
+            1..=30 => &mut response.overdue_1_to_30,
+            31..=60 => &mut response.overdue_31_to_60,
+            61..=90 => &mut response.overdue_61_to_90,
+            _ => &mut response.overdue_over_90,
+        };
+        *bucket += amount;
+        response.total += amount;
+        response.payables.push(AgedPayable {
+            invoice_index,
+            supplier: invoice.supplier,
+            due_date,
+            days_overdue,
+            outstanding: amount,
+        });
+    }
+    Ok(response)
+}
+
+/// Retrieves a purchase order, its goods receipts and the match results of its invoices, validated by a permit.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the purchase order.
+/// * `permit` - The permit used to validate access to the purchase order.
+/// * `number` - The number of the purchase order.
+///
+/// # Returns
+///
+/// A `StdResult<PurchaseOrderResponse>` containing the purchase order and its goods receipts.
+fn get_purchase_order(
+    deps: Deps,
+    env: Env,
+    wallet: Addr,
+    permit: Permit,
+    number: String,
+) -> StdResult<PurchaseOrderResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let state = config_read(deps.storage).load()?;
+    check_owner_or_auditor(deps, &state, &wallet)?;
+
+    let order = purchase_orders_read(deps.storage)
+        .may_load(number.as_bytes())?
+        .ok_or_else(|| StdError::generic_err(format!("Unknown purchase order: {}", number)))?;
+    let receipts = (0..order.receipt_count)
+        .map(|position| goods_receipts_read(deps.storage).load(&goods_receipt_key(&number, position)))
+        .collect::<StdResult<Vec<_>>>()?;
+    let match_results = order
+        .invoices
+        .iter()
+        .map(|index| Ok(config_invoice_read(deps.storage, &invoice_key(*index)).load()?.match_result))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(PurchaseOrderResponse {
+        order,
+        receipts,
+        match_results,
+        tolerance: match_tolerance_read(deps.storage).may_load()?.unwrap_or_default(),
+    })
+}
+
+/// Retrieves a page of the supplier registry, validated by a permit.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the suppliers.
+/// * `permit` - The permit used to validate access to the suppliers.
+/// * `index` - The index at which to start retrieving suppliers.
+///
+/// # Returns
+///
+/// A `StdResult<SupplierListResponse>` containing the page of suppliers.
+fn get_suppliers(deps: Deps, env: Env, wallet: Addr, permit: Permit, index: u32) -> StdResult<SupplierListResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let state = config_read(deps.storage).load()?;
+    check_owner_or_auditor(deps, &state, &wallet)?;
+
+    let ids = supplier_ids_read(deps.storage);
+    let registry = suppliers_read(deps.storage);
+    let end = state.supplier_count.min(index.saturating_add(PAGE_SIZE));
+    let mut suppliers = vec![];
+    for supplier_index in index..end {
+        let id = ids.load(&supplier_index.to_be_bytes())?;
+        suppliers.push(registry.load(id.as_bytes())?);
+    }
+
+    Ok(SupplierListResponse {
+        suppliers,
+        total: state.supplier_count,
+    })
+}
+
+/// Retrieves a page of the invoices of a supplier and its totals, validated by a permit.
+///
+/// The owner sees every invoice of the supplier, an auditor only the invoices assigned to them.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the invoices.
+/// * `permit` - The permit used to validate access to the invoices.
+/// * `supplier` - The id of the supplier.
+/// * `index` - The position at which to start retrieving the invoices of the supplier.
+///
+/// # Returns
+///
+/// A `StdResult<SupplierInvoicesResponse>` containing the supplier, its totals and the page of invoices.
+fn get_supplier_invoices(
+    deps: Deps,
+    env: Env,
+    wallet: Addr,
+    permit: Permit,
+    supplier: String,
+    index: u32,
+) -> StdResult<SupplierInvoicesResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let state = config_read(deps.storage).load()?;
+    check_owner_or_auditor(deps, &state, &wallet)?;
+
+    let supplier = suppliers_read(deps.storage)
+        .may_load(supplier.as_bytes())?
+        .ok_or_else(|| StdError::generic_err(format!("Unknown supplier: {}", supplier)))?;
+    let totals = supplier_totals_read(deps.storage).may_load(supplier.id.as_bytes())?.unwrap_or_default();
+    let end = totals.invoice_count.min(index.saturating_add(PAGE_SIZE));
+    let mut invoice_indexes = vec![];
+    let mut vect_invoice = vec![];
+    for position in index..end {
+        let invoice_index = supplier_invoices_read(deps.storage).load(&supplier_invoice_key(&supplier.id, position))?;
+        let invoice = config_invoice_read(deps.storage, &invoice_key(invoice_index)).load()?;
+        if wallet == state.owner || wallet == invoice.auditors {
+            invoice_indexes.push(invoice_index);
+            vect_invoice.push(invoice);
+        }
+    }
+
+    Ok(SupplierInvoicesResponse {
+        supplier,
+        totals,
+        invoice_indexes,
+        vect_invoice,
+    })
+}
+
+/// Retrieves a page of the tax codes, validated by a permit.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the tax codes.
+/// * `permit` - The permit used to validate access to the tax codes.
+/// * `index` - The index at which to start retrieving tax codes.
+///
+/// # Returns
+///
+/// A `StdResult<TaxCodeListResponse>` containing the page of tax codes and the rounding tolerance.
+fn get_tax_codes(deps: Deps, env: Env, wallet: Addr, permit: Permit, index: u32) -> StdResult<TaxCodeListResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let state = config_read(deps.storage).load()?;
+    check_owner_or_auditor(deps, &state, &wallet)?;
+
+    let index_bucket = tax_code_index_read(deps.storage);
+    let codes = tax_codes_read(deps.storage);
+    let end = state.tax_code_count.min(index.saturating_add(PAGE_SIZE));
+    let mut tax_codes = vec![];
+    for code_index in index..end {
+        let code = index_bucket.load(&code_index.to_be_bytes())?;
+        tax_codes.push(codes.load(code.as_bytes())?);
+    }
+
+    Ok(TaxCodeListResponse {
+        tax_codes,
+        total: state.tax_code_count,
+        tolerance: load_tax_tolerance(deps.storage)?,
+    })
+}
+
+/// Retrieves the input VAT of a fiscal period by tax code and currency, validated by a permit.
+///
+/// The totals are kept per month, so the period must start on the first day of a month and end on the last day of a
+/// month.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the VAT return.
+/// * `permit` - The permit used to validate access to the ledger.
+/// * `period` - The name of the fiscal period.
+///
+/// # Returns
+///
+/// A `StdResult<VatReturnResponse>` containing the input VAT totals of the period.
+fn get_vat_return(deps: Deps, env: Env, wallet: Addr, permit: Permit, period: String) -> StdResult<VatReturnResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let state = config_read(deps.storage).load()?;
+    check_owner_or_auditor(deps, &state, &wallet)?;
+
+    let fiscal_period = fiscal_periods_read(deps.storage)
+        .may_load(period.as_bytes())?
+        .ok_or_else(|| StdError::generic_err(format!("Unknown fiscal period: {}", period)))?;
+    let mut totals: Vec<VatTotals> = vec![];
+    let months = vat_months_read(deps.storage);
+    for month in whole_months(&fiscal_period)? {
+        for month_totals in months.may_load(&month.to_be_bytes())?.unwrap_or_default() {
+            match totals
+                .iter_mut()
+                .find(|entry| entry.tax_code == month_totals.tax_code && entry.currency == month_totals.currency)
+            {
+                Some(entry) => {
+                    entry.base += month_totals.base;
+                    entry.tax += month_totals.tax;
+                    entry.credited_base += month_totals.credited_base;
+                    entry.credited_tax += month_totals.credited_tax;
+                    entry.invoices.extend(month_totals.invoices);
+                }
+                None => totals.push(month_totals),
+            }
+        }
+    }
+    totals.sort_by(|a, b| (&a.tax_code, &a.currency).cmp(&(&b.tax_code, &b.currency)));
+    for entry in &mut totals {
+        entry.invoices.sort_unstable();
+    }
+
+    Ok(VatReturnResponse { period, totals })
+}
+
+/// Retrieves the invoices waiting for approval and the approval rules, validated by a permit.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the pending approvals.
+/// * `permit` - The permit used to validate access to the pending approvals.
+///
+/// # Returns
+///
+/// A `StdResult<PendingApprovalsResponse>` containing the invoices waiting for approval in submission order.
+fn get_pending_approvals(deps: Deps, env: Env, wallet: Addr, permit: Permit) -> StdResult<PendingApprovalsResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let state = config_read(deps.storage).load()?;
+    if wallet != state.owner && approvers_read(deps.storage).may_load(wallet.as_bytes())?.is_none() {
+        return Err(StdError::generic_err("Only the Owner or an Approver can run this query"));
+    }
+
+    let mut pending_indexes = vec![];
+    for page in 0..=state.pending_count / OPEN_INDEX_PAGE {
+        pending_indexes.extend(open_approvals_read(deps.storage).may_load(&page.to_be_bytes())?.unwrap_or_default());
+    }
+    let pending = pending_indexes
+        .iter()
+        .map(|index| pending_invoices_read(deps.storage).load(&index.to_be_bytes()))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(PendingApprovalsResponse {
+        pending_indexes,
+        pending,
+        rules: approval_rules_read(deps.storage).may_load()?.unwrap_or_default(),
+    })
+}
+
+/// Retrieves the budget against the actual spend of every expense category over a fiscal period, validated by a
+/// permit.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the report.
+/// * `permit` - The permit used to validate access to the report.
+/// * `period` - The name of the fiscal period.
+///
+/// # Returns
+///
+/// A `StdResult<BudgetReportResponse>` containing a line per expense category.
+fn get_budget_report(
+    deps: Deps,
+    env: Env,
+    wallet: Addr,
+    permit: Permit,
+    period: String,
+) -> StdResult<BudgetReportResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let state = config_read(deps.storage).load()?;
+    check_owner_or_auditor(deps, &state, &wallet)?;
+    if fiscal_periods_read(deps.storage).may_load(period.as_bytes())?.is_none() {
+        return Err(StdError::generic_err(format!("Unknown fiscal period: {}", period)));
+    }
+
+    let mut lines = vec![];
+    for category_index in 0..state.category_count {
+        let code = expense_category_codes_read(deps.storage).load(&category_index.to_be_bytes())?;
+        let category = expense_categories_read(deps.storage).load(code.as_bytes())?;
+        let budget = category_budgets_read(deps.storage)
+            .may_load(&category_budget_key(&code, &period))?
+            .unwrap_or_default();
+        lines.push(BudgetLine {
+            category,
+            budget: budget.budget,
+            spent: budget.spent,
+            overrun: budget.budget.is_some_and(|limit| budget.spent > limit),
+        });
+    }
+
+    Ok(BudgetReportResponse {
+        period,
+        currency: base_currency(deps.storage)?,
+        lines,
+    })
+}
+
+/// Retrieves the first-digit distribution and the anomaly statistics of the invoices of a fiscal period, validated by
+/// a permit.
+///
+/// The statistics are summed from the counters of each month of the period, kept up to date by `Add`, `AmendInvoice`
+/// and `VoidInvoice`, so the query does not load any invoice.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the statistics.
+/// * `permit` - The permit used to validate access to the statistics.
+/// * `period` - The name of the fiscal period.
+///
+/// # Returns
+///
+/// A `StdResult<AnomalyStatisticsResponse>` containing the counts and shares of the period.
+fn get_anomaly_statistics(
+    deps: Deps,
+    env: Env,
+    wallet: Addr,
+    permit: Permit,
+    period: String,
+) -> StdResult<AnomalyStatisticsResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let state = config_read(deps.storage).load()?;
+    check_auditor(deps, &state, &wallet)?;
+    let fiscal_period = fiscal_periods_read(deps.storage)
+        .may_load(period.as_bytes())?
+        .ok_or_else(|| StdError::generic_err(format!("Unknown fiscal period: {}", period)))?;
+
+    let mut totals = AnomalyCounters {
+        first_digits: vec![0; 9],
+        ..Default::default()
+    };
+    for month in whole_months(&fiscal_period)? {
+        if let Some(counters) = anomaly_months_read(deps.storage).may_load(&month.to_be_bytes())? {
+            totals.invoices += counters.invoices;
+            for (total, count) in totals.first_digits.iter_mut().zip(counters.first_digits) {
+                *total += count;
+            }
+            totals.round_amounts += counters.round_amounts;
+            totals.weekend_dates += counters.weekend_dates;
+            totals.near_threshold += counters.near_threshold;
+        }
+    }
+
+    let share = |count: u32| {
+        if totals.invoices == 0 {
+            Decimal::zero()
+        } else {
+            Decimal::from_ratio(count, totals.invoices)
+        }
+    };
+    Ok(AnomalyStatisticsResponse {
+        period,
+        invoices: totals.invoices,
+        first_digit_shares: totals.first_digits.iter().map(|count| share(*count)).collect(),
+        first_digits: totals.first_digits.clone(),
+        benford_shares: BENFORD_PERMILLE.iter().map(|permille| Decimal::permille(*permille)).collect(),
+        round_amounts: totals.round_amounts,
+        round_amount_share: share(totals.round_amounts),
+        weekend_dates: totals.weekend_dates,
+        weekend_share: share(totals.weekend_dates),
+        near_threshold: totals.near_threshold,
+        near_threshold_share: share(totals.near_threshold),
+    })
+}
+
+/// Retrieves the audit sample of a fiscal period and the invoices drawn, validated by a permit.
+///
+/// Only the auditors engaged on the period can run it, so that the auditee does not learn which invoices will be
+/// tested.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the sample.
+/// * `permit` - The permit used to validate access to the sample.
+/// * `period` - The name of the fiscal period.
+///
+/// # Returns
+///
+/// A `StdResult<AuditSampleResponse>` containing the sample and the invoices drawn.
+fn get_audit_sample(
+    deps: Deps,
+    env: Env,
+    wallet: Addr,
+    permit: Permit,
+    period: String,
+) -> StdResult<AuditSampleResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let state = config_read(deps.storage).load()?;
+    check_auditor(deps, &state, &wallet)?;
+    let fiscal_period = fiscal_periods_read(deps.storage)
+        .may_load(period.as_bytes())?
+        .ok_or_else(|| StdError::generic_err(format!("Unknown fiscal period: {}", period)))?;
+    if !is_period_auditor(deps.storage, &fiscal_period, &wallet)? {
+        return Err(StdError::generic_err(format!("Only an Auditor of fiscal period {} can run this query", period)));
+    }
+
+    let sample = audit_samples_read(deps.storage)
+        .may_load(period.as_bytes())?
+        .ok_or_else(|| StdError::generic_err(format!("Fiscal period {} is not sampled", period)))?;
+    let vect_invoice = sample
+        .invoices
+        .iter()
+        .map(|index| config_invoice_read(deps.storage, &invoice_key(*index)).load())
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(AuditSampleResponse { sample, vect_invoice })
+}
+
+/// Retrieves the audit opinion issued on a fiscal period, validated by a permit.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the opinion.
+/// * `permit` - The permit used to validate access to the opinion.
+/// * `period` - The name of the fiscal period.
+///
+/// # Returns
+///
+/// A `StdResult<AuditOpinionResponse>` containing the opinion.
+fn get_audit_opinion(
+    deps: Deps,
+    env: Env,
+    wallet: Addr,
+    permit: Permit,
+    period: String,
+) -> StdResult<AuditOpinionResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let state = config_read(deps.storage).load()?;
+    check_owner_or_auditor(deps, &state, &wallet)?;
+
+    let opinion = audit_opinions_read(deps.storage)
+        .may_load(period.as_bytes())?
+        .ok_or_else(|| StdError::generic_err(format!("No audit opinion for fiscal period {}", period)))?;
+    Ok(AuditOpinionResponse { opinion })
+}
+
+/// Retrieves the Merkle roots of the ledger and of every fiscal period, validated by a permit.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the roots.
+/// * `permit` - The permit used to validate access to the roots.
+///
+/// # Returns
+///
+/// A `StdResult<MerkleRootsResponse>` containing the size and root of each tree.
+fn get_merkle_roots(deps: Deps, env: Env, wallet: Addr, permit: Permit) -> StdResult<MerkleRootsResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let state = config_read(deps.storage).load()?;
+    check_owner_or_auditor(deps, &state, &wallet)?;
+
+    let ledger = merkle_trees_read(deps.storage).may_load(LEDGER_TREE)?.unwrap_or_default();
+    let mut periods = vec![];
+    for period_index in 0..state.period_count {
+        let period = fiscal_period_names_read(deps.storage).load(&period_index.to_be_bytes())?;
+        let tree = merkle_trees_read(deps.storage).may_load(period.as_bytes())?.unwrap_or_default();
+        periods.push(PeriodRoot { period, tree });
+    }
+    Ok(MerkleRootsResponse { ledger, periods })
+}
+
+/// Retrieves the proofs that the latest version of an invoice is included in the Merkle roots of the ledger and of
+/// the fiscal period it was dated in when sealed, validated by a permit.
+///
+/// The proofs only hold hashes, so that a single invoice can be shown to belong to a published root without
+/// revealing the rest of the ledger. Each proof can target the root a tree had at an earlier size.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the proof.
+/// * `permit` - The permit used to validate access to the proof.
+/// * `invoice_index` - The index of the invoice.
+/// * `leaf_count` - The size of the ledger tree to prove against, its current size if not set.
+/// * `period_leaf_count` - The size of the period tree to prove against, its current size if not set.
+///
+/// # Returns
+///
+/// A `StdResult<InclusionProofResponse>` containing the leaf and the proofs.
+fn get_inclusion_proof(
+    deps: Deps,
+    env: Env,
+    wallet: Addr,
+    permit: Permit,
+    invoice_index: u32,
+    leaf_count: Option<u32>,
+    period_leaf_count: Option<u32>,
+) -> StdResult<InclusionProofResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let state = config_read(deps.storage).load()?;
+    check_owner_or_auditor(deps, &state, &wallet)?;
+
+    let invoice = config_invoice_read(deps.storage, &invoice_key(invoice_index)).load()?;
+    let ledger_leaf = invoice
+        .ledger_leaf
+        .ok_or_else(|| StdError::generic_err("Invoice was sealed before the Merkle tree was kept"))?;
+    let period_proof = match (&ledger_leaf.period, ledger_leaf.period_leaf) {
+        (Some(period), Some(leaf)) => Some(merkle_proof(deps.storage, period.as_bytes(), leaf, period_leaf_count)?),
+        _ => None,
+    };
+    Ok(InclusionProofResponse {
+        invoice_index,
+        leaf_hash: merkle_leaf_hash(&invoice.line_hash, &invoice.field_commitment)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect(),
+        line_hash: invoice.line_hash,
+        field_commitment: invoice.field_commitment,
+        ledger: merkle_proof(deps.storage, LEDGER_TREE, ledger_leaf.leaf, leaf_count)?,
+        period: ledger_leaf.period,
+        period_proof,
+    })
+}
+
+/// Retrieves the fiscal periods, validated by a permit.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the periods.
+/// * `permit` - The permit used to validate access to the periods.
+///
+/// # Returns
+///
+/// A `StdResult<FiscalPeriodListResponse>` containing the periods in creation order.
+fn get_fiscal_periods(deps: Deps, env: Env, wallet: Addr, permit: Permit) -> StdResult<FiscalPeriodListResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let state = config_read(deps.storage).load()?;
+    check_owner_or_auditor(deps, &state, &wallet)?;
+
+    let mut periods = vec![];
+    for period_index in 0..state.period_count {
+        let name = fiscal_period_names_read(deps.storage).load(&period_index.to_be_bytes())?;
+        periods.push(fiscal_periods_read(deps.storage).load(name.as_bytes())?);
+    }
+    Ok(FiscalPeriodListResponse { periods })
+}
+
+/// Retrieves a page of the chart of accounts, validated by a permit.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the accounts.
+/// * `permit` - The permit used to validate access to the accounts.
+/// * `index` - The index at which to start retrieving accounts.
+///
+/// # Returns
+///
+/// A `StdResult<AccountListResponse>` containing the page of accounts.
+fn get_accounts(
+    deps: Deps,
+    env: Env,
+    wallet: Addr,
+    permit: Permit,
+    index: u32,
+) -> StdResult<AccountListResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let state = config_read(deps.storage).load()?;
+    check_owner_or_auditor(deps, &state, &wallet)?;
+
+    let codes = account_codes_read(deps.storage);
+    let chart = accounts_read(deps.storage);
+    let end = state.account_count.min(index.saturating_add(PAGE_SIZE));
+    let mut accounts = vec![];
+    for account_index in index..end {
+        let code = codes.load(&account_index.to_be_bytes())?;
+        accounts.push(chart.load(code.as_bytes())?);
+    }
+
+    Ok(AccountListResponse {
+        accounts,
+        total: state.account_count,
+    })
+}
+
+/// Retrieves the trial balance, validated by a permit.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the trial balance.
+/// * `permit` - The permit used to validate access to the ledger.
+/// * `as_of` - The last month included in cumulative balances, if any.
+/// * `period` - The only month whose postings are included, if any.
+///
+/// # Returns
+///
+/// A `StdResult<TrialBalanceResponse>` containing the accounts with postings and the total debits and credits.
+fn get_trial_balance(
+    deps: Deps,
+    env: Env,
+    wallet: Addr,
+    permit: Permit,
+    as_of: Option<String>,
+    period: Option<String>,
+) -> StdResult<TrialBalanceResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let state = config_read(deps.storage).load()?;
+    check_owner_or_auditor(deps, &state, &wallet)?;
+
+    let months = match (as_of, period) {
+        (Some(_), Some(_)) => return Err(StdError::generic_err("Use either as_of or period, not both")),
+        (None, Some(period)) => {
+            let month = parse_period(&period)?;
+            month_range(deps.storage, Some(month), Some(month))?
+        }
+        (Some(as_of), None) => month_range(deps.storage, None, Some(parse_period(&as_of)?))?,
+        (None, None) => None,
+    };
+
+    let codes = account_codes_read(deps.storage);
+    let chart = accounts_read(deps.storage);
+    let mut response = TrialBalanceResponse {
+        balances: vec![],
+        total_debit: Decimal::zero(),
+        total_credit: Decimal::zero(),
+        currency: base_currency(deps.storage)?,
+    };
+    for account_index in 0..state.account_count {
+        let account = chart.load(codes.load(&account_index.to_be_bytes())?.as_bytes())?;
+        let balance = account_balance(deps.storage, account, months)?;
+        if balance.debit.is_zero() && balance.credit.is_zero() {
+            continue;
+        }
+        response.total_debit += balance.debit;
+        response.total_credit += balance.credit;
+        response.balances.push(balance);
+    }
+    Ok(response)
+}
+
+/// Retrieves the postings of one account, validated by a permit.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the balance.
+/// * `permit` - The permit used to validate access to the ledger.
+/// * `account` - The account code.
+/// * `from` - The first month included, if any.
+/// * `to` - The last month included, if any.
+///
+/// # Returns
+///
+/// A `StdResult<AccountBalanceResponse>` containing the debits and credits posted to the account.
+fn get_account_balance(
+    deps: Deps,
+    env: Env,
+    wallet: Addr,
+    permit: Permit,
+    account: String,
+    from: Option<String>,
+    to: Option<String>,
+) -> StdResult<AccountBalanceResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+    let state = config_read(deps.storage).load()?;
+    check_owner_or_auditor(deps, &state, &wallet)?;
+
+    let account = accounts_read(deps.storage)
+        .may_load(account.as_bytes())?
+        .ok_or_else(|| StdError::generic_err(format!("Unknown account: {}", account)))?;
+    let months = match (from, to) {
+        (None, None) => None,
+        (from, to) => {
+            let from = from.as_deref().map(parse_period).transpose()?;
+            let to = to.as_deref().map(parse_period).transpose()?;
+            month_range(deps.storage, from, to)?
+        }
+    };
+    Ok(AccountBalanceResponse {
+        balance: account_balance(deps.storage, account, months)?,
+        currency: base_currency(deps.storage)?,
+    })
+}
+
+/// Clamps a range of months to the months with postings.
+///
+/// Returns `Some((first, last))`, an empty range when the ledger has no postings.
+fn month_range(storage: &dyn Storage, from: Option<u32>, to: Option<u32>) -> StdResult<Option<(u32, u32)>> {
+    let range = match ledger_span_read(storage).may_load()? {
+        Some(span) => (
+            from.map_or(span.first_month, |from| from.max(span.first_month)),
+            to.map_or(span.last_month, |to| to.min(span.last_month)),
+        ),
+        None => (1, 0),
+    };
+    Ok(Some(range))
+}
+
+/// Sums the postings of an account over a range of months, or over the whole ledger when `months` is `None`.
+fn account_balance(storage: &dyn Storage, account: Account, months: Option<(u32, u32)>) -> StdResult<AccountBalance> {
+    let totals = match months {
+        None => account_totals_read(storage).may_load(account.code.as_bytes())?.unwrap_or_default(),
+        Some((first, last)) => {
+            let monthly = account_month_totals_read(storage);
+            let mut totals = AccountTotals::default();
+            for month in first..=last {
+                if let Some(month_totals) = monthly.may_load(&account_month_key(month, &account.code))? {
+                    totals.debit += month_totals.debit;
+                    totals.credit += month_totals.credit;
+                }
+            }
+            totals
+        }
+    };
+    Ok(AccountBalance {
+        account: account.code,
+        account_type: account.account_type,
+        debit: totals.debit,
+        credit: totals.credit,
+    })
+}
+
+/// Retrieves every version of an invoice, validated by a permit.
+///
+/// # Arguments
+///
+/// * `deps` - A reference to the dependencies required by CosmWasm contracts.
+/// * `env` - The environment object containing information about the current block, transaction, etc.
+/// * `wallet` - The address of the wallet requesting the versions.
+/// * `permit` - The permit used to validate access to the invoice.
+/// * `invoice_index` - The index of the invoice.
+///
+/// # Returns
+///
+/// A `StdResult<InvoiceListResponse>` containing the versions, from the original to the latest.
+fn get_invoice_versions(
+    deps: Deps,
+    env: Env,
+    wallet: Addr,
+    permit: Permit,
+    invoice_index: u32,
+) -> StdResult<InvoiceListResponse> {
+    authenticate(deps, &env, &wallet, &permit)?;
+
+    let state = config_read(deps.storage).load()?;
+    let latest = config_invoice_read(deps.storage, &invoice_key(invoice_index)).load()?;
+    if wallet != state.owner && wallet != latest.auditors {
+        return Err(StdError::generic_err("Only the Owner or Auditor can read Invoice versions"));
+    }
+
+    let versions = invoice_versions_read(deps.storage);
+    let mut vect_invoice = vec![];
+    for version in 1..latest.version {
+        vect_invoice.push(versions.load(&invoice_version_key(invoice_index, version))?);
+    }
+    vect_invoice.push(latest);
+
+    Ok(InvoiceListResponse { vect_invoice })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::AccountType;
+    use cosmwasm_std::testing::*;
+    use cosmwasm_std::{from_binary, to_binary, Coin, CosmosMsg, OwnedDeps, StdError, Timestamp, Uint128, WasmMsg};
+    const PATH_PERMIT: &str = "../contract/tests/permit.json";
+    const DOC_HASH: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+    const LINE_HASH: &str = "a9224f4212351653f0422236989e7a968142bbf35ea7fffca526d90268c8950a";
+    use serde::{Deserialize, Serialize};
+    use serde_json::from_str;
+    use std::fs::read_to_string;
+
+    // Struct to deserialize data from a JSON file
+    #[derive(Serialize, Deserialize)]
+    struct GetAllData {
+        pub wallet: Addr,
+        pub index: u32,
+        pub permit: Permit,
+    }
+
+    // Struct to hold the deserialized JSON data
+    #[derive(Serialize, Deserialize)]
+    struct JsonData {
+        pub get_all: GetAllData,
+    }
+
+    // Builds a valid invoice for the tests that do not check its content
+    fn invoice_fixture() -> Invoice {
+        Invoice {
+            invoice_number: "INV-001".to_string(),
+            date: "2025-02-26".to_string(),
+            client_name: "Client A".to_string(),
+            description: "Service".to_string(),
+            total_amount: "1000".to_string(),
+            tax_amount: "100".to_string(),
+            currency: "USD".to_string(),
+            doc_hash: DOC_HASH.to_string(),
+            line_hash: LINE_HASH.to_string(),
+            credibility: CredibilityScore(85),
+            ..Default::default()
+        }
+    }
+
+    // The contract instantiated for the tests that query it with the permit on disk (created by Client.query_get_all)
+    struct PermitSetup {
+        deps: OwnedDeps<MockStorage, MockApi, MockQuerier>,
+        env: Env,
+        info: MessageInfo,
+        wallet: Addr,
+        permit: Permit,
+    }
+
+    // Points the environment at the contract of the permit and instantiates the contract, owned by the permit wallet
+    // or, when the wallet plays another role, by "creator"
+    fn permit_setup(wallet_owns: bool) -> PermitSetup {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let json_data_str = read_to_string(PATH_PERMIT).expect("Unable to read file");
+        let json_data: JsonData = from_str(&json_data_str).expect("Failed to deserialize JSON data");
+        let wallet = json_data.get_all.wallet;
+        let permit = json_data.get_all.permit;
+        let contract_address = permit.clone().params.allowed_tokens[0].clone();
+        env.contract.address = Addr::unchecked(contract_address);
+        let info = if wallet_owns { mock_info(wallet.as_str(), &[]) } else { mock_info("creator", &[]) };
+        let instantiate_msg = InstantiateMsg { count: 0 };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg).unwrap();
+        PermitSetup { deps, env, info, wallet, permit }
+    }
+
+    /// Test function for retrieving all invoices.
+    #[test]
+    fn get_invoice_all() {
+        let mut deps = mock_dependencies();
+        // Load data from a permit JSON file on disk (created by Client.query_get_all)
+        let json_data_str = read_to_string(PATH_PERMIT).expect("Unable to read file");
+        let json_data: JsonData = from_str(&json_data_str).expect("Failed to deserialize JSON data");
+        let wallet = &json_data.get_all.wallet;
+        let index = json_data.get_all.index;
+        let permit = json_data.get_all.permit;
+        println!("wallet: {:?}", &wallet);
+        println!("index: {:?}", index);
+        println!("permit: {:?}", permit);
+        // Set up the mock environment and contract address
+        let info = mock_info(
+            &wallet.clone().into_string(),
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let mut env = mock_env();
+        let contract_address = permit.clone().params.allowed_tokens[0].clone();
+        env.contract.address = Addr::unchecked(contract_address);
+
+        // Instantiate the contract
+        let instantiate_msg = InstantiateMsg { count: 0 };
+        let _res = instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            instantiate_msg
+        ).unwrap();
+
+        // Define an invoice to add
+        let invoice = Invoice {
+            invoice_number: "INV-001".to_string(),
+            date: "2025-02-26".to_string(),
+            client_name: "Client A".to_string(),
+            description: "Service".to_string(),
+            total_amount: "1000".to_string(),
+            tax_amount: "100".to_string(),
+            currency: "USD".to_string(),
+            doc_hash: DOC_HASH.to_string(),
+            line_hash: LINE_HASH.to_string(),
+            auditors: "Auditor A".to_string(),
+            credibility: CredibilityScore(85),
+            audit_state: "Pending".to_string(),
+            ..Default::default()
+        };
+
+        // Call the try_add function
+        let execute_msg = ExecuteMsg::Add { invoice: invoice.clone() };
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            execute_msg
+        ).unwrap();
+
+        // Verify that the invoice was added successfully
+        assert_eq!(_res.messages.len(), 0);
+
+        // Call the get_all function to retrieve all invoices
+        let list_invoice = get_all(
+            deps.as_ref(),
+            env.clone(),
+            wallet.clone(),
+            permit,
+            index,
+        );
+
+        println!("list_invoice: {:?}", list_invoice);
+
+        // Verify that the invoice was retrieved successfully
+        match list_invoice {
+            Err(StdError::GenericErr { .. }) => panic!("Must return unauthorized error"),
+            Err(e) => panic!("Unexpected error: {:?}", e),
+            Ok(response) => {
+                let invoices = response.vect_invoice;
+                assert_eq!(invoices.len(), 1);
+                let retrieved_invoice = &invoices[0];
+                assert_eq!(retrieved_invoice.invoice_number, "INV-001");
+                assert_eq!(retrieved_invoice.date, "2025-02-26");
+                assert_eq!(retrieved_invoice.client_name, "Client A");
+                assert_eq!(retrieved_invoice.description, "Service");
+                assert_eq!(retrieved_invoice.total_amount, "1000");
+                assert_eq!(retrieved_invoice.tax_amount, "100");
+                assert_eq!(retrieved_invoice.currency, "USD");
+                assert_eq!(retrieved_invoice.doc_hash, DOC_HASH);
+                assert_eq!(retrieved_invoice.line_hash, LINE_HASH);
+                assert_eq!(retrieved_invoice.auditors, "Auditor A");
+                assert_eq!(retrieved_invoice.credibility, CredibilityScore(85));
+                assert_eq!(retrieved_invoice.audit_state, "Pending");
+            }
+        }
+    }
+
+    /// Test function to ensure proper contract initialization.
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies();
+
+        // Set up the mock environment and instantiate message
+        let info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let init_msg = InstantiateMsg { count: 17 };
+
+        // Instantiate the contract
+        let res = instantiate(deps.as_mut(), mock_env(), info.clone(), init_msg).unwrap();
+
+        assert_eq!(0, res.messages.len());
+
+        // Query the state to verify initialization
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetCount {}
+        ).unwrap();
+        let value: CountResponse = from_binary(&res).unwrap();
+        assert_eq!(17, value.count);
+    }
+
+   /// Test function for incrementing a counter.
+    #[test]
+    fn increment() {
+        let mut deps = mock_dependencies_with_balance(&[Coin {
+            denom: "token".to_string(),
+            amount: Uint128::new(2),
+        }]);
+
+        // Set up the mock environment and instantiate message
+        let info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "token".to_string(),
+                amount: Uint128::new(2),
+            }],
+        );
+
+        let init_msg = InstantiateMsg { count: 17 };
+
+        // Instantiate the contract
+        let _res = instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+        // Anyone can increment the counter
+        let info = mock_info(
+            "anyone",
+            &[Coin {
+                denom: "token".to_string(),
+                amount: Uint128::new(2),
+            }],
+        );
+
+        let exec_msg = ExecuteMsg::Increment {};
+        let _res = execute(deps.as_mut(), mock_env(), info.clone(), exec_msg).unwrap();
+
+        // Should increase counter by 1
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
+        let value: CountResponse = from_binary(&res).unwrap();
+        assert_eq!(18, value.count);
+    }
+
+    /// Test function to verify that only the contract creator can reset the counter.
+    #[test]
+    fn reset() {
+        let mut deps = mock_dependencies_with_balance(&[Coin {
+            denom: "token".to_string(),
+            amount: Uint128::new(2),
+        }]);
+        let info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "token".to_string(),
+                amount: Uint128::new(2),
+            }],
+        );
+        let init_msg = InstantiateMsg { count: 17 };
+
+        let _res = instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+        // Verify that only the creator can reset the counter
+
+        let info = mock_info(
+            "anyone",
+            &[Coin {
+                denom: "token".to_string(),
+                amount: Uint128::new(2),
+            }],
+        );
+        let exec_msg = ExecuteMsg::Reset { count: 5 };
+
+        let res = execute(deps.as_mut(), mock_env(), info, exec_msg);
+
+        match res {
+            Err(StdError::GenericErr { .. }) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        // Only the original creator can reset the counter
+        let info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "token".to_string(),
+                amount: Uint128::new(2),
+            }],
+        );
+        let exec_msg = ExecuteMsg::Reset { count: 5 };
+
+        let _res = execute(deps.as_mut(), mock_env(), info.clone(), exec_msg).unwrap();
+
+        // Verify that the counter has been reset to 5
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
+        let value: CountResponse = from_binary(&res).unwrap();
+        assert_eq!(5, value.count);
+    }
+
+    /// Test function for adding an invoice.
+    #[test]
+    fn add_invoice() {
+        // Initialize mock dependencies
+        let mut deps = mock_dependencies_with_balance(&[Coin {
+            denom: "token".to_string(),
+            amount: Uint128::new(2),
+        }]);
+
+        // Set up the mock environment and instantiate message
+        let info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "token".to_string(),
+                amount: Uint128::new(2),
+            }],
+        );
+
+        // Instantiate the contract
+        let instantiate_msg = InstantiateMsg { count: 0 };
+        let _res = instantiate(
             deps.as_mut(),
-            env.clone(),
+            mock_env(),
+            info.clone(),
+            instantiate_msg
+        ).unwrap();
+
+        // Define an invoice to add
+        let invoice = Invoice {
+            invoice_number: "INV-001".to_string(),
+            date: "2025-02-26".to_string(),
+            client_name: "Client A".to_string(),
+            description: "Service".to_string(),
+            total_amount: "1000".to_string(),
+            tax_amount: "100".to_string(),
+            currency: "USD".to_string(),
+            doc_hash: DOC_HASH.to_string(),
+            line_hash: LINE_HASH.to_string(),
+            auditors: "Auditor A".to_string(),
+            credibility: CredibilityScore(85),
+            audit_state: "Pending".to_string(),
+            ..Default::default()
+        };
+
+        // Call the try_add function
+        let execute_msg = ExecuteMsg::Add { invoice: invoice.clone() };
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
             info.clone(),
             execute_msg
         ).unwrap();
 
-        // Verify that the invoice was added successfully
-        assert_eq!(_res.messages.len(), 0);
+        // Verify that the invoice was added successfully
+        assert_eq!(_res.messages.len(), 0);
+
+        // Retrieve and verify the saved invoice
+        let index = b"0"; // Convert the integer to a byte slice
+        let stored_invoice: Invoice = config_invoice_read(deps.as_mut().storage, index).load().unwrap();
+
+        assert_eq!(stored_invoice.invoice_number, "INV-001");
+        assert_eq!(stored_invoice.date, "2025-02-26");
+        assert_eq!(stored_invoice.client_name, "Client A");
+        assert_eq!(stored_invoice.description, "Service");
+        assert_eq!(stored_invoice.total_amount, "1000");
+        assert_eq!(stored_invoice.tax_amount, "100");
+        assert_eq!(stored_invoice.currency, "USD");
+        assert_eq!(stored_invoice.doc_hash, DOC_HASH);
+        assert_eq!(stored_invoice.line_hash, LINE_HASH);
+        assert_eq!(stored_invoice.auditors, "Auditor A");
+        assert_eq!(stored_invoice.credibility, CredibilityScore(85));
+        assert_eq!(stored_invoice.audit_state, "Pending");
+
+        // Verify that only "creator" can add an invoice
+        let _info_public = mock_info(
+            "public",
+            &[Coin {
+                denom: "token".to_string(),
+                amount: Uint128::new(2),
+            }],
+        );
+        let execute_msg = ExecuteMsg::Add { invoice: invoice.clone() };
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            _info_public.clone(),
+            execute_msg
+        );
+
+        // Verify that the invoice is not added
+        match _res {
+            Err(StdError::GenericErr { .. }) => {}
+            _ => panic!("Must return unauthorized error")
+        }
+    }
+
+    /// Test function for updating an invoice auditor.
+    #[test]
+    fn update_auditor() {
+        let mut deps = mock_dependencies();
+        let info = mock_info(
+            "creator",
+            &[Coin {
+                denom: "earth".to_string(),
+                amount: Uint128::new(1000),
+            }],
+        );
+        let mut env = mock_env();
+
+        // Load data from a permit JSON file on disk (created by Client.query_get_all)
+        let json_data_str = read_to_string(PATH_PERMIT).expect("Unable to read file");
+        let json_data: JsonData = from_str(&json_data_str).expect("Failed to deserialize JSON data");
+        let wallet = &json_data.get_all.wallet;
+        let index = json_data.get_all.index;
+        let permit = json_data.get_all.permit;
+        println!("wallet: {:?}", &wallet);
+        println!("index: {:?}", index);
+        println!("permit: {:?}", permit);
+        // Set up the mock environment and contract address
+    
+        let contract_address = permit.clone().params.allowed_tokens[0].clone();
+        env.contract.address = Addr::unchecked(contract_address);
+
+        let instantiate_msg = InstantiateMsg { count: 0 };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg).unwrap();
+
+        let invoice = Invoice {
+            invoice_number: "INV-001".to_string(),
+            date: "2025-02-26".to_string(),
+            client_name: "Client A".to_string(),
+            description: "Service".to_string(),
+            total_amount: "1000".to_string(),
+            tax_amount: "100".to_string(),
+            currency: "USD".to_string(),
+            doc_hash: DOC_HASH.to_string(),
+            line_hash: LINE_HASH.to_string(),
+            auditors: "Auditor A".to_string(),
+            credibility: CredibilityScore(85),
+            audit_state: "Pending".to_string(),
+            ..Default::default()
+        };
+
+        let _res = try_add(deps.as_mut(), mock_env(), info.clone(), invoice.clone()).unwrap();
+
+        let new_auditor = Addr::unchecked("new_auditor");
+        let index_u8 = 0;
+        let res = try_update_auditor(deps.as_mut(), info, index_u8, new_auditor.clone()).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        //let index = index_u8.to_be_bytes(); // Convert the integer to a byte slice
+        let index_conf = b"0"; // Convert the integer to a byte slice
+        let updated_invoice = config_invoice_read(&deps.storage, index_conf).load().unwrap();
+        assert_eq!(updated_invoice.auditors, new_auditor.to_string());    
+    }
+
+    /// Test function for revoking the ledger access of a replaced auditor.
+    #[test]
+    fn replace_auditor() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        let instantiate_msg = InstantiateMsg { count: 0 };
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
+        for _ in 0..2 {
+            let _res = try_add(deps.as_mut(), mock_env(), info.clone(), invoice_fixture()).unwrap();
+        }
+        let state = config_read(deps.as_ref().storage).load().unwrap();
+        let (former, current) = (Addr::unchecked("former_auditor"), Addr::unchecked("current_auditor"));
+
+        // An auditor stays engaged while assigned to at least one invoice
+        let _res = try_update_auditor(deps.as_mut(), info.clone(), 0, former.clone()).unwrap();
+        let _res = try_update_auditor(deps.as_mut(), info.clone(), 1, former.clone()).unwrap();
+        let _res = try_update_auditor(deps.as_mut(), info.clone(), 0, current.clone()).unwrap();
+        assert!(check_owner_or_auditor(deps.as_ref(), &state, &former).is_ok());
+        assert_eq!(auditors_read(deps.as_ref().storage).load(former.as_bytes()).unwrap(), 1);
+
+        // Once replaced everywhere, the former auditor is refused
+        let _res = try_update_auditor(deps.as_mut(), info, 1, current.clone()).unwrap();
+        match check_owner_or_auditor(deps.as_ref(), &state, &former) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Only the Owner or an Auditor can run this query"),
+            _ => panic!("Must refuse a replaced auditor"),
+        }
+        assert!(check_auditor(deps.as_ref(), &state, &former).is_err());
+        assert_eq!(auditors_read(deps.as_ref().storage).load(current.as_bytes()).unwrap(), 2);
+    }
+
+    /// Test function for amending an invoice and reading its version chain.
+    #[test]
+    fn amend_invoice() {
+        let PermitSetup { mut deps, env, info, wallet, permit } = permit_setup(true);
+
+        let invoice = Invoice {
+            invoice_number: "INV-001".to_string(),
+            date: "2025-02-26".to_string(),
+            client_name: "Client A".to_string(),
+            description: "Service".to_string(),
+            total_amount: "1000".to_string(),
+            tax_amount: "100".to_string(),
+            currency: "USD".to_string(),
+            doc_hash: DOC_HASH.to_string(),
+            line_hash: LINE_HASH.to_string(),
+            auditors: "Auditor A".to_string(),
+            credibility: CredibilityScore(85),
+            audit_state: "Pending".to_string(),
+            ..Default::default()
+        };
+        let _res = try_add(deps.as_mut(), mock_env(), info.clone(), invoice.clone()).unwrap();
+
+        // The auditor requests a correction
+        let auditor_info = mock_info("Auditor A", &[]);
+        let _res = try_audit_invoice(deps.as_mut(), auditor_info, 0, AuditDecision::RequestCorrection).unwrap();
+
+        // Only the owner can amend an invoice
+        let mut corrected = invoice.clone();
+        corrected.total_amount = "1100".to_string();
+        let res = try_amend_invoice(deps.as_mut(), mock_env(), mock_info("anyone", &[]), 0, corrected.clone());
+        match res {
+            Err(StdError::GenericErr { .. }) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        // Amending an unknown invoice fails
+        let res = try_amend_invoice(deps.as_mut(), mock_env(), info.clone(), 1, corrected.clone());
+        assert!(res.is_err());
+
+        let res = try_amend_invoice(deps.as_mut(), mock_env(), info.clone(), 0, corrected.clone()).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // The latest version supersedes the original and is back to pending
+        let amended = config_invoice_read(&deps.storage, b"0").load().unwrap();
+        assert_eq!(amended.total_amount, "1100");
+        assert_eq!(amended.version, 2);
+        assert_eq!(amended.supersedes, Some(LINE_HASH.to_string()));
+        assert_eq!(amended.line_hash, compute_line_hash(&corrected).unwrap());
+        assert_ne!(amended.line_hash, LINE_HASH);
+        assert_eq!(amended.audit_state, AUDIT_STATE_PENDING);
+        assert_eq!(amended.auditors, "Auditor A");
+
+        // The latest version is returned by GetAll, the full chain by GetInvoiceVersions
+        let list = get_all(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), 0).unwrap();
+        assert_eq!(list.vect_invoice, vec![amended.clone()]);
+
+        let versions = get_invoice_versions(deps.as_ref(), env.clone(), wallet.clone(), permit, 0).unwrap();
+        assert_eq!(versions.vect_invoice.len(), 2);
+        assert_eq!(versions.vect_invoice[0].total_amount, "1000");
+        assert_eq!(versions.vect_invoice[0].version, 1);
+        assert_eq!(versions.vect_invoice[0].audit_state, AUDIT_STATE_CORRECTION_REQUESTED);
+        assert_eq!(versions.vect_invoice[1], amended);
+    }
+
+    /// Test function for credit notes and voiding.
+    #[test]
+    fn credit_notes_and_void() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        let instantiate_msg = InstantiateMsg { count: 0 };
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
+
+        let invoice = invoice_fixture();
+        let _res = try_add(deps.as_mut(), mock_env(), info.clone(), invoice.clone()).unwrap();
+
+        // A credit note must reference an existing invoice
+        let mut credit_note = invoice.clone();
+        credit_note.invoice_number = "CN-001".to_string();
+        credit_note.kind = DocumentKind::CreditNote;
+        credit_note.total_amount = "600".to_string();
+        assert!(try_add(deps.as_mut(), mock_env(), info.clone(), credit_note.clone()).is_err());
+        credit_note.reference = Some(7);
+        assert!(try_add(deps.as_mut(), mock_env(), info.clone(), credit_note.clone()).is_err());
+
+        credit_note.reference = Some(0);
+        let _res = try_add(deps.as_mut(), mock_env(), info.clone(), credit_note.clone()).unwrap();
+        let original = config_invoice_read(&deps.storage, b"0").load().unwrap();
+        assert_eq!(original.credited_amount, Decimal::from_str("600").unwrap());
+
+        // Credits may not exceed the original amount
+        let mut second_credit = credit_note.clone();
+        second_credit.total_amount = "500".to_string();
+        match try_add(deps.as_mut(), mock_env(), info.clone(), second_credit.clone()) {
+            Err(StdError::GenericErr { msg, .. }) => assert!(msg.contains("exceed")),
+            _ => panic!("Must return an over-credit error"),
+        }
+
+        // A plain invoice cannot reference another one
+        let mut plain = invoice.clone();
+        plain.reference = Some(0);
+        assert!(try_add(deps.as_mut(), mock_env(), info.clone(), plain).is_err());
+
+        // Only the owner can void, and voiding a credit note releases its amount
+        assert!(try_void_invoice(deps.as_mut(), mock_info("anyone", &[]), 1, "typo".to_string()).is_err());
+        let _res = try_void_invoice(deps.as_mut(), info.clone(), 1, "Issued twice".to_string()).unwrap();
+        let voided = config_invoice_read(&deps.storage, b"1").load().unwrap();
+        assert!(voided.voided);
+        assert_eq!(voided.void_reason, Some("Issued twice".to_string()));
+        let original = config_invoice_read(&deps.storage, b"0").load().unwrap();
+        assert_eq!(original.credited_amount, Decimal::zero());
+        assert!(try_void_invoice(deps.as_mut(), info.clone(), 1, "again".to_string()).is_err());
+        assert!(try_amend_invoice(deps.as_mut(), mock_env(), info.clone(), 1, credit_note.clone()).is_err());
+
+        let _res = try_add(deps.as_mut(), mock_env(), info.clone(), second_credit).unwrap();
+
+        // An invoice is voided only once its credit notes are
+        match try_void_invoice(deps.as_mut(), info.clone(), 0, "Cancelled order".to_string()) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "An Invoice cannot be voided before its credit notes")
+            }
+            _ => panic!("Must refuse to void a credited invoice"),
+        }
+        let _res = try_void_invoice(deps.as_mut(), info.clone(), 2, "Issued twice".to_string()).unwrap();
+
+        // A voided invoice stays stored but can no longer be referenced
+        let _res = try_void_invoice(deps.as_mut(), info.clone(), 0, "Cancelled order".to_string()).unwrap();
+        assert!(config_invoice_read(&deps.storage, b"0").load().unwrap().voided);
+        assert!(try_add(deps.as_mut(), mock_env(), info, credit_note).is_err());
+    }
+
+    /// Test function for the sealing block, time and sender stamped on each invoice version.
+    #[test]
+    fn sealing_stamps() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        let instantiate_msg = InstantiateMsg { count: 0 };
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
+
+        // The client cannot choose the sealing stamps
+        let invoice = Invoice {
+            date: "2024-12-31".to_string(),
+            sealed_at_height: 1,
+            sealed_at_time: Timestamp::from_seconds(1),
+            sealed_by: Some(Addr::unchecked("someone_else")),
+            ..invoice_fixture()
+        };
+        let mut env = mock_env();
+        env.block.height = 100;
+        env.block.time = Timestamp::from_seconds(1_740_000_000);
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice.clone()).unwrap();
+
+        let sealed = config_invoice_read(&deps.storage, b"0").load().unwrap();
+        assert_eq!(sealed.sealed_at_height, 100);
+        assert_eq!(sealed.sealed_at_time, Timestamp::from_seconds(1_740_000_000));
+        assert_eq!(sealed.sealed_by, Some(Addr::unchecked("creator")));
+        assert_eq!(sealed.date, "2024-12-31");
+
+        // An amendment is stamped with its own block
+        env.block.height = 250;
+        env.block.time = Timestamp::from_seconds(1_750_000_000);
+        let _res = try_amend_invoice(deps.as_mut(), env, info, 0, invoice).unwrap();
+        let amended = config_invoice_read(&deps.storage, b"0").load().unwrap();
+        assert_eq!(amended.sealed_at_height, 250);
+        assert_eq!(amended.sealed_at_time, Timestamp::from_seconds(1_750_000_000));
+        let original = invoice_versions_read(&deps.storage).load(&invoice_version_key(0, 1)).unwrap();
+        assert_eq!(original.sealed_at_height, 100);
+    }
+
+    /// Test function for the validation of invoice fields.
+    #[test]
+    fn validate_invoice_fields() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        let instantiate_msg = InstantiateMsg { count: 0 };
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
+
+        let cases = vec![
+            (Invoice { invoice_number: " ".to_string(), ..invoice_fixture() }, "Missing invoice_number"),
+            (Invoice { invoice_number: "N".repeat(65), ..invoice_fixture() }, "invoice_number is too long"),
+            (Invoice { client_name: "".to_string(), ..invoice_fixture() }, "Missing client_name"),
+            (Invoice { description: "D".repeat(1025), ..invoice_fixture() }, "description is too long"),
+            (Invoice { date: "26/02/2025".to_string(), ..invoice_fixture() }, "Invalid date"),
+            (Invoice { date: "2025-02-29".to_string(), ..invoice_fixture() }, "Invalid date"),
+            (Invoice { date: "2025-13-01".to_string(), ..invoice_fixture() }, "Invalid date"),
+            (Invoice { total_amount: "".to_string(), ..invoice_fixture() }, "Missing total_amount"),
+            (Invoice { total_amount: "1,000".to_string(), ..invoice_fixture() }, "Invalid total_amount"),
+            (Invoice { tax_amount: "-5".to_string(), ..invoice_fixture() }, "Invalid tax_amount"),
+            (Invoice { currency: "usd".to_string(), ..invoice_fixture() }, "Invalid currency"),
+            (Invoice { doc_hash: "hash123".to_string(), ..invoice_fixture() }, "Invalid doc_hash"),
+            (Invoice { doc_hash: DOC_HASH.to_uppercase(), ..invoice_fixture() }, "Invalid doc_hash"),
+            (Invoice { credibility: CredibilityScore(101), ..invoice_fixture() }, "Invalid credibility"),
+        ];
+        for (invoice, expected) in cases {
+            match try_add(deps.as_mut(), mock_env(), info.clone(), invoice) {
+                Err(StdError::GenericErr { msg, .. }) => assert!(msg.starts_with(expected), "{}", msg),
+                _ => panic!("Must return a validation error: {}", expected),
+            }
+        }
+
+        // Leap days and optional fields are accepted
+        let invoice = Invoice {
+            date: "2024-02-29".to_string(),
+            description: "".to_string(),
+            credibility: CredibilityScore(100),
+            ..invoice_fixture()
+        };
+        let _res = try_add(deps.as_mut(), mock_env(), info, invoice).unwrap();
+    }
+
+    /// Test function for the credibility thresholds and the auditor decisions they require.
+    #[test]
+    fn credibility_thresholds_drive_audit_state() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        let instantiate_msg = InstantiateMsg { count: 0 };
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
+
+        // Default thresholds
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCredibilityThresholds {}).unwrap();
+        let value: CredibilityThresholdsResponse = from_binary(&res).unwrap();
+        assert_eq!(value.thresholds, CredibilityThresholds::default());
+
+        // Only the owner can set consistent thresholds
+        let exec_msg = ExecuteMsg::SetCredibilityThresholds {
+            review_below: CredibilityScore(60),
+            seal_from: Some(CredibilityScore(90)),
+        };
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), exec_msg.clone()).is_err());
+        let bad_msg = ExecuteMsg::SetCredibilityThresholds {
+            review_below: CredibilityScore(60),
+            seal_from: Some(CredibilityScore(40)),
+        };
+        assert!(execute(deps.as_mut(), mock_env(), info.clone(), bad_msg).is_err());
+        let _res = execute(deps.as_mut(), mock_env(), info.clone(), exec_msg).unwrap();
+
+        // The client cannot choose the audit state
+        let scores = [(59, AUDIT_STATE_MUST_REVIEW), (60, AUDIT_STATE_PENDING), (90, AUDIT_STATE_SEALED)];
+        for (index, (score, expected)) in scores.iter().enumerate() {
+            let invoice = Invoice {
+                credibility: CredibilityScore(*score),
+                auditors: "auditor".to_string(),
+                audit_state: AUDIT_STATE_APPROVED.to_string(),
+                ..invoice_fixture()
+            };
+            let _res = try_add(deps.as_mut(), mock_env(), info.clone(), invoice).unwrap();
+            let stored = config_invoice_read(&deps.storage, &invoice_key(index as u32)).load().unwrap();
+            assert_eq!(stored.audit_state, *expected);
+        }
+
+        // A must-review invoice can only be approved by its auditor, never by the owner
+        assert!(try_audit_invoice(deps.as_mut(), info.clone(), 0, AuditDecision::Approve).is_err());
+        assert!(try_audit_invoice(deps.as_mut(), mock_info("anyone", &[]), 0, AuditDecision::Approve).is_err());
+        let _res = try_update_auditor(deps.as_mut(), info.clone(), 0, Addr::unchecked("creator")).unwrap();
+        assert!(try_audit_invoice(deps.as_mut(), info.clone(), 0, AuditDecision::Approve).is_err());
+        let _res = try_update_auditor(deps.as_mut(), info.clone(), 0, Addr::unchecked("auditor")).unwrap();
+        let _res = try_audit_invoice(deps.as_mut(), mock_info("auditor", &[]), 0, AuditDecision::Approve).unwrap();
+        let stored = config_invoice_read(&deps.storage, b"0").load().unwrap();
+        assert_eq!(stored.audit_state, AUDIT_STATE_APPROVED);
+
+        // An amendment with a low score goes back to review
+        let low = Invoice { credibility: CredibilityScore(10), ..invoice_fixture() };
+        let _res = try_amend_invoice(deps.as_mut(), mock_env(), info, 2, low).unwrap();
+        let stored = config_invoice_read(&deps.storage, b"2").load().unwrap();
+        assert_eq!(stored.audit_state, AUDIT_STATE_MUST_REVIEW);
+    }
+
+    // Builds the journal lines of a 1000 + 200 VAT purchase
+    fn journal_lines_fixture() -> Vec<JournalLine> {
+        vec![
+            JournalLine {
+                account: "6061".to_string(),
+                debit: Decimal::from_str("1000").unwrap(),
+                tax_code: Some("VAT20".to_string()),
+                cost_centre: Some("OPS".to_string()),
+                ..Default::default()
+            },
+            JournalLine {
+                account: "44566".to_string(),
+                debit: Decimal::from_str("200").unwrap(),
+                ..Default::default()
+            },
+            JournalLine {
+                account: "401".to_string(),
+                credit: Decimal::from_str("1200").unwrap(),
+                ..Default::default()
+            },
+        ]
+    }
+
+    // Registers the accounts and the tax code used by the journal lines fixture
+    fn setup_ledger(mut deps: DepsMut, owner: &str) {
+        let chart = [
+            ("6061", "Supplies", AccountType::Expense),
+            ("44566", "Deductible VAT", AccountType::Asset),
+            ("401", "Suppliers", AccountType::Liability),
+        ];
+        let info = mock_info(owner, &[]);
+        for (code, name, account_type) in chart {
+            let account = Account {
+                code: code.to_string(),
+                name: name.to_string(),
+                account_type,
+                active: true,
+            };
+            try_set_account(deps.branch(), info.clone(), account).unwrap();
+        }
+        let tax_code = TaxCode {
+            code: "VAT20".to_string(),
+            rate: Decimal::percent(20),
+            jurisdiction: "FR".to_string(),
+            active: true,
+        };
+        try_set_tax_code(deps, info, tax_code).unwrap();
+    }
+
+    /// Test function for the validation and sealing of journal lines.
+    #[test]
+    fn journal_lines() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        let instantiate_msg = InstantiateMsg { count: 0 };
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
+
+        setup_ledger(deps.as_mut(), "creator");
+
+        let invoice = Invoice {
+            total_amount: "1200".to_string(),
+            tax_amount: "200".to_string(),
+            lines: journal_lines_fixture(),
+            ..invoice_fixture()
+        };
+
+        let mut unbalanced = invoice.clone();
+        unbalanced.lines[2].credit = Decimal::from_str("1100").unwrap();
+        let mut wrong_total = invoice.clone();
+        wrong_total.total_amount = "1300".to_string();
+        let mut wrong_tax = invoice.clone();
+        wrong_tax.tax_amount = "150".to_string();
+        let mut both_sides = invoice.clone();
+        both_sides.lines[1].credit = Decimal::from_str("200").unwrap();
+        let mut no_account = invoice.clone();
+        no_account.lines[0].account = "".to_string();
+        let cases = vec![
+            (unbalanced, "Journal lines are not balanced"),
+            (wrong_total, "Journal lines total"),
+            (wrong_tax, "Journal lines taxable base"),
+            (both_sides, "Journal line on account 44566 must have either a debit or a credit"),
+            (no_account, "Missing account"),
+        ];
+        for (invoice, expected) in cases {
+            match try_add(deps.as_mut(), mock_env(), info.clone(), invoice) {
+                Err(StdError::GenericErr { msg, .. }) => assert!(msg.starts_with(expected), "{}", msg),
+                _ => panic!("Must return a journal line error: {}", expected),
+            }
+        }
+
+        // The sealed line hash covers the journal lines
+        let _res = try_add(deps.as_mut(), mock_env(), info, invoice.clone()).unwrap();
+        let stored = config_invoice_read(&deps.storage, b"0").load().unwrap();
+        assert_eq!(stored.lines, journal_lines_fixture());
+        assert_eq!(stored.line_hash, compute_line_hash(&invoice).unwrap());
+        let without_lines = Invoice { lines: vec![], ..invoice };
+        assert_ne!(stored.line_hash, compute_line_hash(&without_lines).unwrap());
+    }
+
+    /// Test function for the chart of accounts.
+    #[test]
+    fn chart_of_accounts() {
+        // The permit wallet is an auditor, not the owner
+        let PermitSetup { mut deps, env, info, wallet, permit } = permit_setup(false);
+
+        // Only the owner manages the chart of accounts
+        let account = Account {
+            code: "6061".to_string(),
+            name: "Supplies".to_string(),
+            account_type: AccountType::Expense,
+            active: true,
+        };
+        assert!(try_set_account(deps.as_mut(), mock_info("anyone", &[]), account.clone()).is_err());
+        setup_ledger(deps.as_mut(), "creator");
+
+        // Lines must reference known, active accounts
+        let invoice = Invoice {
+            total_amount: "1200".to_string(),
+            tax_amount: "200".to_string(),
+            lines: journal_lines_fixture(),
+            ..invoice_fixture()
+        };
+        let mut unknown = invoice.clone();
+        unknown.lines[0].account = "999".to_string();
+        match try_add(deps.as_mut(), env.clone(), info.clone(), unknown) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Unknown account: 999"),
+            _ => panic!("Must return an unknown account error"),
+        }
+        let inactive = Account { active: false, ..account };
+        let _res = try_set_account(deps.as_mut(), info.clone(), inactive.clone()).unwrap();
+        match try_add(deps.as_mut(), env.clone(), info.clone(), invoice.clone()) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Inactive account: 6061"),
+            _ => panic!("Must return an inactive account error"),
+        }
+
+        // Only the owner and the engaged auditors can page through the chart
+        assert!(get_accounts(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), 0).is_err());
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice_fixture()).unwrap();
+        let _res = try_update_auditor(deps.as_mut(), info, 0, wallet.clone()).unwrap();
 
-        // Call the get_all function to retrieve all invoices
-        let list_invoice = get_all(
+        let page = get_accounts(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), 0).unwrap();
+        assert_eq!(page.total, 3);
+        assert_eq!(page.accounts.len(), 3);
+        assert_eq!(page.accounts[0], inactive);
+        assert_eq!(page.accounts[2].code, "401");
+        let page = get_accounts(deps.as_ref(), env, wallet.clone(), permit, 2).unwrap();
+        assert_eq!(page.accounts.len(), 1);
+    }
+
+    /// Test function for the trial balance and account balance queries.
+    #[test]
+    fn trial_balance() {
+        let PermitSetup { mut deps, env, info, wallet, permit } = permit_setup(true);
+        setup_ledger(deps.as_mut(), wallet.as_str());
+
+        // Two invoices in January and March, one in February that gets voided
+        let invoice = Invoice {
+            total_amount: "1200".to_string(),
+            tax_amount: "200".to_string(),
+            lines: journal_lines_fixture(),
+            ..invoice_fixture()
+        };
+        for date in ["2025-01-15", "2025-02-10", "2025-03-31"] {
+            let dated = Invoice { date: date.to_string(), ..invoice.clone() };
+            let _res = try_add(deps.as_mut(), env.clone(), info.clone(), dated).unwrap();
+        }
+        let _res = try_void_invoice(deps.as_mut(), info.clone(), 1, "Duplicate".to_string()).unwrap();
+
+        let amount = |value: &str| Decimal::from_str(value).unwrap();
+        let trial = get_trial_balance(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), None, None).unwrap();
+        assert_eq!(trial.total_debit, amount("2400"));
+        assert_eq!(trial.total_credit, amount("2400"));
+        assert_eq!(trial.balances.len(), 3);
+        assert_eq!(trial.balances[0].account, "6061");
+        assert_eq!(trial.balances[0].debit, amount("2000"));
+        assert_eq!(trial.balances[2].credit, amount("2400"));
+
+        // Cumulative up to February, then only March
+        let as_of = Some("2025-02".to_string());
+        let trial = get_trial_balance(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), as_of, None).unwrap();
+        assert_eq!(trial.total_debit, amount("1200"));
+        let period = Some("2025-03".to_string());
+        let trial =
+            get_trial_balance(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), None, period).unwrap();
+        assert_eq!(trial.total_credit, amount("1200"));
+        let both = get_trial_balance(
             deps.as_ref(),
             env.clone(),
             wallet.clone(),
-            permit,
-            index,
+            permit.clone(),
+            Some("2025-02".to_string()),
+            Some("2025-03".to_string()),
         );
+        assert!(both.is_err());
 
-        println!("list_invoice: {:?}", list_invoice);
+        // An amendment moves the postings to the new date and amount
+        let amended = Invoice {
+            date: "2025-02-01".to_string(),
+            total_amount: "600".to_string(),
+            tax_amount: "100".to_string(),
+            lines: vec![
+                JournalLine { debit: amount("500"), ..journal_lines_fixture()[0].clone() },
+                JournalLine { debit: amount("100"), ..journal_lines_fixture()[1].clone() },
+                JournalLine { credit: amount("600"), ..journal_lines_fixture()[2].clone() },
+            ],
+            ..invoice_fixture()
+        };
+        let _res = try_amend_invoice(deps.as_mut(), env.clone(), info, 2, amended).unwrap();
+        let balance = get_account_balance(
+            deps.as_ref(),
+            env.clone(),
+            wallet.clone(),
+            permit.clone(),
+            "401".to_string(),
+            Some("2025-02".to_string()),
+            Some("2025-03".to_string()),
+        )
+        .unwrap();
+        assert_eq!(balance.balance.credit, amount("600"));
+        let balance = get_account_balance(
+            deps.as_ref(),
+            env.clone(),
+            wallet.clone(),
+            permit.clone(),
+            "6061".to_string(),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(balance.balance.debit, amount("1500"));
+        assert_eq!(balance.balance.account_type, AccountType::Expense);
 
-        // Verify that the invoice was retrieved successfully
-        match list_invoice {
-            Err(StdError::GenericErr { .. }) => panic!("Must return unauthorized error"),
-            Err(e) => panic!("Unexpected error: {:?}", e),
-            Ok(response) => {
-                let invoices = response.vect_invoice;
-                assert_eq!(invoices.len(), 1);
-                let retrieved_invoice = &invoices[0];
-                assert_eq!(retrieved_invoice.invoice_number, "INV-001");
-                assert_eq!(retrieved_invoice.date, "2025-02-26");
-                assert_eq!(retrieved_invoice.client_name, "Client A");
-                assert_eq!(retrieved_invoice.description, "Service");
-                assert_eq!(retrieved_invoice.total_amount, "1000");
-                assert_eq!(retrieved_invoice.tax_amount, "100");
-                assert_eq!(retrieved_invoice.currency, "USD");
-                assert_eq!(retrieved_invoice.doc_hash, "hash123");
-                assert_eq!(retrieved_invoice.line_hash, "linehash123");
-                assert_eq!(retrieved_invoice.auditors, "Auditor A");
-                assert_eq!(retrieved_invoice.credibility, "High");
-                assert_eq!(retrieved_invoice.audit_state, "Pending");
+        let unknown = get_account_balance(deps.as_ref(), env, wallet.clone(), permit, "999".to_string(), None, None);
+        assert!(unknown.is_err());
+    }
+
+    /// Test function for fiscal periods, their closing sign-offs and reopening.
+    #[test]
+    fn fiscal_periods() {
+        // The permit wallet is an auditor, not the owner
+        let PermitSetup { mut deps, env, info, wallet, permit } = permit_setup(false);
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice_fixture()).unwrap();
+        let _res = try_update_auditor(deps.as_mut(), info.clone(), 0, wallet.clone()).unwrap();
+
+        // Only the owner manages the approvers and the periods, which cannot overlap
+        let approver = mock_info("approver", &[]);
+        assert!(try_set_approver(deps.as_mut(), approver.clone(), approver.sender.clone(), true).is_err());
+        let _res = try_set_approver(deps.as_mut(), info.clone(), approver.sender.clone(), true).unwrap();
+        let date = invoice_fixture().date;
+        let start = format!("{}-01-01", &date[..4]);
+        let end = format!("{}-12-31", &date[..4]);
+        let add_period = |deps: DepsMut, name: &str, start: &str, end: &str| {
+            try_add_fiscal_period(deps, info.clone(), name.to_string(), start.to_string(), end.to_string(), true)
+        };
+        let _res = add_period(deps.as_mut(), "FY", &start, &end).unwrap();
+        assert!(add_period(deps.as_mut(), "FY", "2100-01-01", "2100-12-31").is_err());
+        assert!(add_period(deps.as_mut(), "Overlap", &end, "2100-12-31").is_err());
+        assert!(add_period(deps.as_mut(), "Backwards", "2100-12-31", "2100-01-01").is_err());
+
+        // A period that requires an auditor is signed off once an auditor is engaged on it
+        let _res = add_period(deps.as_mut(), "FY2100", "2100-01-01", "2100-12-31").unwrap();
+        match try_close_period(deps.as_mut(), env.clone(), approver.clone(), "FY2100".to_string()) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "An Auditor must be engaged on fiscal period FY2100 before its closing is signed off")
+            }
+            _ => panic!("Must refuse a sign-off before an auditor is engaged"),
+        }
+
+        // Closing needs the approver, then an auditor since the period requires one
+        let auditor = mock_info(wallet.as_str(), &[]);
+        assert!(try_close_period(deps.as_mut(), env.clone(), info.clone(), "FY".to_string()).is_err());
+        let _res = try_close_period(deps.as_mut(), env.clone(), approver.clone(), "FY".to_string()).unwrap();
+        assert!(try_close_period(deps.as_mut(), env.clone(), approver.clone(), "FY".to_string()).is_err());
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice_fixture()).unwrap();
+        let _res = try_close_period(deps.as_mut(), env.clone(), auditor, "FY".to_string()).unwrap();
+
+        // Invoices dated in the closed period are rejected
+        match try_add(deps.as_mut(), env.clone(), info.clone(), invoice_fixture()) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, format!("Invoice date {} falls in the closed fiscal period FY", date))
             }
+            _ => panic!("Must return a closed period error"),
         }
+        assert!(try_amend_invoice(deps.as_mut(), env.clone(), info.clone(), 0, invoice_fixture()).is_err());
+        assert!(try_void_invoice(deps.as_mut(), info.clone(), 0, "Duplicate".to_string()).is_err());
+
+        // A recorded reopen lets them through again
+        let reason = "Late supplier invoice".to_string();
+        assert!(try_reopen_period(deps.as_mut(), env.clone(), info.clone(), "FY".to_string(), reason.clone()).is_err());
+        let _res = try_reopen_period(deps.as_mut(), env.clone(), approver.clone(), "FY".to_string(), reason).unwrap();
+        let _res = try_add(deps.as_mut(), env.clone(), info, invoice_fixture()).unwrap();
+
+        let periods = get_fiscal_periods(deps.as_ref(), env.clone(), wallet.clone(), permit).unwrap().periods;
+        assert_eq!(periods.len(), 2);
+        assert_eq!(periods[0].status, PeriodStatus::Open);
+        assert_eq!(periods[0].approver_signoff, None);
+        assert_eq!(periods[0].reopens.len(), 1);
+        assert_eq!(periods[0].reopens[0].reopened_by, approver.sender);
+        assert_eq!(periods[0].reopens[0].reopened_at, env.block.time);
     }
 
-    /// Test function to ensure proper contract initialization.
+    /// Test function for the tax code table and the tax mismatch flag.
     #[test]
-    fn proper_initialization() {
+    fn tax_codes() {
+        let PermitSetup { mut deps, env, info, wallet, permit } = permit_setup(false);
+
+        // Tagged lines must reference a known tax code, and only the owner manages the tax codes
+        let invoice = Invoice {
+            total_amount: "1200".to_string(),
+            tax_amount: "200".to_string(),
+            credibility: CredibilityScore(85),
+            lines: journal_lines_fixture(),
+            ..invoice_fixture()
+        };
+        setup_ledger(deps.as_mut(), "creator");
+        let mut unknown = invoice.clone();
+        unknown.lines[0].tax_code = Some("VAT5".to_string());
+        match try_add(deps.as_mut(), env.clone(), info.clone(), unknown) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Unknown tax code: VAT5"),
+            _ => panic!("Must return an unknown tax code error"),
+        }
+        let vat = TaxCode {
+            code: "VAT20".to_string(),
+            rate: Decimal::percent(20),
+            jurisdiction: "FR".to_string(),
+            active: true,
+        };
+        assert!(try_set_tax_code(deps.as_mut(), mock_info("anyone", &[]), vat.clone()).is_err());
+        let excessive = TaxCode { rate: Decimal::percent(120), ..vat.clone() };
+        assert!(try_set_tax_code(deps.as_mut(), info.clone(), excessive).is_err());
+
+        // A consistent tax amount leaves the audit state to the credibility score
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice.clone()).unwrap();
+        let stored = config_invoice_read(deps.as_ref().storage, &invoice_key(0)).load().unwrap();
+        assert!(stored.audit_flags.is_empty());
+        assert_eq!(stored.audit_state, AUDIT_STATE_PENDING);
+
+        // A mismatch beyond the tolerance is flagged for review instead of rejected
+        let reduced_rate = TaxCode { rate: Decimal::percent(10), ..vat };
+        let _res = try_set_tax_code(deps.as_mut(), info.clone(), reduced_rate).unwrap();
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice.clone()).unwrap();
+        let stored = config_invoice_read(deps.as_ref().storage, &invoice_key(1)).load().unwrap();
+        assert_eq!(stored.audit_flags, vec![AUDIT_FLAG_TAX_MISMATCH.to_string()]);
+        assert_eq!(stored.audit_state, AUDIT_STATE_MUST_REVIEW);
+
+        // Within the tolerance the same invoice passes again
+        assert!(try_set_tax_tolerance(deps.as_mut(), mock_info("anyone", &[]), Decimal::percent(10000)).is_err());
+        let _res = try_set_tax_tolerance(deps.as_mut(), info.clone(), Decimal::percent(10000)).unwrap();
+        let _res = try_amend_invoice(deps.as_mut(), env.clone(), info.clone(), 1, invoice).unwrap();
+        let stored = config_invoice_read(deps.as_ref().storage, &invoice_key(1)).load().unwrap();
+        assert!(stored.audit_flags.is_empty());
+
+        // The table is readable by the owner and the engaged auditors
+        let _res = try_update_auditor(deps.as_mut(), info, 0, wallet.clone()).unwrap();
+        let page = get_tax_codes(deps.as_ref(), env, wallet.clone(), permit, 0).unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.tax_codes[0].rate, Decimal::percent(10));
+        assert_eq!(page.tolerance, Decimal::percent(10000));
+    }
+
+    /// Test function for the VAT return of a fiscal period.
+    #[test]
+    fn vat_return() {
+        let PermitSetup { mut deps, env, info, wallet, permit } = permit_setup(true);
+        setup_ledger(deps.as_mut(), wallet.as_str());
+        let add_period = |deps: DepsMut, name: &str, start: &str, end: &str| {
+            try_add_fiscal_period(deps, info.clone(), name.to_string(), start.to_string(), end.to_string(), false)
+        };
+        let _res = add_period(deps.as_mut(), "Q1", "2025-01-01", "2025-03-31").unwrap();
+        let _res = add_period(deps.as_mut(), "Broken", "2025-04-15", "2025-05-31").unwrap();
+
+        // A EUR invoice in January, USD invoices in February and March, then one in April outside the period
+        let invoice = Invoice {
+            total_amount: "1200".to_string(),
+            tax_amount: "200".to_string(),
+            lines: journal_lines_fixture(),
+            ..invoice_fixture()
+        };
+        let dates = [("2025-01-15", "EUR"), ("2025-02-10", "USD"), ("2025-03-31", "USD"), ("2025-04-01", "USD")];
+        for (date, currency) in dates {
+            let dated = Invoice {
+                date: date.to_string(),
+                currency: currency.to_string(),
+                ..invoice.clone()
+            };
+            let _res = try_add(deps.as_mut(), env.clone(), info.clone(), dated).unwrap();
+        }
+
+        // The March invoice is voided and half of the February one is credited
+        let _res = try_void_invoice(deps.as_mut(), info.clone(), 2, "Duplicate".to_string()).unwrap();
+        let amount = |value: &str| Decimal::from_str(value).unwrap();
+        let credit_note = Invoice {
+            date: "2025-02-20".to_string(),
+            total_amount: "600".to_string(),
+            tax_amount: "100".to_string(),
+            kind: DocumentKind::CreditNote,
+            reference: Some(1),
+            lines: vec![
+                JournalLine { debit: amount("500"), ..journal_lines_fixture()[0].clone() },
+                JournalLine { debit: amount("100"), ..journal_lines_fixture()[1].clone() },
+                JournalLine { credit: amount("600"), ..journal_lines_fixture()[2].clone() },
+            ],
+            ..invoice_fixture()
+        };
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), credit_note).unwrap();
+
+        let vat_return = get_vat_return(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), "Q1".to_string())
+            .unwrap();
+        assert_eq!(
+            vat_return.totals,
+            vec![
+                VatTotals {
+                    tax_code: "VAT20".to_string(),
+                    currency: "EUR".to_string(),
+                    base: amount("1000"),
+                    tax: amount("200"),
+                    invoices: vec![0],
+                    ..VatTotals::default()
+                },
+                VatTotals {
+                    tax_code: "VAT20".to_string(),
+                    currency: "USD".to_string(),
+                    base: amount("1000"),
+                    tax: amount("200"),
+                    credited_base: amount("500"),
+                    credited_tax: amount("100"),
+                    invoices: vec![1, 4],
+                },
+            ]
+        );
+
+        // Returns need a known period made of whole months
+        let broken = get_vat_return(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), "Broken".to_string());
+        assert!(broken.is_err());
+        assert!(get_vat_return(deps.as_ref(), env, wallet.clone(), permit, "FY".to_string()).is_err());
+    }
+
+    /// Test function for the base currency and the exchange rates.
+    #[test]
+    fn multi_currency() {
+        let PermitSetup { mut deps, env, info, wallet, permit } = permit_setup(true);
+        setup_ledger(deps.as_mut(), wallet.as_str());
+
+        // Only the owner sets the base currency, which cannot change afterwards
+        let oracle = mock_info("oracle", &[]);
+        let eur = "EUR".to_string();
+        assert!(try_set_currency_config(deps.as_mut(), oracle.clone(), eur.clone(), None).is_err());
+        let oracle_address = Some(oracle.sender.clone());
+        let _res = try_set_currency_config(deps.as_mut(), info.clone(), eur.clone(), oracle_address).unwrap();
+        assert!(try_set_currency_config(deps.as_mut(), info.clone(), "USD".to_string(), None).is_err());
+
+        // A USD invoice needs a rate to EUR on its date, recorded by the owner or the oracle
+        let invoice = Invoice {
+            total_amount: "1200".to_string(),
+            tax_amount: "200".to_string(),
+            lines: journal_lines_fixture(),
+            ..invoice_fixture()
+        };
+        match try_add(deps.as_mut(), env.clone(), info.clone(), invoice.clone()) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "No exchange rate from USD to EUR on 2025-02-26"),
+            _ => panic!("Must return a missing exchange rate error"),
+        }
+        let amount = |value: &str| Decimal::from_str(value).unwrap();
+        let set_rate = |deps: DepsMut, info: MessageInfo| {
+            let (from, to, date) = ("USD".to_string(), eur.clone(), invoice.date.clone());
+            try_set_exchange_rate(deps, info, from, to, date, amount("0.9"))
+        };
+        assert!(set_rate(deps.as_mut(), mock_info("anyone", &[])).is_err());
+        let _res = set_rate(deps.as_mut(), oracle.clone()).unwrap();
+        let rate = query_exchange_rate(deps.as_ref(), "USD".to_string(), eur.clone(), invoice.date.clone()).unwrap();
+        assert_eq!(rate.rate.recorded_by, oracle.sender);
+
+        // Both the original amounts and their base equivalents are stored
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice.clone()).unwrap();
+        let stored = config_invoice_read(deps.as_ref().storage, &invoice_key(0)).load().unwrap();
+        assert_eq!(stored.total_amount, "1200");
+        assert_eq!(stored.exchange_rate, Some(amount("0.9")));
+        assert_eq!(stored.base_total_amount, Some(amount("1080")));
+        assert_eq!(stored.base_tax_amount, Some(amount("180")));
+        let in_euros = Invoice { currency: eur.clone(), ..invoice };
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), in_euros).unwrap();
+        let stored = config_invoice_read(deps.as_ref().storage, &invoice_key(1)).load().unwrap();
+        assert_eq!(stored.exchange_rate, Some(Decimal::one()));
+
+        // The ledger aggregates in the base currency
+        let trial = get_trial_balance(deps.as_ref(), env, wallet.clone(), permit, None, None).unwrap();
+        assert_eq!(trial.currency, Some(eur.clone()));
+        assert_eq!(trial.total_debit, amount("2280"));
+        assert_eq!(trial.total_credit, amount("2280"));
+
+        // Invoices sealed without a base currency prevent setting one
+        let mut late = mock_dependencies();
+        let _res = instantiate(late.as_mut(), mock_env(), info.clone(), InstantiateMsg { count: 0 }).unwrap();
+        let _res = try_add(late.as_mut(), mock_env(), info.clone(), invoice_fixture()).unwrap();
+        match try_set_currency_config(late.as_mut(), info, eur, None) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "The base currency must be set before the first invoice")
+            }
+            _ => panic!("Must refuse a base currency after the first invoice"),
+        }
+    }
+
+    /// Test function for the supplier registry and the supplier totals.
+    #[test]
+    fn supplier_registry() {
+        // The permit wallet is an auditor, not the owner
+        let PermitSetup { mut deps, env, info, wallet, permit } = permit_setup(false);
+
+        // Only the owner manages the registry, and identifiers are validated
+        let supplier = Supplier {
+            id: "ACME".to_string(),
+            legal_name: "Acme Supplies SAS".to_string(),
+            vat_number: Some("FR40303265045".to_string()),
+            country: "FR".to_string(),
+            iban_hash: Some(DOC_HASH.to_string()),
+            status: SupplierStatus::Active,
+            address: None,
+        };
+        assert!(try_set_supplier(deps.as_mut(), mock_info("anyone", &[]), supplier.clone()).is_err());
+        let bad_country = Supplier { country: "France".to_string(), ..supplier.clone() };
+        assert!(try_set_supplier(deps.as_mut(), info.clone(), bad_country).is_err());
+        let bad_iban = Supplier { iban_hash: Some("FR7630006000011234567890189".to_string()), ..supplier.clone() };
+        assert!(try_set_supplier(deps.as_mut(), info.clone(), bad_iban).is_err());
+        let _res = try_set_supplier(deps.as_mut(), info.clone(), supplier.clone()).unwrap();
+
+        // Invoices reference a known supplier
+        let invoice = Invoice { supplier: Some("ACME".to_string()), ..invoice_fixture() };
+        let unknown = Invoice { supplier: Some("NOPE".to_string()), ..invoice_fixture() };
+        match try_add(deps.as_mut(), env.clone(), info.clone(), unknown) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Unknown supplier: NOPE"),
+            _ => panic!("Must return an unknown supplier error"),
+        }
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice.clone()).unwrap();
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice_fixture()).unwrap();
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice.clone()).unwrap();
+        let credit_note = Invoice {
+            total_amount: "400".to_string(),
+            tax_amount: "40".to_string(),
+            kind: DocumentKind::CreditNote,
+            reference: Some(0),
+            ..invoice.clone()
+        };
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), credit_note).unwrap();
+        let _res = try_void_invoice(deps.as_mut(), info.clone(), 2, "Duplicate".to_string()).unwrap();
+
+        // Amendments keep the supplier of the sealed invoice
+        let amended = Invoice { total_amount: "1500".to_string(), supplier: None, ..invoice_fixture() };
+        let _res = try_amend_invoice(deps.as_mut(), env.clone(), info.clone(), 0, amended).unwrap();
+        let stored = config_invoice_read(deps.as_ref().storage, &invoice_key(0)).load().unwrap();
+        assert_eq!(stored.supplier, Some("ACME".to_string()));
+
+        // Totals cover the invoices that are not voided, and auditors only see their own invoices
+        let _res = try_update_auditor(deps.as_mut(), info.clone(), 0, wallet.clone()).unwrap();
+        let response =
+            get_supplier_invoices(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), "ACME".to_string(), 0)
+                .unwrap();
+        let amount = |value: &str| Decimal::from_str(value).unwrap();
+        assert_eq!(response.supplier, supplier);
+        assert_eq!(
+            response.totals,
+            SupplierTotals {
+                invoice_count: 3,
+                total_amount: amount("1500"),
+                tax_amount: amount("100"),
+                credited_amount: amount("400"),
+            }
+        );
+        assert_eq!(response.invoice_indexes, vec![0]);
+        assert_eq!(response.vect_invoice[0].total_amount, "1500");
+        let page = get_suppliers(deps.as_ref(), env.clone(), wallet.clone(), permit, 0).unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.suppliers, vec![supplier.clone()]);
+
+        // Inactive suppliers cannot be invoiced any more
+        let inactive = Supplier { status: SupplierStatus::Inactive, ..supplier };
+        let _res = try_set_supplier(deps.as_mut(), info.clone(), inactive).unwrap();
+        assert!(try_add(deps.as_mut(), env, info, invoice).is_err());
+    }
+
+    /// Test function for payments and the aged payables.
+    #[test]
+    fn payments() {
+        let PermitSetup { mut deps, mut env, info, wallet, permit } = permit_setup(true);
+        env.block.time = Timestamp::from_seconds(1_743_508_800);
+        assert_eq!(block_date(&env), "2025-04-01");
+
+        // Overdue, due on its date (2025-02-26), and not due yet
+        for due_date in [Some("2025-03-28"), None, Some("2025-05-15")] {
+            let invoice = Invoice { due_date: due_date.map(str::to_string), ..invoice_fixture() };
+            let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice).unwrap();
+        }
+        let early = Invoice { due_date: Some("2025-01-01".to_string()), ..invoice_fixture() };
+        assert!(try_add(deps.as_mut(), env.clone(), info.clone(), early).is_err());
+
+        let amount = |value: &str| Decimal::from_str(value).unwrap();
+        let pay = |deps: DepsMut, info: MessageInfo, invoice_index: u32, value: &str| {
+            let (date, method) = ("2025-03-30".to_string(), PaymentMethod::BankTransfer);
+            let reference_hash = DOC_HASH.to_string();
+            try_record_payment(deps, env.clone(), info, invoice_index, amount(value), date, method, reference_hash)
+        };
+        assert!(pay(deps.as_mut(), mock_info("anyone", &[]), 0, "400").is_err());
+        match pay(deps.as_mut(), info.clone(), 0, "1000.01") {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Payment of 1000.01 exceeds the outstanding balance 1000")
+            }
+            _ => panic!("Must return an overpayment error"),
+        }
+
+        // Partial payments leave an outstanding balance until the invoice is paid
+        let _res = pay(deps.as_mut(), info.clone(), 0, "400").unwrap();
+        let status = get_payments(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), 0).unwrap();
+        assert_eq!(status.outstanding, amount("600"));
+        assert_eq!(status.status, PaymentStatus::Overdue);
+        let _res = pay(deps.as_mut(), info.clone(), 0, "600").unwrap();
+        let status = get_payments(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), 0).unwrap();
+        assert_eq!(status.payments.len(), 2);
+        assert_eq!(status.payments[1].recorded_by, info.sender);
+        assert_eq!(status.paid_amount, amount("1000"));
+        assert_eq!(status.status, PaymentStatus::Paid);
+        let _res = pay(deps.as_mut(), info.clone(), 2, "100").unwrap();
+        let status = get_payments(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), 2).unwrap();
+        assert_eq!(status.status, PaymentStatus::PartiallyPaid);
+
+        // Paid invoices cannot be voided or amended below what was paid
+        assert!(try_void_invoice(deps.as_mut(), info.clone(), 0, "Duplicate".to_string()).is_err());
+        let below = Invoice { total_amount: "50".to_string(), ..invoice_fixture() };
+        assert!(try_amend_invoice(deps.as_mut(), env.clone(), info.clone(), 2, below).is_err());
+
+        // Payments are dated on or after their invoice, outside the closed periods
+        let (date, method, reference_hash) = ("2025-02-01".into(), PaymentMethod::BankTransfer, DOC_HASH.into());
+        let backdated =
+            try_record_payment(deps.as_mut(), env.clone(), info.clone(), 2, amount("10"), date, method, reference_hash);
+        match backdated {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Payment date 2025-02-01 is before the invoice date 2025-02-26")
+            }
+            _ => panic!("Must refuse a payment dated before the invoice"),
+        }
+        let (name, start, end) = ("Q1".to_string(), "2025-01-01".to_string(), "2025-03-31".to_string());
+        let _res = try_add_fiscal_period(deps.as_mut(), info.clone(), name, start, end, false).unwrap();
+        let _res = try_set_approver(deps.as_mut(), info.clone(), Addr::unchecked("approver"), true).unwrap();
+        let _res = try_close_period(deps.as_mut(), env.clone(), mock_info("approver", &[]), "Q1".to_string()).unwrap();
+        match pay(deps.as_mut(), info.clone(), 2, "100") {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Payment date 2025-03-30 falls in the closed fiscal period Q1")
+            }
+            _ => panic!("Must refuse a payment dated in a closed period"),
+        }
+        let (date, method, reference_hash) = ("2025-04-02".into(), PaymentMethod::BankTransfer, DOC_HASH.into());
+        let late =
+            try_record_payment(deps.as_mut(), env.clone(), info.clone(), 2, amount("10"), date, method, reference_hash);
+        match late {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Invoice date 2025-02-26 falls in the closed fiscal period Q1")
+            }
+            _ => panic!("Must refuse to pay an invoice dated in a closed period"),
+        }
+
+        // Outstanding balances are aged from their due date
+        let aged = get_aged_payables(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), None).unwrap();
+        assert_eq!(aged.as_of, "2025-04-01");
+        assert_eq!(aged.not_due, amount("900"));
+        assert_eq!(aged.overdue_31_to_60, amount("1000"));
+        assert_eq!(aged.total, amount("1900"));
+        assert_eq!(aged.payables.iter().map(|payable| payable.invoice_index).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(aged.payables[0].days_overdue, 34);
+        let as_of = Some("2025-08-01".to_string());
+        let aged = get_aged_payables(deps.as_ref(), env, wallet.clone(), permit, as_of).unwrap();
+        assert_eq!(aged.overdue_61_to_90, amount("900"));
+        assert_eq!(aged.overdue_over_90, amount("1000"));
+    }
+
+    // Simulates a SNIP-20 token contract calling `Receive` on 2025-04-01, after `from` sent it `amount` with an
+    // embedded message
+    fn mock_token_send(deps: DepsMut, token: &str, from: &str, amount: u128, msg: &ReceiveMsg) -> StdResult<Response> {
+        let receive = ExecuteMsg::Receive {
+            sender: Addr::unchecked(from),
+            from: Addr::unchecked(from),
+            amount: Uint128::new(amount),
+            memo: None,
+            msg: Some(to_binary(msg).unwrap()),
+        };
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(1_743_508_800);
+        execute(deps, env, mock_info(token, &[]), receive)
+    }
+
+    /// Test function for invoices paid in SNIP-20 tokens.
+    #[test]
+    fn token_payments() {
         let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        let instantiate_msg = InstantiateMsg { count: 0 };
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
 
-        // Set up the mock environment and instantiate message
-        let info = mock_info(
-            "creator",
-            &[Coin {
-                denom: "earth".to_string(),
-                amount: Uint128::new(1000),
-            }],
+        // Registering a token registers the contract as its receiver
+        let register = ExecuteMsg::RegisterToken {
+            contract: Addr::unchecked("susdc"),
+            code_hash: "token_code_hash".to_string(),
+            currency: "USD".to_string(),
+            decimals: 6,
+        };
+        assert!(execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), register.clone()).is_err());
+        let invalid = ExecuteMsg::RegisterToken {
+            contract: Addr::unchecked("ab"),
+            code_hash: "token_code_hash".to_string(),
+            currency: "USD".to_string(),
+            decimals: 6,
+        };
+        assert!(execute(deps.as_mut(), mock_env(), info.clone(), invalid).is_err());
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), register).unwrap();
+        assert_eq!(res.messages.len(), 1);
+
+        let supplier = Supplier {
+            id: "ACME".to_string(),
+            legal_name: "Acme Supplies SAS".to_string(),
+            vat_number: None,
+            country: "FR".to_string(),
+            iban_hash: None,
+            status: SupplierStatus::Active,
+            address: Some(Addr::unchecked("acme_wallet")),
+        };
+        let invalid = Supplier { address: Some(Addr::unchecked("ab")), ..supplier.clone() };
+        assert!(try_set_supplier(deps.as_mut(), info.clone(), invalid).is_err());
+        let _res = try_set_supplier(deps.as_mut(), info.clone(), supplier.clone()).unwrap();
+        let without_address = Supplier { id: "NOADDR".to_string(), address: None, ..supplier };
+        let _res = try_set_supplier(deps.as_mut(), info.clone(), without_address).unwrap();
+        for (supplier, currency) in [("ACME", "USD"), ("ACME", "EUR"), ("NOADDR", "USD")] {
+            let invoice = Invoice {
+                supplier: Some(supplier.to_string()),
+                currency: currency.to_string(),
+                ..invoice_fixture()
+            };
+            let _res = try_add(deps.as_mut(), mock_env(), info.clone(), invoice).unwrap();
+        }
+
+        // Only the owner pays, with a registered token, in the currency of the invoice, to a supplier with an address
+        let pay = |invoice_index| ReceiveMsg::PayInvoice { invoice_index };
+        assert!(mock_token_send(deps.as_mut(), "fake", "creator", 400_000_000, &pay(0)).is_err());
+        assert!(mock_token_send(deps.as_mut(), "susdc", "anyone", 400_000_000, &pay(0)).is_err());
+        assert!(mock_token_send(deps.as_mut(), "susdc", "creator", 400_000_000, &pay(1)).is_err());
+        match mock_token_send(deps.as_mut(), "susdc", "creator", 400_000_000, &pay(2)) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Supplier NOADDR has no registered address"),
+            _ => panic!("Must return a missing address error"),
+        }
+        assert!(mock_token_send(deps.as_mut(), "susdc", "creator", 1_000_000_001, &pay(0)).is_err());
+
+        // The tokens are forwarded to the supplier and the payment is recorded
+        let res = mock_token_send(deps.as_mut(), "susdc", "creator", 400_000_000, &pay(0)).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        match &res.messages[0].msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, msg, .. }) => {
+                assert_eq!(contract_addr, "susdc");
+                let transfer: serde_json::Value = serde_json::from_slice(msg.as_slice()).unwrap();
+                assert_eq!(transfer["transfer"]["recipient"], "acme_wallet");
+                assert_eq!(transfer["transfer"]["amount"], "400000000");
+            }
+            _ => panic!("Must forward the tokens to the supplier"),
+        }
+        let stored = config_invoice_read(deps.as_ref().storage, &invoice_key(0)).load().unwrap();
+        assert_eq!(stored.paid_amount, Decimal::from_str("400").unwrap());
+        let payment = payments_read(deps.as_ref().storage).load(&payment_key(0, 0)).unwrap();
+        assert_eq!(payment.method, PaymentMethod::Token);
+        assert_eq!(payment.token, Some(Addr::unchecked("susdc")));
+        assert_eq!(payment.recorded_by, info.sender);
+    }
+
+    /// Test function for the three-way match of invoices with purchase orders and goods receipts.
+    #[test]
+    fn three_way_match() {
+        // The permit wallet is an auditor, not the owner
+        let PermitSetup { mut deps, env, info, wallet, permit } = permit_setup(false);
+
+        // Only the owner creates purchase orders, with unique numbers and items
+        let amount = |value: &str| Decimal::from_str(value).unwrap();
+        let line = |item: &str, quantity: &str, unit_price: &str| ItemLine {
+            item: item.to_string(),
+            quantity: amount(quantity),
+            unit_price: amount(unit_price),
+        };
+        let order = PurchaseOrder {
+            number: "PO-1".to_string(),
+            currency: "USD".to_string(),
+            date: "2025-02-01".to_string(),
+            lines: vec![line("WIDGET", "10", "50"), line("GADGET", "4", "100")],
+            ..Default::default()
+        };
+        assert!(try_create_purchase_order(deps.as_mut(), mock_info("anyone", &[]), order.clone()).is_err());
+        let twice = PurchaseOrder {
+            lines: vec![line("WIDGET", "1", "50"), line("WIDGET", "2", "50")],
+            ..order.clone()
+        };
+        assert!(try_create_purchase_order(deps.as_mut(), info.clone(), twice).is_err());
+        for (quantity, unit_price) in [("0", "50"), ("10", "0")] {
+            let zero = PurchaseOrder { lines: vec![line("WIDGET", quantity, unit_price)], ..order.clone() };
+            assert!(try_create_purchase_order(deps.as_mut(), info.clone(), zero).is_err());
+        }
+        let _res = try_create_purchase_order(deps.as_mut(), info.clone(), order.clone()).unwrap();
+        assert!(try_create_purchase_order(deps.as_mut(), info.clone(), order).is_err());
+
+        // Only the widgets are received so far
+        let received = |item: &str, quantity: &str| ItemQuantity {
+            item: item.to_string(),
+            quantity: amount(quantity),
+        };
+        let date = "2025-02-20".to_string();
+        let unknown_item = vec![received("BOLT", "1")];
+        let res = try_record_goods_receipt(deps.as_mut(), info.clone(), "PO-1".into(), date.clone(), unknown_item);
+        assert!(res.is_err());
+        let lines = vec![received("WIDGET", "10")];
+        let _res = try_record_goods_receipt(deps.as_mut(), info.clone(), "PO-1".into(), date, lines).unwrap();
+
+        // Invoices must be in the currency of their purchase order
+        let invoice = Invoice {
+            purchase_order: Some("PO-1".to_string()),
+            items: vec![line("WIDGET", "10", "50"), line("GADGET", "4", "100")],
+            auditors: wallet.to_string(),
+            ..invoice_fixture()
+        };
+        let in_euros = Invoice { currency: "EUR".to_string(), ..invoice.clone() };
+        match try_add(deps.as_mut(), env.clone(), info.clone(), in_euros) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Purchase order PO-1 is in USD"),
+            _ => panic!("Must return a currency error"),
+        }
+
+        // An invoice for goods not received yet is flagged and cannot be approved
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice.clone()).unwrap();
+        let stored = config_invoice_read(deps.as_ref().storage, &invoice_key(0)).load().unwrap();
+        let result = stored.match_result.unwrap();
+        assert!(!result.matched);
+        assert_eq!(
+            result.reasons,
+            vec!["item GADGET: 4 invoiced, 0 received".to_string(), "900 invoiced, 500 received".to_string()]
         );
-        let init_msg = InstantiateMsg { count: 17 };
+        assert_eq!(stored.audit_flags, vec![AUDIT_FLAG_PO_MISMATCH.to_string()]);
+        assert_eq!(stored.audit_state, AUDIT_STATE_MUST_REVIEW);
+        let auditor = mock_info(wallet.as_str(), &[]);
+        match try_audit_invoice(deps.as_mut(), auditor.clone(), 0, AuditDecision::Approve) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "An Invoice that does not match its purchase order cannot be approved")
+            }
+            _ => panic!("Must refuse to approve a mismatched invoice"),
+        }
 
-        // Instantiate the contract
-        let res = instantiate(deps.as_mut(), mock_env(), info.clone(), init_msg).unwrap();
+        // Receiving the gadgets matches the invoice again
+        let lines = vec![received("GADGET", "4")];
+        let date = "2025-02-24".to_string();
+        let _res = try_record_goods_receipt(deps.as_mut(), info.clone(), "PO-1".into(), date, lines).unwrap();
+        let stored = config_invoice_read(deps.as_ref().storage, &invoice_key(0)).load().unwrap();
+        assert!(stored.match_result.unwrap().matched);
+        assert!(stored.audit_flags.is_empty());
+        assert_eq!(stored.audit_state, AUDIT_STATE_PENDING);
+        let _res = try_audit_invoice(deps.as_mut(), auditor.clone(), 0, AuditDecision::Approve).unwrap();
+
+        // A second invoice for the same goods exceeds what was ordered, and prices are checked within tolerance
+        let _res = try_set_match_tolerance(
+            deps.as_mut(),
+            info.clone(),
+            MatchTolerance { quantity: Decimal::zero(), amount: Decimal::percent(5) },
+        )
+        .unwrap();
+        let again = Invoice {
+            total_amount: "204".to_string(),
+            tax_amount: "0".to_string(),
+            items: vec![line("WIDGET", "4", "51")],
+            ..invoice
+        };
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), again).unwrap();
+        let stored = config_invoice_read(deps.as_ref().storage, &invoice_key(1)).load().unwrap();
+        assert_eq!(
+            stored.match_result.unwrap().reasons,
+            vec![
+                "item WIDGET: 14 invoiced, 10 ordered".to_string(),
+                "item WIDGET: 14 invoiced, 10 received".to_string(),
+                "1104 invoiced, 900 received".to_string(),
+            ]
+        );
+
+        // Voiding the first invoice releases its goods for the second one
+        let _res = try_void_invoice(deps.as_mut(), info.clone(), 0, "Duplicate".to_string()).unwrap();
+        let _res = try_update_auditor(deps.as_mut(), info.clone(), 1, wallet.clone()).unwrap();
+        let response =
+            get_purchase_order(deps.as_ref(), env.clone(), wallet.clone(), permit, "PO-1".to_string()).unwrap();
+        assert_eq!(response.order.invoices, vec![0, 1]);
+        assert_eq!(response.order.received, vec![received("WIDGET", "10"), received("GADGET", "4")]);
+        assert_eq!(response.receipts.len(), 2);
+        assert_eq!(response.receipts[1].lines, vec![received("GADGET", "4")]);
+        assert_eq!(response.match_results[1], Some(MatchResult { matched: true, reasons: vec![] }));
+        assert_eq!(response.tolerance.amount, Decimal::percent(5));
+
+        // Items need a quantity and a unit price
+        let zero = Invoice {
+            purchase_order: Some("PO-1".to_string()),
+            items: vec![line("WIDGET", "4", "0")],
+            ..invoice_fixture()
+        };
+        match try_add(deps.as_mut(), env.clone(), info.clone(), zero) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Invalid unit price of item WIDGET"),
+            _ => panic!("Must refuse an item without a unit price"),
+        }
+
+        // An approved invoice that an amendment of an earlier one takes over the goods received is reviewed again
+        let order = PurchaseOrder {
+            number: "PO-2".to_string(),
+            currency: "USD".to_string(),
+            date: "2025-02-01".to_string(),
+            lines: vec![line("WIDGET", "10", "50")],
+            ..Default::default()
+        };
+        let _res = try_create_purchase_order(deps.as_mut(), info.clone(), order).unwrap();
+        let (date, lines) = ("2025-02-20".to_string(), vec![received("WIDGET", "10")]);
+        let _res = try_record_goods_receipt(deps.as_mut(), info.clone(), "PO-2".into(), date, lines).unwrap();
+        let widgets = |quantity: &str, total_amount: &str| Invoice {
+            purchase_order: Some("PO-2".to_string()),
+            total_amount: total_amount.to_string(),
+            tax_amount: "0".to_string(),
+            items: vec![line("WIDGET", quantity, "50")],
+            auditors: wallet.to_string(),
+            ..invoice_fixture()
+        };
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), widgets("4", "200")).unwrap();
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), widgets("6", "300")).unwrap();
+        let _res = try_audit_invoice(deps.as_mut(), auditor.clone(), 3, AuditDecision::Approve).unwrap();
+        let _res = try_amend_invoice(deps.as_mut(), env.clone(), info.clone(), 2, widgets("5", "250")).unwrap();
+        let stored = config_invoice_read(deps.as_ref().storage, &invoice_key(3)).load().unwrap();
+        assert_eq!(stored.audit_flags, vec![AUDIT_FLAG_PO_MISMATCH.to_string()]);
+        assert_eq!(stored.audit_state, AUDIT_STATE_MUST_REVIEW);
+
+        // Invoices dated in a closed period are no longer matched again, audited or given another auditor
+        let order = PurchaseOrder {
+            number: "PO-3".to_string(),
+            currency: "USD".to_string(),
+            date: "2025-02-01".to_string(),
+            lines: vec![line("WIDGET", "10", "50")],
+            ..Default::default()
+        };
+        let _res = try_create_purchase_order(deps.as_mut(), info.clone(), order).unwrap();
+        let (date, lines) = ("2025-02-20".to_string(), vec![received("WIDGET", "10")]);
+        let _res = try_record_goods_receipt(deps.as_mut(), info.clone(), "PO-3".into(), date, lines).unwrap();
+        let po3 = |quantity: &str, total_amount: &str, date: &str| Invoice {
+            purchase_order: Some("PO-3".to_string()),
+            date: date.to_string(),
+            ..widgets(quantity, total_amount)
+        };
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), po3("4", "200", "2025-03-10")).unwrap();
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), po3("6", "300", "2025-02-26")).unwrap();
+        let approver = mock_info("approver", &[]);
+        let _res = try_set_approver(deps.as_mut(), info.clone(), approver.sender.clone(), true).unwrap();
+        let (name, start, end) = ("FEB".to_string(), "2025-02-01".to_string(), "2025-02-28".to_string());
+        let _res = try_add_fiscal_period(deps.as_mut(), info.clone(), name.clone(), start, end, false).unwrap();
+        let _res = try_close_period(deps.as_mut(), env.clone(), approver, name).unwrap();
+        let _res = try_amend_invoice(deps.as_mut(), env, info.clone(), 4, po3("5", "250", "2025-03-10")).unwrap();
+        let stored = config_invoice_read(deps.as_ref().storage, &invoice_key(5)).load().unwrap();
+        assert!(stored.match_result.unwrap().matched);
+        assert!(stored.audit_flags.is_empty());
+        match try_audit_invoice(deps.as_mut(), auditor, 5, AuditDecision::FlagIssue) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Invoice date 2025-02-26 falls in the closed fiscal period FEB")
+            }
+            _ => panic!("Must refuse an audit decision in a closed period"),
+        }
+        assert!(try_update_auditor(deps.as_mut(), info, 5, Addr::unchecked("other")).is_err());
+    }
+
+    /// Test function for the approval of invoices above the approval thresholds.
+    #[test]
+    fn approval_workflow() {
+        // The permit wallet is an approver, not the owner
+        let PermitSetup { mut deps, mut env, info, wallet, permit } = permit_setup(false);
+        let _res = try_set_approver(deps.as_mut(), info.clone(), wallet.clone(), true).unwrap();
+        let _res = try_set_approver(deps.as_mut(), info.clone(), Addr::unchecked("alice"), true).unwrap();
+        let _res = try_set_approver(deps.as_mut(), info.clone(), Addr::unchecked("creator"), true).unwrap();
+
+        // Only the owner sets the rules, and each rule needs an approval
+        let rule = |threshold: u64, approvals: u32| ApprovalRule {
+            threshold: Decimal::from_atomics(threshold, 0).unwrap(),
+            approvals,
+        };
+        let rules = vec![rule(1000, 1), rule(10000, 2)];
+        assert!(try_set_approval_rules(deps.as_mut(), mock_info("anyone", &[]), rules.clone()).is_err());
+        assert!(try_set_approval_rules(deps.as_mut(), info.clone(), vec![rule(500, 0)]).is_err());
+        let _res = try_set_approval_rules(deps.as_mut(), info.clone(), rules.clone()).unwrap();
+
+        // Invoices up to the lowest threshold are sealed straight away
+        let res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice_fixture()).unwrap();
+        assert_eq!(res.attributes[0].key, "invoice_index");
+
+        // Larger invoices are submitted for approval, and invalid ones are rejected on submission
+        let invalid = Invoice {
+            total_amount: "12000".to_string(),
+            doc_hash: "hash123".to_string(),
+            ..invoice_fixture()
+        };
+        assert!(try_add(deps.as_mut(), env.clone(), info.clone(), invalid).is_err());
+        let large = Invoice { total_amount: "12000".to_string(), ..invoice_fixture() };
+        let res = try_add(deps.as_mut(), env.clone(), info.clone(), large).unwrap();
+        assert_eq!(res.attributes[0].value, "0");
+        assert_eq!(res.attributes[1].value, "2");
+        let medium = Invoice { total_amount: "5000".to_string(), ..invoice_fixture() };
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), medium).unwrap();
+        assert_eq!(config_read(deps.as_ref().storage).load().unwrap().invoice_count, 1);
+
+        // Approvers see the pending invoices
+        let response = get_pending_approvals(deps.as_ref(), env.clone(), wallet.clone(), permit.clone()).unwrap();
+        assert_eq!(response.pending_indexes, vec![0, 1]);
+        assert_eq!(response.pending[0].required_approvals, 2);
+        assert_eq!(response.pending[1].required_approvals, 1);
+        assert_eq!(response.rules, rules);
+
+        // Only distinct approvers other than the submitter approve
+        match try_approve_invoice(deps.as_mut(), env.clone(), mock_info("anyone", &[]), 0) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Only an Approver can approve an Invoice"),
+            _ => panic!("Must refuse an approval from a non-approver"),
+        }
+        assert!(try_approve_invoice(deps.as_mut(), env.clone(), info.clone(), 0).is_err());
+        let alice = mock_info("alice", &[]);
+        let res = try_approve_invoice(deps.as_mut(), env.clone(), alice.clone(), 0).unwrap();
+        assert_eq!(res.attributes.len(), 2);
+        match try_approve_invoice(deps.as_mut(), env.clone(), alice.clone(), 0) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Invoice is already approved by this Approver"),
+            _ => panic!("Must refuse a second approval from the same approver"),
+        }
+
+        // The last approval seals the invoice with its approvers
+        env.block.height += 10;
+        let approver = mock_info(wallet.as_str(), &[]);
+        let res = try_approve_invoice(deps.as_mut(), env.clone(), approver, 0).unwrap();
+        assert_eq!(res.attributes[2].key, "invoice_index");
+        assert_eq!(res.attributes[2].value, "1");
+        let stored = config_invoice_read(deps.as_ref().storage, &invoice_key(1)).load().unwrap();
+        assert_eq!(stored.total_amount, "12000");
+        assert_eq!(stored.approvals, vec![Addr::unchecked("alice"), wallet.clone()]);
+        assert_eq!(stored.sealed_by, Some(Addr::unchecked("creator")));
+        assert_eq!(stored.sealed_at_height, env.block.height);
+        assert!(try_approve_invoice(deps.as_mut(), env.clone(), alice.clone(), 0).is_err());
+
+        let response = get_pending_approvals(deps.as_ref(), env.clone(), wallet.clone(), permit.clone()).unwrap();
+        assert_eq!(response.pending_indexes, vec![1]);
+
+        // An approver other than the submitter rejects a pending invoice, which can no longer be approved
+        let reason = "Not budgeted".to_string();
+        assert!(try_reject_invoice(deps.as_mut(), mock_info("anyone", &[]), 1, reason.clone()).is_err());
+        assert!(try_reject_invoice(deps.as_mut(), alice.clone(), 0, reason.clone()).is_err());
+        let _res = try_reject_invoice(deps.as_mut(), alice.clone(), 1, reason.clone()).unwrap();
+        match try_approve_invoice(deps.as_mut(), env.clone(), alice.clone(), 1) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Invoice is already rejected"),
+            _ => panic!("Must refuse to approve a rejected invoice"),
+        }
+        let rejected = pending_invoices_read(deps.as_ref().storage).load(&1u32.to_be_bytes()).unwrap();
+        assert_eq!(rejected.rejected_by, Some(alice.sender));
+        assert_eq!(rejected.rejection_reason, Some(reason.clone()));
+        assert_eq!(config_read(deps.as_ref().storage).load().unwrap().invoice_count, 2);
+
+        // The owner withdraws its own submission
+        let withdrawn = Invoice { total_amount: "5000".to_string(), ..invoice_fixture() };
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), withdrawn).unwrap();
+        let _res = try_reject_invoice(deps.as_mut(), info.clone(), 2, reason).unwrap();
+        let response = get_pending_approvals(deps.as_ref(), env.clone(), wallet.clone(), permit.clone()).unwrap();
+        assert!(response.pending_indexes.is_empty());
+
+        // An amendment within the approvals recorded by the current version is applied straight away
+        let amended = Invoice { total_amount: "13000".to_string(), ..invoice_fixture() };
+        let res = try_amend_invoice(deps.as_mut(), env.clone(), info.clone(), 1, amended).unwrap();
+        assert_eq!(res.attributes[1].key, "version");
+
+        // A larger amendment is submitted for approval instead, and the current version is left unchanged
+        let amended = Invoice { total_amount: "1000000".to_string(), ..invoice_fixture() };
+        let res = try_amend_invoice(deps.as_mut(), env.clone(), info.clone(), 0, amended).unwrap();
+        assert_eq!(res.attributes[1].key, "pending_index");
+        assert_eq!(res.attributes[1].value, "3");
+        assert_eq!(res.attributes[2].value, "2");
+        let stored = config_invoice_read(deps.as_ref().storage, &invoice_key(0)).load().unwrap();
+        assert_eq!(stored.version, 1);
+        assert_eq!(stored.total_amount, "1000");
+
+        // An approver revoked after approving no longer counts
+        let _res = try_approve_invoice(deps.as_mut(), env.clone(), mock_info("alice", &[]), 3).unwrap();
+        let _res = try_set_approver(deps.as_mut(), info.clone(), Addr::unchecked("alice"), false).unwrap();
+        let res = try_approve_invoice(deps.as_mut(), env.clone(), mock_info(wallet.as_str(), &[]), 3).unwrap();
+        assert_eq!(res.attributes.len(), 2);
+        assert_eq!(res.attributes[1].value, "1");
+        let _res = try_set_approver(deps.as_mut(), info.clone(), Addr::unchecked("bob"), true).unwrap();
+        let res = try_approve_invoice(deps.as_mut(), env.clone(), mock_info("bob", &[]), 3).unwrap();
+        assert_eq!((res.attributes[2].key.as_str(), res.attributes[2].value.as_str()), ("version", "2"));
+        assert_eq!((res.attributes[3].key.as_str(), res.attributes[3].value.as_str()), ("invoice_index", "0"));
+        let stored = config_invoice_read(deps.as_ref().storage, &invoice_key(0)).load().unwrap();
+        assert_eq!(stored.version, 2);
+        assert_eq!(stored.total_amount, "1000000");
+        assert_eq!(stored.approvals, vec![wallet.clone(), Addr::unchecked("bob")]);
+        assert_eq!(stored.sealed_by, Some(Addr::unchecked("creator")));
+
+        // Auditors do not see the invoices waiting for approval
+        let _res = try_set_approver(deps.as_mut(), info.clone(), wallet.clone(), false).unwrap();
+        let _res = try_update_auditor(deps.as_mut(), info.clone(), 0, wallet.clone()).unwrap();
+        assert!(get_pending_approvals(deps.as_ref(), env.clone(), wallet.clone(), permit).is_err());
+
+        // Only an approver of the invoices of a period signs off its closing
+        let (name, start, end) = ("FY2025".to_string(), "2025-01-01".to_string(), "2025-12-31".to_string());
+        let _res = try_add_fiscal_period(deps.as_mut(), info.clone(), name.clone(), start, end, false).unwrap();
+        match try_close_period(deps.as_mut(), env.clone(), info, name.clone()) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Only an Approver of the invoices of fiscal period FY2025 can sign off its closing")
+            }
+            _ => panic!("Must refuse the sign-off of an approver of no invoice of the period"),
+        }
+        let _res = try_close_period(deps.as_mut(), env, mock_info("bob", &[]), name).unwrap();
+    }
+
+    /// Test function for expense categories and their budgets.
+    #[test]
+    fn budgets() {
+        let PermitSetup { mut deps, env, info, wallet, permit } = permit_setup(true);
+        let period = |name: &str, start: &str, end: &str| (name.to_string(), start.to_string(), end.to_string());
+        let (name, start, end) = period("FY2025", "2025-01-01", "2025-12-31");
+        let _res = try_add_fiscal_period(deps.as_mut(), info.clone(), name, start, end, false).unwrap();
+
+        // Only the owner manages the categories and their budgets
+        let travel = ExpenseCategory {
+            code: "TRAVEL".to_string(),
+            name: "Travel".to_string(),
+            active: true,
+        };
+        let amount = |value: &str| Decimal::from_str(value).unwrap();
+        assert!(try_set_expense_category(deps.as_mut(), mock_info("anyone", &[]), travel.clone()).is_err());
+        let _res = try_set_expense_category(deps.as_mut(), info.clone(), travel.clone()).unwrap();
+        let (category, fy) = ("TRAVEL".to_string(), "FY2025".to_string());
+        let unknown_period =
+            try_set_budget(deps.as_mut(), info.clone(), category.clone(), "FY2024".into(), amount("2000"));
+        assert!(unknown_period.is_err());
+        assert!(try_set_budget(deps.as_mut(), info.clone(), "NOPE".into(), fy.clone(), amount("2000")).is_err());
+        let _res = try_set_budget(deps.as_mut(), info.clone(), category.clone(), fy.clone(), amount("2000")).unwrap();
+
+        // Invoices reference a known category, and their net amounts are spent against its budget
+        let unknown = Invoice { category: Some("NOPE".to_string()), ..invoice_fixture() };
+        match try_add(deps.as_mut(), env.clone(), info.clone(), unknown) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Unknown expense category: NOPE"),
+            _ => panic!("Must return an unknown category error"),
+        }
+        let invoice = Invoice { category: Some("TRAVEL".to_string()), ..invoice_fixture() };
+        let res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice.clone()).unwrap();
+        assert!(res.events.is_empty());
+        let res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice.clone()).unwrap();
+        assert!(res.events.is_empty());
+
+        // The invoice pushing the category over budget is accepted, flagged and reported by an event
+        let res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice.clone()).unwrap();
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].ty, "budget_overrun");
+        assert_eq!(
+            res.events[0].attributes.iter().map(|attr| attr.value.as_str()).collect::<Vec<_>>(),
+            vec!["2", "TRAVEL", "FY2025", "2000", "2700"]
+        );
+        let stored = config_invoice_read(deps.as_ref().storage, &invoice_key(2)).load().unwrap();
+        assert_eq!(stored.audit_flags, vec![AUDIT_FLAG_BUDGET_OVERRUN.to_string()]);
+        assert_eq!(stored.audit_state, AUDIT_STATE_MUST_REVIEW);
+        let response =
+            get_budget_report(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), fy.clone()).unwrap();
+        assert_eq!(
+            response.lines,
+            vec![BudgetLine {
+                category: travel,
+                budget: Some(amount("2000")),
+                spent: amount("2700"),
+                overrun: true,
+            }]
+        );
+
+        // Voiding and amending move the spend back under budget, and amendments keep the category
+        let _res = try_void_invoice(deps.as_mut(), info.clone(), 2, "Duplicate".to_string()).unwrap();
+        let amended = Invoice { total_amount: "500".to_string(), tax_amount: "50".to_string(), ..invoice_fixture() };
+        let _res = try_amend_invoice(deps.as_mut(), env.clone(), info.clone(), 1, amended).unwrap();
+        let stored = config_invoice_read(deps.as_ref().storage, &invoice_key(1)).load().unwrap();
+        assert_eq!(stored.category, Some("TRAVEL".to_string()));
+        let response = get_budget_report(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), fy).unwrap();
+        assert_eq!(response.lines[0].spent, amount("1350"));
+        assert!(!response.lines[0].overrun);
+
+        // Reversals apply to the period the invoice was spent against when sealed, not to a period added since
+        let earlier = Invoice { date: "2024-06-01".to_string(), ..invoice.clone() };
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), earlier.clone()).unwrap();
+        let (name, start, end) = period("FY2024", "2024-01-01", "2024-12-31");
+        let _res = try_add_fiscal_period(deps.as_mut(), info.clone(), name, start, end, false).unwrap();
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), earlier).unwrap();
+        let _res = try_void_invoice(deps.as_mut(), info, 3, "Duplicate".to_string()).unwrap();
+        let response = get_budget_report(deps.as_ref(), env, wallet.clone(), permit, "FY2024".to_string()).unwrap();
+        assert_eq!(response.lines[0].spent, amount("900"));
+    }
+
+    /// Test function for the audit sample drawn for a fiscal period.
+    #[test]
+    fn audit_sampling() {
+        // The permit wallet is an auditor, not the owner
+        let PermitSetup { mut deps, mut env, info, wallet, permit } = permit_setup(false);
+        for (name, start, end) in [("FY2024", "2024-01-01", "2024-12-31"), ("FY2025", "2025-01-01", "2025-12-31")] {
+            let (name, start, end) = (name.to_string(), start.to_string(), end.to_string());
+            let _res = try_add_fiscal_period(deps.as_mut(), info.clone(), name, start, end, false).unwrap();
+        }
+
+        // Seven invoices in 2025 across two strata, one voided and one in 2024
+        let amounts = ["100", "200", "300", "500", "1000", "2000", "900000", "400"];
+        for (position, amount) in amounts.iter().enumerate() {
+            let invoice = Invoice {
+                total_amount: amount.to_string(),
+                tax_amount: "0".to_string(),
+                date: format!("2025-{:02}-10", position + 1),
+                ..invoice_fixture()
+            };
+            let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice).unwrap();
+        }
+        let _res = try_void_invoice(deps.as_mut(), info.clone(), 7, "Duplicate".to_string()).unwrap();
+        let old = Invoice { date: "2024-06-01".to_string(), ..invoice_fixture() };
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), old).unwrap();
+        let _res = try_update_auditor(deps.as_mut(), info.clone(), 8, wallet.clone()).unwrap();
+
+        // Only an auditor engaged on the period draws a sample, from the block randomness
+        let auditor = mock_info(wallet.as_str(), &[]);
+        let strata = vec![Decimal::from_atomics(1000u128, 0).unwrap()];
+        let (fy, other) = ("FY2025".to_string(), "FY2024".to_string());
+        let res = try_draw_sample(deps.as_mut(), env.clone(), info.clone(), fy.clone(), 2, strata.clone(), false);
+        assert!(res.is_err());
+        match try_draw_sample(deps.as_mut(), env.clone(), auditor.clone(), fy.clone(), 2, strata.clone(), false) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Only an Auditor of fiscal period FY2025 can draw a sample")
+            }
+            _ => panic!("Must refuse an auditor of another period"),
+        }
+        let _res = try_update_auditor(deps.as_mut(), info.clone(), 0, wallet.clone()).unwrap();
+        env.block.random = None;
+        match try_draw_sample(deps.as_mut(), env.clone(), auditor.clone(), fy.clone(), 2, strata.clone(), false) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "The block randomness is not available"),
+            _ => panic!("Must require the block randomness"),
+        }
+        env.block.random = Some(Binary::from(vec![7u8; 32]));
+        let _res = try_draw_sample(deps.as_mut(), env.clone(), auditor.clone(), fy.clone(), 2, strata.clone(), false)
+            .unwrap();
+
+        // Two invoices are drawn from each stratum of the period, and the sample cannot be redrawn
+        let response =
+            get_audit_sample(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), fy.clone()).unwrap();
+        let sample = response.sample;
+        assert_eq!(sample.population, 7);
+        assert_eq!(sample.invoices.len(), 4);
+        assert_eq!(sample.invoices.iter().filter(|index| **index < 4).count(), 2);
+        assert!(sample.invoices.iter().all(|index| *index < 7));
+        assert_eq!(sample.seed.len(), 64);
+        assert_eq!(response.vect_invoice.len(), 4);
+        match try_draw_sample(deps.as_mut(), env.clone(), auditor.clone(), fy.clone(), 2, strata, false) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Fiscal period FY2025 is already sampled"),
+            _ => panic!("Must refuse to redraw a sample"),
+        }
+
+        // Once replaced on the period, the auditor no longer sees its sample
+        let _res = try_update_auditor(deps.as_mut(), info.clone(), 0, Addr::unchecked("successor")).unwrap();
+        match get_audit_sample(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), fy.clone()) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Only an Auditor of fiscal period FY2025 can run this query")
+            }
+            _ => panic!("Must refuse the sample to a replaced auditor"),
+        }
+
+        // Monetary-unit sampling favours the largest amounts
+        let mut weighted = mock_dependencies();
+        let _res = instantiate(weighted.as_mut(), env.clone(), info.clone(), InstantiateMsg { count: 0 }).unwrap();
+        let (name, start, end) = ("FY2025".to_string(), "2025-01-01".to_string(), "2025-12-31".to_string());
+        let _res = try_add_fiscal_period(weighted.as_mut(), info.clone(), name, start, end, false).unwrap();
+        for (position, amount) in amounts.iter().enumerate() {
+            let invoice = Invoice {
+                total_amount: amount.to_string(),
+                tax_amount: "0".to_string(),
+                date: format!("2025-{:02}-10", position + 1),
+                ..invoice_fixture()
+            };
+            let _res = try_add(weighted.as_mut(), env.clone(), info.clone(), invoice).unwrap();
+        }
+        let _res = try_update_auditor(weighted.as_mut(), info, 0, wallet.clone()).unwrap();
+        let _res = try_draw_sample(weighted.as_mut(), env.clone(), auditor, fy, 1, vec![], true).unwrap();
+        let sample = audit_samples_read(weighted.as_ref().storage).load(b"FY2025").unwrap();
+        assert_eq!(sample.invoices, vec![6]);
+        assert!(sample.monetary_unit);
+
+        // The owner cannot see the samples
+        let mut owned = mock_dependencies();
+        let owner_info = mock_info(wallet.as_str(), &[]);
+        let _res = instantiate(owned.as_mut(), env.clone(), owner_info, InstantiateMsg { count: 0 }).unwrap();
+        match get_audit_sample(owned.as_ref(), env, wallet.clone(), permit, other) {
+            Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Only an Auditor can run this query"),
+            _ => panic!("Must refuse the sample to the owner"),
+        }
+    }
+
+    /// Test function for the anomaly statistics of a fiscal period.
+    #[test]
+    fn anomaly_statistics() {
+        // The permit wallet is an auditor, not the owner
+        let PermitSetup { mut deps, env, info, wallet, permit } = permit_setup(false);
+        let (name, start, end) = ("FY2025".to_string(), "2025-01-01".to_string(), "2025-12-31".to_string());
+        let _res = try_add_fiscal_period(deps.as_mut(), info.clone(), name, start, end, false).unwrap();
+        let rules = vec![ApprovalRule {
+            threshold: Decimal::from_atomics(5000u128, 0).unwrap(),
+            approvals: 2,
+        }];
+        let _res = try_set_approval_rules(deps.as_mut(), info.clone(), rules).unwrap();
+
+        // Wednesday, Saturday, Wednesday and Sunday invoices, a voided one and a credit note
+        let entries = [
+            ("4800", "2025-02-26"),
+            ("1200", "2025-03-01"),
+            ("150.5", "2025-02-26"),
+            ("0.75", "2025-03-02"),
+            ("2000", "2025-04-10"),
+        ];
+        for (total_amount, date) in entries {
+            let invoice = Invoice {
+                total_amount: total_amount.to_string(),
+                tax_amount: "0".to_string(),
+                date: date.to_string(),
+                ..invoice_fixture()
+            };
+            let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice).unwrap();
+        }
+        let _res = try_void_invoice(deps.as_mut(), info.clone(), 4, "Duplicate".to_string()).unwrap();
+        let credit_note = Invoice {
+            total_amount: "100".to_string(),
+            tax_amount: "0".to_string(),
+            kind: DocumentKind::CreditNote,
+            reference: Some(0),
+            ..invoice_fixture()
+        };
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), credit_note).unwrap();
+        assert!(config_invoice_read(deps.as_ref().storage, &invoice_key(0)).load().unwrap().near_threshold);
+
+        // Only an auditor reads the statistics
+        let fy = "FY2025".to_string();
+        let response = get_anomaly_statistics(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), fy.clone());
+        assert!(response.is_err());
+        let _res = try_update_auditor(deps.as_mut(), info.clone(), 0, wallet.clone()).unwrap();
+        let response =
+            get_anomaly_statistics(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), fy.clone()).unwrap();
+        assert_eq!(response.invoices, 4);
+        assert_eq!(response.first_digits, vec![2, 0, 0, 1, 0, 0, 1, 0, 0]);
+        assert_eq!(response.first_digit_shares[0], Decimal::percent(50));
+        assert_eq!(response.benford_shares[0], Decimal::permille(301));
+        assert_eq!(response.round_amounts, 2);
+        assert_eq!(response.round_amount_share, Decimal::percent(50));
+        assert_eq!(response.weekend_dates, 2);
+        assert_eq!(response.near_threshold, 1);
+        assert_eq!(response.near_threshold_share, Decimal::percent(25));
 
-        assert_eq!(0, res.messages.len());
+        // Amendments move the counters of the previous version to the new one
+        let amended = Invoice {
+            total_amount: "3900".to_string(),
+            tax_amount: "0".to_string(),
+            date: "2025-03-01".to_string(),
+            ..invoice_fixture()
+        };
+        let _res = try_amend_invoice(deps.as_mut(), env.clone(), info.clone(), 0, amended).unwrap();
+        let response = get_anomaly_statistics(deps.as_ref(), env.clone(), wallet.clone(), permit, fy).unwrap();
+        assert_eq!(response.invoices, 4);
+        assert_eq!(response.first_digits, vec![2, 0, 1, 0, 0, 0, 1, 0, 0]);
+        assert_eq!(response.round_amounts, 2);
+        assert_eq!(response.weekend_dates, 3);
+        assert_eq!(response.near_threshold, 0);
 
-        // Query the state to verify initialization
-        let res = query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::GetCount {}
-        ).unwrap();
-        let value: CountResponse = from_binary(&res).unwrap();
-        assert_eq!(17, value.count);
+        // Invoices sealed before the counters of their month existed are not taken out of them
+        let key = month_index(2025, 2).to_be_bytes();
+        anomaly_months(deps.as_mut().storage).remove(&key);
+        let _res = try_void_invoice(deps.as_mut(), info.clone(), 2, "Duplicate".to_string()).unwrap();
+        assert!(anomaly_months_read(deps.as_ref().storage).may_load(&key).unwrap().is_none());
+
+        // Amounts in another currency are counted in the base currency
+        let mut converted = mock_dependencies();
+        let _res = instantiate(converted.as_mut(), env.clone(), info.clone(), InstantiateMsg { count: 0 }).unwrap();
+        let _res = try_set_currency_config(converted.as_mut(), info.clone(), "EUR".to_string(), None).unwrap();
+        let (from, to, date) = ("USD".to_string(), "EUR".to_string(), "2025-02-26".to_string());
+        let rate = Decimal::percent(95);
+        let _res = try_set_exchange_rate(converted.as_mut(), info.clone(), from, to, date, rate).unwrap();
+        let invoice = Invoice { total_amount: "1000".to_string(), tax_amount: "0".to_string(), ..invoice_fixture() };
+        let _res = try_add(converted.as_mut(), env, info, invoice).unwrap();
+        let counters = anomaly_months_read(converted.as_ref().storage).load(&key).unwrap();
+        assert_eq!(counters.first_digits, vec![0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(counters.round_amounts, 0);
     }
 
-   /// Test function for incrementing a counter.
+    /// Test function for the detection of split invoices.
     #[test]
-    fn increment() {
-        let mut deps = mock_dependencies_with_balance(&[Coin {
-            denom: "token".to_string(),
-            amount: Uint128::new(2),
-        }]);
+    fn split_invoice_detection() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        let instantiate_msg = InstantiateMsg { count: 0 };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg).unwrap();
+        let supplier = Supplier {
+            id: "ACME".to_string(),
+            legal_name: "Acme Supplies SAS".to_string(),
+            vat_number: Some("FR40303265045".to_string()),
+            country: "FR".to_string(),
+            iban_hash: Some(DOC_HASH.to_string()),
+            status: SupplierStatus::Active,
+            address: None,
+        };
+        let _res = try_set_supplier(deps.as_mut(), info.clone(), supplier).unwrap();
 
-        // Set up the mock environment and instantiate message
-        let info = mock_info(
-            "creator",
-            &[Coin {
-                denom: "token".to_string(),
-                amount: Uint128::new(2),
-            }],
-        );
+        // Only the owner sets the rule, with a window and a threshold
+        let rule = SplitRule { window_days: 7, threshold: Decimal::from_atomics(5000u128, 0).unwrap() };
+        assert!(try_set_split_rule(deps.as_mut(), mock_info("anyone", &[]), Some(rule.clone())).is_err());
+        let no_window = SplitRule { window_days: 0, ..rule.clone() };
+        assert!(try_set_split_rule(deps.as_mut(), info.clone(), Some(no_window)).is_err());
+        let _res = try_set_split_rule(deps.as_mut(), info.clone(), Some(rule.clone())).unwrap();
 
-        let init_msg = InstantiateMsg { count: 17 };
+        // Invoices above the threshold or outside the window are not combined, and the window includes its bounds
+        let entries = [
+            ("2400", "2025-02-19"),
+            ("6000", "2025-02-21"),
+            ("100", "2025-02-18"),
+            ("2400", "2025-02-24"),
+        ];
+        for (total_amount, date) in entries {
+            let invoice = Invoice {
+                total_amount: total_amount.to_string(),
+                tax_amount: "0".to_string(),
+                date: date.to_string(),
+                supplier: Some("ACME".to_string()),
+                ..invoice_fixture()
+            };
+            let res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice).unwrap();
+            assert!(res.events.is_empty());
+        }
+        let flags = |storage: &dyn Storage, index: u32| {
+            config_invoice_read(storage, &invoice_key(index)).load().unwrap().audit_flags
+        };
+        assert!(flags(&deps.storage, 3).is_empty());
 
-        // Instantiate the contract
-        let _res = instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+        // The invoice that takes the cluster over the threshold flags the whole cluster
+        let invoice = Invoice {
+            total_amount: "300".to_string(),
+            tax_amount: "0".to_string(),
+            supplier: Some("ACME".to_string()),
+            ..invoice_fixture()
+        };
+        let res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice.clone()).unwrap();
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].ty, "split_invoice");
+        let cluster = res.events[0].attributes.iter().find(|attribute| attribute.key == "cluster").unwrap();
+        assert_eq!(cluster.value, "3,0");
+        for index in [0, 3, 4] {
+            assert_eq!(flags(&deps.storage, index), vec![AUDIT_FLAG_SPLIT_INVOICE.to_string()]);
+        }
+        assert!(flags(&deps.storage, 1).is_empty());
+        assert!(flags(&deps.storage, 2).is_empty());
+        let first = config_invoice_read(&deps.storage, &invoice_key(0)).load().unwrap();
+        assert_eq!(first.audit_state, AUDIT_STATE_MUST_REVIEW);
 
-        // Anyone can increment the counter
-        let info = mock_info(
-            "anyone",
-            &[Coin {
-                denom: "token".to_string(),
-                amount: Uint128::new(2),
-            }],
-        );
+        // Flagging an earlier invoice keeps its version and its seal
+        assert_eq!(first.version, 1);
+        assert_eq!(first.supersedes, None);
+        assert!(invoice_versions_read(&deps.storage).may_load(&invoice_version_key(0, 1)).unwrap().is_none());
 
-        let exec_msg = ExecuteMsg::Increment {};
-        let _res = execute(deps.as_mut(), mock_env(), info.clone(), exec_msg).unwrap();
+        // Amendments are scanned again, and invoices without a supplier are never combined
+        let amended = Invoice { total_amount: "250".to_string(), ..invoice.clone() };
+        let res = try_amend_invoice(deps.as_mut(), env.clone(), info.clone(), 4, amended).unwrap();
+        assert_eq!(res.events[0].ty, "split_invoice");
+        assert_eq!(flags(&deps.storage, 4), vec![AUDIT_FLAG_SPLIT_INVOICE.to_string()]);
+        let amended = Invoice { total_amount: "100".to_string(), ..invoice };
+        let res = try_amend_invoice(deps.as_mut(), env.clone(), info.clone(), 4, amended).unwrap();
+        assert!(res.events.is_empty());
+        assert!(flags(&deps.storage, 4).is_empty());
+        let invoice = Invoice { total_amount: "4900".to_string(), tax_amount: "0".to_string(), ..invoice_fixture() };
+        let res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice).unwrap();
+        assert!(res.events.is_empty());
 
-        // Should increase counter by 1
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
-        let value: CountResponse = from_binary(&res).unwrap();
-        assert_eq!(18, value.count);
+        // Without a rule nothing is flagged
+        let _res = try_set_split_rule(deps.as_mut(), info.clone(), None).unwrap();
+        let invoice = Invoice {
+            total_amount: "4900".to_string(),
+            tax_amount: "0".to_string(),
+            supplier: Some("ACME".to_string()),
+            ..invoice_fixture()
+        };
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice).unwrap();
+        assert!(flags(&deps.storage, 6).is_empty());
+
+        // Invoices dated in a closed period count towards a cluster but are left unchanged
+        let _res = try_set_split_rule(deps.as_mut(), info.clone(), Some(rule)).unwrap();
+        let approver = mock_info("approver", &[]);
+        let _res = try_set_approver(deps.as_mut(), info.clone(), approver.sender.clone(), true).unwrap();
+        let (name, start, end) = ("JAN".to_string(), "2025-01-01".to_string(), "2025-01-31".to_string());
+        let _res = try_add_fiscal_period(deps.as_mut(), info.clone(), name.clone(), start, end, false).unwrap();
+        let acme = |total_amount: &str, date: &str| Invoice {
+            total_amount: total_amount.to_string(),
+            tax_amount: "0".to_string(),
+            date: date.to_string(),
+            supplier: Some("ACME".to_string()),
+            ..invoice_fixture()
+        };
+        let _res = try_add(deps.as_mut(), env.clone(), info.clone(), acme("2400", "2025-01-30")).unwrap();
+        let _res = try_close_period(deps.as_mut(), env.clone(), approver, name).unwrap();
+        let _res = try_add(deps.as_mut(), env, info, acme("2700", "2025-02-02")).unwrap();
+        assert_eq!(flags(&deps.storage, 8), vec![AUDIT_FLAG_SPLIT_INVOICE.to_string()]);
+        assert!(flags(&deps.storage, 7).is_empty());
+        assert_eq!(config_invoice_read(&deps.storage, &invoice_key(7)).load().unwrap().version, 1);
     }
 
-    /// Test function to verify that only the contract creator can reset the counter.
+    /// Test function for the audit opinion issued on a fiscal period.
     #[test]
-    fn reset() {
-        let mut deps = mock_dependencies_with_balance(&[Coin {
-            denom: "token".to_string(),
-            amount: Uint128::new(2),
-        }]);
-        let info = mock_info(
-            "creator",
-            &[Coin {
-                denom: "token".to_string(),
-                amount: Uint128::new(2),
-            }],
-        );
-        let init_msg = InstantiateMsg { count: 17 };
-
-        let _res = instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
-
-        // Verify that only the creator can reset the counter
+    fn audit_opinion() {
+        let PermitSetup { mut deps, env, info, wallet, permit } = permit_setup(false);
+        let periods = [("FY2025", "2025-01-01", "2025-12-31", false), ("FY2026", "2026-01-01", "2026-12-31", true)];
+        for (name, start, end, require_auditor) in periods {
+            let (name, start, end) = (name.to_string(), start.to_string(), end.to_string());
+            let _res = try_add_fiscal_period(deps.as_mut(), info.clone(), name, start, end, require_auditor).unwrap();
+        }
+        for date in ["2025-02-26", "2025-03-01", "2026-01-15"] {
+            let invoice = Invoice { date: date.to_string(), ..invoice_fixture() };
+            let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice).unwrap();
+        }
+        let _res = try_void_invoice(deps.as_mut(), info.clone(), 1, "Duplicate".to_string()).unwrap();
+        let _res = try_update_auditor(deps.as_mut(), info.clone(), 0, wallet.clone()).unwrap();
+        let auditor = mock_info(wallet.as_str(), &[]);
+        let _res = try_set_approver(deps.as_mut(), info.clone(), Addr::unchecked("approver"), true).unwrap();
+        let approver = mock_info("approver", &[]);
+        let fy = "FY2025".to_string();
 
-        let info = mock_info(
-            "anyone",
-            &[Coin {
-                denom: "token".to_string(),
-                amount: Uint128::new(2),
-            }],
-        );
-        let exec_msg = ExecuteMsg::Reset { count: 5 };
+        // The period must be closed first
+        let (opinion, report) = (OpinionType::Qualified, DOC_HASH.to_string());
+        match try_issue_audit_opinion(deps.as_mut(), env.clone(), auditor.clone(), fy.clone(), opinion, report, false) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Fiscal period FY2025 must be closed before its audit opinion")
+            }
+            _ => panic!("Must refuse an opinion on an open period"),
+        }
+        let _res = try_close_period(deps.as_mut(), env.clone(), approver.clone(), fy.clone()).unwrap();
 
-        let res = execute(deps.as_mut(), mock_env(), info, exec_msg);
+        // Only an auditor engaged on the period issues an opinion, with the hash of the report
+        let report = DOC_HASH.to_string();
+        let res = try_issue_audit_opinion(deps.as_mut(), env.clone(), info.clone(), fy.clone(), opinion, report, false);
+        assert!(res.is_err());
+        let _res = try_update_auditor(deps.as_mut(), info.clone(), 2, Addr::unchecked("other")).unwrap();
+        let (other, report) = (mock_info("other", &[]), DOC_HASH.to_string());
+        match try_issue_audit_opinion(deps.as_mut(), env.clone(), other, fy.clone(), opinion, report, false) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Only an Auditor of fiscal period FY2025 can issue an audit opinion")
+            }
+            _ => panic!("Must refuse an auditor of another period"),
+        }
+        let (period, report) = (fy.clone(), "report".to_string());
+        let res = try_issue_audit_opinion(deps.as_mut(), env.clone(), auditor.clone(), period, opinion, report, false);
+        assert!(res.is_err());
+        let (period, report) = (fy.clone(), DOC_HASH.to_string());
+        let res = try_issue_audit_opinion(deps.as_mut(), env.clone(), auditor.clone(), period, opinion, report, false);
+        let res = res.unwrap();
+        assert_eq!(res.attributes[2].value, "2");
 
-        match res {
-            Err(StdError::GenericErr { .. }) => {}
-            _ => panic!("Must return unauthorized error"),
+        // The commitment covers the invoices dated in the period, voided ones included
+        let mut hasher = Sha256::new();
+        for (index, voided) in [(0u32, 0u8), (1, 1)] {
+            let invoice = config_invoice_read(deps.as_ref().storage, &invoice_key(index)).load().unwrap();
+            hasher.update(index.to_be_bytes());
+            hasher.update(invoice.line_hash.as_bytes());
+            hasher.update([voided]);
         }
+        let commitment: String = hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect();
+        let response = get_audit_opinion(deps.as_ref(), env.clone(), wallet.clone(), permit, fy.clone()).unwrap();
+        assert_eq!(response.opinion.commitment, commitment);
+        assert_eq!(response.opinion.invoices, vec![0, 1]);
+        assert_eq!(response.opinion.opinion, OpinionType::Qualified);
+        assert_eq!(response.opinion.issued_by, wallet.clone());
+        assert!(query_public_audit_opinion(deps.as_ref(), fy.clone()).is_err());
 
-        // Only the original creator can reset the counter
-        let info = mock_info(
-            "creator",
-            &[Coin {
-                denom: "token".to_string(),
-                amount: Uint128::new(2),
-            }],
-        );
-        let exec_msg = ExecuteMsg::Reset { count: 5 };
+        // The period is locked for good
+        let period = fiscal_periods_read(deps.as_ref().storage).load(fy.as_bytes()).unwrap();
+        assert_eq!(period.status, PeriodStatus::Audited);
+        let invoice = Invoice { date: "2025-05-01".to_string(), ..invoice_fixture() };
+        assert!(try_add(deps.as_mut(), env.clone(), info.clone(), invoice).is_err());
+        assert!(try_amend_invoice(deps.as_mut(), env.clone(), info.clone(), 0, invoice_fixture()).is_err());
+        let reason = "Restatement".to_string();
+        assert!(try_reopen_period(deps.as_mut(), env.clone(), approver.clone(), fy.clone(), reason).is_err());
+        assert!(try_close_period(deps.as_mut(), env.clone(), approver.clone(), fy.clone()).is_err());
+        let (opinion, report) = (OpinionType::Adverse, DOC_HASH.to_string());
+        let res = try_issue_audit_opinion(deps.as_mut(), env.clone(), auditor.clone(), fy, opinion, report, true);
+        assert!(res.is_err());
 
-        let _res = execute(deps.as_mut(), mock_env(), info.clone(), exec_msg).unwrap();
+        // A period that requires an auditor is closed by the sign-off of an auditor engaged on it
+        let fy = "FY2026".to_string();
+        let _res = try_close_period(deps.as_mut(), env.clone(), approver, fy.clone()).unwrap();
+        let (other, opinion, report) = (mock_info("other", &[]), OpinionType::Unqualified, DOC_HASH.to_string());
+        match try_issue_audit_opinion(deps.as_mut(), env.clone(), other.clone(), fy.clone(), opinion, report, true) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Fiscal period FY2026 must be closed before its audit opinion")
+            }
+            _ => panic!("Must wait for the auditor sign-off"),
+        }
+        match try_close_period(deps.as_mut(), env.clone(), auditor, fy.clone()) {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Only an Approver or an Auditor can sign off a period")
+            }
+            _ => panic!("Must refuse the sign-off of an auditor of another period"),
+        }
+        let _res = try_close_period(deps.as_mut(), env.clone(), other.clone(), fy.clone()).unwrap();
 
-        // Verify that the counter has been reset to 5
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
-        let value: CountResponse = from_binary(&res).unwrap();
-        assert_eq!(5, value.count);
+        // A public opinion can be retrieved without a permit
+        let report = DOC_HASH.to_string();
+        let _res = try_issue_audit_opinion(deps.as_mut(), env, other, fy.clone(), opinion, report, true).unwrap();
+        let response = query_public_audit_opinion(deps.as_ref(), fy).unwrap();
+        assert_eq!(response.opinion.invoices, vec![2]);
+        assert!(response.opinion.public);
     }
 
-    /// Test function for adding an invoice.
+    /// Test function for the Merkle trees of the ledger and their inclusion proofs.
     #[test]
-    fn add_invoice() {
-        // Initialize mock dependencies
-        let mut deps = mock_dependencies_with_balance(&[Coin {
-            denom: "token".to_string(),
-            amount: Uint128::new(2),
-        }]);
-
-        // Set up the mock environment and instantiate message
-        let info = mock_info(
-            "creator",
-            &[Coin {
-                denom: "token".to_string(),
-                amount: Uint128::new(2),
-            }],
-        );
-
-        // Instantiate the contract
-        let instantiate_msg = InstantiateMsg { count: 0 };
-        let _res = instantiate(
-            deps.as_mut(),
-            mock_env(),
-            info.clone(),
-            instantiate_msg
-        ).unwrap();
+    fn merkle_tree() {
+        let PermitSetup { mut deps, env, info, wallet, permit } = permit_setup(false);
+        let (name, start, end) = ("Q1-2025".to_string(), "2025-01-01".to_string(), "2025-03-31".to_string());
+        let _res = try_add_fiscal_period(deps.as_mut(), info.clone(), name, start, end, false).unwrap();
 
-        // Define an invoice to add
-        let invoice = Invoice {
-            invoice_number: "INV-001".to_string(),
-            date: "2025-02-26".to_string(),
-            client_name: "Client A".to_string(),
-            description: "Service".to_string(),
-            total_amount: "1000".to_string(),
-            tax_amount: "100".to_string(),
-            currency: "USD".to_string(),
-            doc_hash: "hash123".to_string(),
-            line_hash: "linehash123".to_string(),
-            auditors: "Auditor A".to_string(),
-            credibility: "High".to_string(),
-            audit_state: "Pending".to_string(),
+        // Every sealed version is appended, amendments included
+        let sealed = |storage: &dyn Storage, invoice_index: u32| {
+            let invoice = config_invoice_read(storage, &invoice_key(invoice_index)).load().unwrap();
+            (invoice.line_hash, invoice.field_commitment)
+        };
+        let mut leaves = vec![];
+        for (number, date) in [("1", "2025-02-26"), ("2", "2025-05-10"), ("3", "2025-03-01"), ("4", "2025-06-30")] {
+            let invoice = Invoice { invoice_number: number.to_string(), date: date.to_string(), ..invoice_fixture() };
+            let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice).unwrap();
+            leaves.push(sealed(deps.as_ref().storage, leaves.len() as u32));
+        }
+        let amended = Invoice {
+            invoice_number: "1".to_string(),
+            description: "Corrected".to_string(),
+            ..invoice_fixture()
         };
+        let _res = try_amend_invoice(deps.as_mut(), env.clone(), info.clone(), 0, amended).unwrap();
+        leaves.push(sealed(deps.as_ref().storage, 0));
 
-        // Call the try_add function
-        let execute_msg = ExecuteMsg::Add { invoice: invoice.clone() };
-        let _res = execute(
-            deps.as_mut(),
-            mock_env(),
-            info.clone(),
-            execute_msg
-        ).unwrap();
+        let to_hex = |node: &[u8]| node.iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+        let root_of = |leaves: &[(String, String)]| {
+            let mut level: Vec<[u8; 32]> =
+                leaves.iter().map(|(line_hash, fields)| merkle_leaf_hash(line_hash, fields)).collect();
+            let mut zero = [0u8; 32];
+            while level.len() > 1 {
+                if level.len() % 2 == 1 {
+                    level.push(zero);
+                }
+                level = level.chunks(2).map(|pair| merkle_parent(&pair[0], &pair[1])).collect();
+                zero = merkle_parent(&zero, &zero);
+            }
+            to_hex(&level[0])
+        };
+        let from_hex = |node: &str| -> [u8; 32] {
+            let bytes: Vec<u8> = (0..32).map(|i| u8::from_str_radix(&node[2 * i..2 * i + 2], 16).unwrap()).collect();
+            bytes.try_into().unwrap()
+        };
+        let verify = |leaf_hash: &str, proof: &MerkleProof| {
+            let mut node = from_hex(leaf_hash);
+            for (level, sibling) in proof.siblings.iter().enumerate() {
+                let sibling = from_hex(sibling);
+                node = if (proof.leaf >> level).is_multiple_of(2) {
+                    merkle_parent(&node, &sibling)
+                } else {
+                    merkle_parent(&sibling, &node)
+                };
+            }
+            to_hex(&node) == proof.root
+        };
 
-        // Verify that the invoice was added successfully
-        assert_eq!(_res.messages.len(), 0);
+        // Only the owner and the engaged auditors read the roots and the proofs
+        assert!(get_merkle_roots(deps.as_ref(), env.clone(), wallet.clone(), permit.clone()).is_err());
+        let proof = get_inclusion_proof(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), 0, None, None);
+        assert!(proof.is_err());
+        let _res = try_update_auditor(deps.as_mut(), info, 0, wallet.clone()).unwrap();
 
-        // Retrieve and verify the saved invoice
-        let index = b"0"; // Convert the integer to a byte slice
-        let stored_invoice: Invoice = config_invoice_read(deps.as_mut().storage, index).load().unwrap();
+        let response = get_merkle_roots(deps.as_ref(), env.clone(), wallet.clone(), permit.clone()).unwrap();
+        assert_eq!(response.ledger.leaf_count, 5);
+        assert_eq!(response.ledger.root, root_of(&leaves));
+        assert_eq!(response.periods.len(), 1);
+        assert_eq!(response.periods[0].period, "Q1-2025");
+        assert_eq!(response.periods[0].tree.leaf_count, 3);
+        let period_leaves = [leaves[0].clone(), leaves[2].clone(), leaves[4].clone()];
+        assert_eq!(response.periods[0].tree.root, root_of(&period_leaves));
 
-        assert_eq!(stored_invoice.invoice_number, "INV-001");
-        assert_eq!(stored_invoice.date, "2025-02-26");
-        assert_eq!(stored_invoice.client_name, "Client A");
-        assert_eq!(stored_invoice.description, "Service");
-        assert_eq!(stored_invoice.total_amount, "1000");
-        assert_eq!(stored_invoice.tax_amount, "100");
-        assert_eq!(stored_invoice.currency, "USD");
-        assert_eq!(stored_invoice.doc_hash, "hash123");
-        assert_eq!(stored_invoice.line_hash, "linehash123");
-        assert_eq!(stored_invoice.auditors, "Auditor A");
-        assert_eq!(stored_invoice.credibility, "High");
-        assert_eq!(stored_invoice.audit_state, "Pending");
+        // The proofs of the latest version hold against the current roots
+        for invoice_index in 0..4 {
+            let (wallet, permit) = (wallet.clone(), permit.clone());
+            let proof = get_inclusion_proof(deps.as_ref(), env.clone(), wallet, permit, invoice_index, None, None);
+            let proof = proof.unwrap();
+            assert!(verify(&proof.leaf_hash, &proof.ledger));
+            assert_eq!(proof.ledger.root, response.ledger.root);
+            assert_eq!(proof.period.is_some(), invoice_index % 2 == 0);
+            if let Some(period_proof) = &proof.period_proof {
+                assert!(verify(&proof.leaf_hash, period_proof));
+                assert_eq!(period_proof.root, response.periods[0].tree.root);
+            }
+        }
+        let proof = get_inclusion_proof(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), 0, None, None);
+        let proof = proof.unwrap();
+        assert_eq!(proof.ledger.leaf, 4);
+        assert_eq!(proof.period_proof.unwrap().leaf, 2);
+        assert_eq!((proof.line_hash, proof.field_commitment), leaves[4].clone());
 
-        // Verify that only "creator" can add an invoice
-        let _info_public = mock_info(
-            "public",
-            &[Coin {
-                denom: "token".to_string(),
-                amount: Uint128::new(2),
-            }],
-        );
-        let execute_msg = ExecuteMsg::Add { invoice: invoice.clone() };
-        let _res = execute(
-            deps.as_mut(),
-            mock_env(),
-            _info_public.clone(),
-            execute_msg
-        );
+        // Proofs can target the roots the trees had at an earlier size
+        let (wallet_2, permit_2) = (wallet.clone(), permit.clone());
+        let proof = get_inclusion_proof(deps.as_ref(), env.clone(), wallet_2, permit_2, 2, Some(3), Some(2)).unwrap();
+        assert!(verify(&proof.leaf_hash, &proof.ledger));
+        assert_eq!(proof.ledger.leaf_count, 3);
+        assert_eq!(proof.ledger.root, root_of(&leaves[..3]));
+        let period_proof = proof.period_proof.unwrap();
+        assert!(verify(&proof.leaf_hash, &period_proof));
+        assert_eq!(period_proof.root, root_of(&period_leaves[..2]));
+        let proof = get_inclusion_proof(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), 1, Some(2), None);
+        assert_eq!(proof.unwrap().ledger.root, root_of(&leaves[..2]));
 
-        // Verify that the invoice is not added
-        match _res {
-            Err(StdError::GenericErr { .. }) => {}
-            _ => panic!("Must return unauthorized error")
+        // A tree cannot prove a leaf it did not hold yet, nor a size it never had
+        let proof = get_inclusion_proof(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), 2, Some(2), None);
+        match proof {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert_eq!(msg, "Leaf 2 was appended after the Merkle tree had 2 leaves")
+            }
+            _ => panic!("Must refuse a leaf appended later"),
         }
+        let proof = get_inclusion_proof(deps.as_ref(), env, wallet.clone(), permit, 2, Some(6), None);
+        assert!(proof.is_err());
     }
 
-    /// Test function for updating an invoice auditor.
+    /// Test function for the public ledger commitment.
     #[test]
-    fn update_auditor() {
+    fn ledger_commitment() {
         let mut deps = mock_dependencies();
-        let info = mock_info(
-            "creator",
-            &[Coin {
-                denom: "earth".to_string(),
-                amount: Uint128::new(1000),
-            }],
-        );
         let mut env = mock_env();
-
-        // Load data from a permit JSON file on disk (created by Client.query_get_all)
-        let json_data_str = read_to_string(PATH_PERMIT).expect("Unable to read file");
-        let json_data: JsonData = from_str(&json_data_str).expect("Failed to deserialize JSON data");
-        let wallet = &json_data.get_all.wallet;
-        let index = json_data.get_all.index;
-        let permit = json_data.get_all.permit;
-        println!("wallet: {:?}", &wallet);
-        println!("index: {:?}", index);
-        println!("permit: {:?}", permit);
-        // Set up the mock environment and contract address
-    
-        let contract_address = permit.clone().params.allowed_tokens[0].clone();
-        env.contract.address = Addr::unchecked(contract_address);
-
+        let info = mock_info("creator", &[]);
         let instantiate_msg = InstantiateMsg { count: 0 };
         let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg).unwrap();
 
-        let invoice = Invoice {
-            invoice_number: "INV-001".to_string(),
-            date: "2025-02-26".to_string(),
-            client_name: "Client A".to_string(),
-            description: "Service".to_string(),
-            total_amount: "1000".to_string(),
-            tax_amount: "100".to_string(),
-            currency: "USD".to_string(),
-            doc_hash: "hash123".to_string(),
-            line_hash: "linehash123".to_string(),
-            auditors: "Auditor A".to_string(),
-            credibility: "High".to_string(),
-            audit_state: "Pending".to_string(),
+        // Anyone can query the commitments, empty before the first invoice
+        let response = query_ledger_commitment(deps.as_ref()).unwrap();
+        assert_eq!(response.root, "");
+        assert_eq!((response.invoice_count, response.last_sealed_at), (0, None));
+
+        env.block.time = Timestamp::from_seconds(1_740_000_000);
+        for number in ["1", "2"] {
+            let invoice = Invoice { invoice_number: number.to_string(), ..invoice_fixture() };
+            let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice).unwrap();
+        }
+        let response = query_ledger_commitment(deps.as_ref()).unwrap();
+        let root = merkle_trees_read(deps.as_ref().storage).load(LEDGER_TREE).unwrap().root;
+        assert_eq!(response.root, root);
+        assert_eq!(response.invoice_count, 2);
+        assert_eq!(response.last_sealed_at, Some(env.block.time));
+
+        // Amendments append a leaf and move the last sealing time, without adding an invoice
+        env.block.time = Timestamp::from_seconds(1_750_000_000);
+        let amended = Invoice { invoice_number: "2".to_string(), ..invoice_fixture() };
+        let _res = try_amend_invoice(deps.as_mut(), env.clone(), info, 1, amended).unwrap();
+        let binary = query(deps.as_ref(), env.clone(), QueryMsg::GetLedgerCommitment {}).unwrap();
+        let response: LedgerCommitmentResponse = from_binary(&binary).unwrap();
+        assert_ne!(response.root, root);
+        assert_eq!(response.invoice_count, 2);
+        assert_eq!(response.last_sealed_at, Some(env.block.time));
+        let json = String::from_utf8(binary.to_vec()).unwrap();
+        assert!(!json.contains("Client A"));
+        assert!(!json.contains("leaf_count"));
+
+        // A root kept earlier is still recorded with its sealing time, which shows the ledger was only extended since
+        let status = query_ledger_root_status(deps.as_ref(), root.to_uppercase()).unwrap();
+        assert_eq!(status.sealed_at, Some(Timestamp::from_seconds(1_740_000_000)));
+        assert!(!status.current);
+        let binary = query(deps.as_ref(), env.clone(), QueryMsg::GetLedgerRootStatus { root: response.root }).unwrap();
+        let status: LedgerRootStatusResponse = from_binary(&binary).unwrap();
+        assert_eq!((status.sealed_at, status.current), (Some(env.block.time), true));
+        let status = query_ledger_root_status(deps.as_ref(), "00".repeat(32)).unwrap();
+        assert_eq!((status.sealed_at, status.current), (None, false));
+    }
+
+    /// Test function for the selective disclosure of invoice fields.
+    #[test]
+    fn selective_disclosure() {
+        let PermitSetup { mut deps, env, info, wallet, permit } = permit_setup(false);
+        for number in ["1", "2"] {
+            let invoice = Invoice { invoice_number: number.to_string(), ..invoice_fixture() };
+            let _res = try_add(deps.as_mut(), env.clone(), info.clone(), invoice).unwrap();
+        }
+
+        // Only the owner grants a disclosure, over existing invoices
+        assert!(get_disclosed_invoices(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), 0).is_err());
+        let grant = DisclosureGrant {
+            fields: vec![DisclosureField::TotalAmount, DisclosureField::Date],
+            invoices: Some(vec![1]),
         };
+        let anyone = mock_info("anyone", &[]);
+        let res = try_set_disclosure_grant(deps.as_mut(), anyone, wallet.clone(), Some(grant.clone()));
+        assert!(res.is_err());
+        let unknown = DisclosureGrant { invoices: Some(vec![2]), ..grant.clone() };
+        assert!(try_set_disclosure_grant(deps.as_mut(), info.clone(), wallet.clone(), Some(unknown)).is_err());
+        let _res = try_set_disclosure_grant(deps.as_mut(), info.clone(), wallet.clone(), Some(grant)).unwrap();
 
-        let _res = try_add(deps.as_mut(), info.clone(), invoice.clone()).unwrap();
+        // The revealed fields and the hashes of the others check against the sealed field commitment
+        let to_hex = |bytes: &[u8]| bytes.iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+        let binary = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::GetDisclosedInvoices { wallet: wallet.clone(), permit: permit.clone(), index: 0 },
+        )
+        .unwrap();
+        assert!(!String::from_utf8(binary.to_vec()).unwrap().contains("Client A"));
+        let response: DisclosedInvoicesResponse = from_binary(&binary).unwrap();
+        assert_eq!(response.invoices.len(), 1);
+        let disclosed = &response.invoices[0];
+        let invoice = config_invoice_read(deps.as_ref().storage, &invoice_key(1)).load().unwrap();
+        assert_eq!(disclosed.invoice_index, 1);
+        assert_eq!(disclosed.line_hash, invoice.line_hash);
+        assert_eq!(disclosed.field_commitment, invoice.field_commitment);
+        assert_eq!(disclosed.fields.len(), 10);
+        for field in &disclosed.fields {
+            let revealed = field.field == DisclosureField::TotalAmount || field.field == DisclosureField::Date;
+            assert_eq!(field.value.is_some(), revealed);
+            if let (Some(value), Some(salt)) = (&field.value, &field.salt) {
+                assert_eq!(to_hex(&Sha256::digest([salt.as_bytes(), value.as_bytes()].concat())), field.hash);
+            }
+        }
+        assert_eq!(disclosed.fields[4].value, Some(invoice.total_amount.clone()));
+        let hashes: String = disclosed.fields.iter().map(|field| field.hash.clone()).collect();
+        assert_eq!(to_hex(&Sha256::digest(hashes.as_bytes())), invoice.field_commitment);
 
-        let new_auditor = Addr::unchecked("new_auditor");
-        let index_u8 = 0;
-        let res = try_update_auditor(deps.as_mut(), info, index_u8, new_auditor.clone()).unwrap();
-        assert_eq!(0, res.messages.len());
+        // Amendments seal a new commitment, and revealing every field lets the grantee recompute the line hash
+        let amended = Invoice {
+            invoice_number: "2".to_string(),
+            description: "Corrected".to_string(),
+            ..invoice_fixture()
+        };
+        let _res = try_amend_invoice(deps.as_mut(), env.clone(), info.clone(), 1, amended).unwrap();
+        let fields = vec![
+            DisclosureField::InvoiceNumber,
+            DisclosureField::Date,
+            DisclosureField::ClientName,
+            DisclosureField::Description,
+            DisclosureField::TotalAmount,
+            DisclosureField::TaxAmount,
+            DisclosureField::Currency,
+            DisclosureField::DocHash,
+            DisclosureField::Lines,
+            DisclosureField::Items,
+        ];
+        let grant = DisclosureGrant { fields, invoices: None };
+        let _res = try_set_disclosure_grant(deps.as_mut(), info.clone(), wallet.clone(), Some(grant)).unwrap();
+        let response = get_disclosed_invoices(deps.as_ref(), env.clone(), wallet.clone(), permit.clone(), 0).unwrap();
+        assert_eq!(response.invoices.len(), 2);
+        let disclosed = &response.invoices[1];
+        assert_eq!(disclosed.version, 2);
+        assert_ne!(disclosed.field_commitment, invoice.field_commitment);
+        let values: Vec<String> = disclosed.fields.iter().map(|field| field.value.clone().unwrap()).collect();
+        let mut data = values[..8].concat();
+        for value in &values[8..] {
+            if value != "[]" {
+                data.push_str(value);
+            }
+        }
+        assert_eq!(to_hex(&Sha256::digest(data.as_bytes())), disclosed.line_hash);
 
-        //let index = index_u8.to_be_bytes(); // Convert the integer to a byte slice
-        let index_conf = b"0"; // Convert the integer to a byte slice
-        let updated_invoice = config_invoice_read(&deps.storage, index_conf).load().unwrap();
-        assert_eq!(updated_invoice.auditors, new_auditor.to_string());    
+        // Revoked grants disclose nothing
+        let _res = try_set_disclosure_grant(deps.as_mut(), info, wallet.clone(), None).unwrap();
+        assert!(get_disclosed_invoices(deps.as_ref(), env, wallet.clone(), permit, 0).is_err());
     }
 }
@@ -1,11 +1,16 @@
 /// This module contains message definitions for a secret pass manager contract.
 ///
 /// It includes messages for instantiation, execution, and querying of the contract.
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Binary, Decimal, Timestamp, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use secret_toolkit::permit::Permit;
-use crate::state::Invoice;
+use crate::state::{
+    Account, AccountType, ApprovalRule, AuditOpinion, AuditSample, CredibilityScore, CredibilityThresholds,
+    CurrencyConfig, DisclosureField, DisclosureGrant, ExchangeRate, ExpenseCategory, FiscalPeriod, GoodsReceipt,
+    Invoice, ItemQuantity, MatchResult, MatchTolerance, MerkleTree, OpinionType, Payment, PaymentMethod, PaymentStatus,
+    PendingInvoice, PurchaseOrder, SplitRule, Supplier, SupplierTotals, TaxCode, VatTotals,
+};
 
 /// Instantiate message for the secret pass manager contract.
 ///
@@ -32,7 +37,217 @@ pub enum ExecuteMsg {
     /// # Arguments
     /// * `invoice_index` - The index of the invoice to update.
     /// * `auditor` - The new auditor address.
-    UpdateAuditor { invoice_index: u8, auditor: Addr },
+    UpdateAuditor { invoice_index: u32, auditor: Addr },
+    /// Amend an existing invoice with a corrected version.
+    ///
+    /// The current version stays readable, the new version gets a recomputed `line_hash` and its audit state is reset.
+    ///
+    /// # Arguments
+    /// * `invoice_index` - The index of the invoice to amend.
+    /// * `invoice` - The corrected invoice.
+    AmendInvoice { invoice_index: u32, invoice: Invoice },
+    /// Void an invoice or note. The record is kept and marked as voided so that it stays auditable.
+    ///
+    /// # Arguments
+    /// * `invoice_index` - The index of the invoice to void.
+    /// * `reason` - Why the invoice is voided.
+    VoidInvoice { invoice_index: u32, reason: String },
+    /// Set the credibility thresholds that decide whether a new invoice must be reviewed or can be sealed directly.
+    ///
+    /// # Arguments
+    /// * `review_below` - Invoices scoring below this value must be approved by their auditor.
+    /// * `seal_from` - Invoices scoring at least this value are sealed without review, if set.
+    SetCredibilityThresholds {
+        review_below: CredibilityScore,
+        seal_from: Option<CredibilityScore>,
+    },
+    /// Record the decision of the auditor of an invoice.
+    ///
+    /// # Arguments
+    /// * `invoice_index` - The index of the audited invoice.
+    /// * `decision` - The audit decision.
+    AuditInvoice { invoice_index: u32, decision: AuditDecision },
+    /// Create or update an account of the chart of accounts.
+    ///
+    /// # Arguments
+    /// * `account` - The account, identified by its code. Deactivated accounts no longer accept journal lines.
+    SetAccount { account: Account },
+    /// Create or update a tax code.
+    SetTaxCode { tax_code: TaxCode },
+    /// Set the rounding tolerance, in currency units, of the check between `tax_amount` and the taxable lines.
+    SetTaxTolerance { tolerance: Decimal },
+    /// Record a full or partial payment of an invoice or debit note.
+    ///
+    /// # Arguments
+    /// * `invoice_index` - The index of the paid invoice.
+    /// * `amount` - The amount paid, in the currency of the invoice, at most its outstanding balance.
+    /// * `date` - The day of the payment, as `YYYY-MM-DD`.
+    /// * `method` - How the payment was made.
+    /// * `reference_hash` - The SHA-256 hash of the bank or transaction reference of the payment.
+    RecordPayment {
+        invoice_index: u32,
+        amount: Decimal,
+        date: String,
+        method: PaymentMethod,
+        reference_hash: String,
+    },
+    /// Accept a SNIP-20 token to pay invoices and register the contract with it to receive its transfers.
+    ///
+    /// # Arguments
+    /// * `contract` - The address of the token contract.
+    /// * `code_hash` - The code hash of the token contract.
+    /// * `currency` - The ISO-4217 code of the currency the token is pegged to.
+    /// * `decimals` - The number of decimals of the token.
+    RegisterToken {
+        contract: Addr,
+        code_hash: String,
+        currency: String,
+        decimals: u8,
+    },
+    /// The SNIP-20 receiver interface, called by a token contract when the contract receives tokens.
+    ///
+    /// The owner sends tokens with a `ReceiveMsg` referencing an invoice; the contract forwards them to the supplier
+    /// of the invoice and records the payment.
+    Receive {
+        sender: Addr,
+        from: Addr,
+        amount: Uint128,
+        memo: Option<String>,
+        msg: Option<Binary>,
+    },
+    /// Create a purchase order. Its number must be unique.
+    CreatePurchaseOrder { order: PurchaseOrder },
+    /// Record goods received against a purchase order, and match its invoices again.
+    ///
+    /// # Arguments
+    /// * `order` - The number of the purchase order.
+    /// * `date` - The day the goods were received, as `YYYY-MM-DD`.
+    /// * `lines` - The quantities received, of items of the order.
+    RecordGoodsReceipt {
+        order: String,
+        date: String,
+        lines: Vec<ItemQuantity>,
+    },
+    /// Set the relative tolerances of the three-way match.
+    SetMatchTolerance { tolerance: MatchTolerance },
+    /// Create or update an expense category.
+    SetExpenseCategory { category: ExpenseCategory },
+    /// Set the budget of an expense category over a fiscal period, in the base currency once set.
+    SetBudget {
+        category: String,
+        period: String,
+        amount: Decimal,
+    },
+    /// Create or update a supplier of the supplier registry.
+    SetSupplier { supplier: Supplier },
+    /// Set the base currency, once, and the exchange-rate oracle.
+    ///
+    /// # Arguments
+    /// * `base_currency` - The ISO-4217 code of the base currency. It cannot change once set.
+    /// * `oracle` - The address allowed to record exchange rates besides the owner, if any.
+    SetCurrencyConfig { base_currency: String, oracle: Option<Addr> },
+    /// Record the rate converting one unit of `from` into `to` on `date`, as the owner or the oracle.
+    SetExchangeRate {
+        from: String,
+        to: String,
+        date: String,
+        rate: Decimal,
+    },
+    /// Grant or revoke the approver role.
+    ///
+    /// # Arguments
+    /// * `approver` - The address of the approver.
+    /// * `active` - Whether the address holds the approver role.
+    SetApprover { approver: Addr, active: bool },
+    /// Replace the approval rules. `Add` submits the invoices above a threshold for approval instead of sealing them.
+    SetApprovalRules { rules: Vec<ApprovalRule> },
+    /// Set the split-invoice detection rule, or remove it with `None`. `Add` flags the invoices of a supplier that
+    /// together reach the threshold within the window while each stays below it.
+    SetSplitRule { rule: Option<SplitRule> },
+    /// Grant an address the disclosure of some invoice fields, or revoke its grant with `None`.
+    ///
+    /// # Arguments
+    /// * `grantee` - The address the fields are disclosed to.
+    /// * `grant` - The fields revealed and the invoices they are revealed for.
+    SetDisclosureGrant {
+        grantee: Addr,
+        grant: Option<DisclosureGrant>,
+    },
+    /// Approve an invoice waiting for approval. It is sealed once enough distinct approvers approved it.
+    ApproveInvoice { pending_index: u32 },
+    /// Reject an invoice waiting for approval, as an approver other than its submitter, or withdraw it as the owner.
+    /// It is never sealed.
+    ///
+    /// # Arguments
+    /// * `pending_index` - The pending index of the invoice.
+    /// * `reason` - Why the invoice is rejected or withdrawn.
+    RejectInvoice { pending_index: u32, reason: String },
+    /// Draw a sample of the invoices dated in a fiscal period from the block randomness, for an engaged auditor. A
+    /// period is sampled once.
+    ///
+    /// # Arguments
+    /// * `period` - The name of the fiscal period.
+    /// * `size` - The number of invoices to draw from each stratum.
+    /// * `strata` - The ascending amount boundaries splitting the invoices into strata, none for a single stratum.
+    /// * `monetary_unit` - Whether to draw invoices with a probability proportional to their amount.
+    DrawSample {
+        period: String,
+        size: u32,
+        strata: Vec<Decimal>,
+        monetary_unit: bool,
+    },
+    /// Create an open fiscal period.
+    ///
+    /// # Arguments
+    /// * `name` - The unique name of the period.
+    /// * `start` - The first day of the period, as `YYYY-MM-DD`.
+    /// * `end` - The last day of the period, as `YYYY-MM-DD`.
+    /// * `require_auditor` - Whether closing the period also requires the sign-off of an auditor.
+    AddFiscalPeriod {
+        name: String,
+        start: String,
+        end: String,
+        require_auditor: bool,
+    },
+    /// Sign off the closing of a fiscal period as an approver or an auditor.
+    ///
+    /// The period is closed once every required sign-off is recorded.
+    ClosePeriod { name: String },
+    /// Reopen a closed fiscal period. Only an approver can reopen a period, and the reopening is recorded.
+    ReopenPeriod { name: String, reason: String },
+    /// Issue the opinion of an engaged auditor on a fiscal period, which locks the period for good.
+    ///
+    /// # Arguments
+    /// * `period` - The name of the fiscal period.
+    /// * `opinion` - The type of the opinion.
+    /// * `report_hash` - The SHA-256 hash of the audit report document, hex-encoded.
+    /// * `public` - Whether anyone can retrieve the opinion without a permit.
+    IssueAuditOpinion {
+        period: String,
+        opinion: OpinionType,
+        report_hash: String,
+        public: bool,
+    },
+}
+
+/// The decisions an auditor can record on an invoice.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditDecision {
+    /// Approve the invoice.
+    Approve,
+    /// Flag an issue on the invoice.
+    FlagIssue,
+    /// Ask the company to amend the invoice.
+    RequestCorrection,
+}
+
+/// The message embedded in a SNIP-20 transfer to the contract.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    /// Pay an invoice with the received tokens, forwarded to the registered address of its supplier.
+    PayInvoice { invoice_index: u32 },
 }
 
 /// Query messages for the secret pass manager contract.
@@ -44,6 +259,19 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     // GetCount returns the current count as a json-encoded number
     GetCount {},
+    /// Retrieve the credibility thresholds.
+    GetCredibilityThresholds {},
+    /// Retrieve the base currency and the exchange-rate oracle.
+    GetCurrencyConfig {},
+    /// Retrieve the rate converting one unit of `from` into `to` on `date`.
+    GetExchangeRate { from: String, to: String, date: String },
+    /// Retrieve the audit opinion issued on a fiscal period, when the auditor made it public.
+    GetPublicAuditOpinion { period: String },
+    /// Retrieve the opaque commitments to the ledger, without a permit: its Merkle root, the number of invoices and
+    /// the time an invoice version was last sealed.
+    GetLedgerCommitment {},
+    /// Retrieve, without a permit, whether `root` is a root the ledger Merkle tree had, and when it was sealed.
+    GetLedgerRootStatus { root: String },
     /// Retrieve all invoices associated with a given wallet address.
     GetAll {
         /// The wallet address to retrieve invoices for
@@ -51,7 +279,202 @@ pub enum QueryMsg {
          /// A permit to authenticate the query request.
         permit: Permit,
         /// An index to paginate through the results.
-        index: u8,
+        index: u32,
+    },
+    /// Retrieve a page of the invoices disclosed to a wallet by its disclosure grant, with the fields it does not
+    /// reveal replaced by their salted hashes.
+    GetDisclosedInvoices {
+        /// The wallet address the invoices are disclosed to
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+        /// An index to paginate through the results.
+        index: u32,
+    },
+    /// Retrieve a page of the chart of accounts, for the owner and the engaged auditors.
+    GetAccounts {
+        /// The wallet address requesting the accounts
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+        /// An index to paginate through the results.
+        index: u32,
+    },
+    /// Retrieve the trial balance, for the owner and the engaged auditors.
+    ///
+    /// Without arguments the balances cover the whole ledger.
+    GetTrialBalance {
+        /// The wallet address requesting the trial balance
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+        /// The last month (`YYYY-MM`) included in cumulative balances.
+        as_of: Option<String>,
+        /// The only month (`YYYY-MM`) whose postings are included, instead of cumulative balances.
+        period: Option<String>,
+    },
+    /// Retrieve the postings of one account, for the owner and the engaged auditors.
+    GetAccountBalance {
+        /// The wallet address requesting the balance
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+        /// The account code.
+        account: String,
+        /// The first month (`YYYY-MM`) included, from the first posting if not set.
+        from: Option<String>,
+        /// The last month (`YYYY-MM`) included, up to the last posting if not set.
+        to: Option<String>,
+    },
+    /// Retrieve the payments and the settlement status of an invoice, for the owner and its auditor.
+    GetPayments {
+        /// The wallet address requesting the payments
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+        /// The index of the invoice.
+        invoice_index: u32,
+    },
+    /// Retrieve the outstanding balances by age, for the owner and the engaged auditors.
+    GetAgedPayables {
+        /// The wallet address requesting the aged payables
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+        /// The day the balances are aged at, as `YYYY-MM-DD`. Defaults to the day of the current block.
+        as_of: Option<String>,
+    },
+    /// Retrieve a purchase order and its goods receipts, for the owner and the engaged auditors.
+    GetPurchaseOrder {
+        /// The wallet address requesting the purchase order
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+        /// The number of the purchase order.
+        order: String,
+    },
+    /// Retrieve a page of the supplier registry, for the owner and the engaged auditors.
+    GetSuppliers {
+        /// The wallet address requesting the suppliers
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+        /// An index to paginate through the results.
+        index: u32,
+    },
+    /// Retrieve a page of the invoices of a supplier and its totals, for the owner and the engaged auditors.
+    ///
+    /// Auditors only see the invoices assigned to them.
+    GetSupplierInvoices {
+        /// The wallet address requesting the invoices
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+        /// The id of the supplier.
+        supplier: String,
+        /// An index to paginate through the invoices of the supplier.
+        index: u32,
+    },
+    /// Retrieve a page of the tax codes, for the owner and the engaged auditors.
+    GetTaxCodes {
+        /// The wallet address requesting the tax codes
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+        /// An index to paginate through the results.
+        index: u32,
+    },
+    /// Retrieve the input VAT of a fiscal period by tax code and currency, for the owner and the engaged auditors.
+    GetVatReturn {
+        /// The wallet address requesting the VAT return
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+        /// The name of a fiscal period made of whole months.
+        period: String,
+    },
+    /// Retrieve the invoices waiting for approval and the approval rules, for the owner, the approvers and the engaged
+    /// auditors.
+    GetPendingApprovals {
+        /// The wallet address requesting the pending approvals
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+    },
+    /// Retrieve the budget against the actual spend of every expense category over a fiscal period, for the owner and
+    /// the engaged auditors.
+    GetBudgetReport {
+        /// The wallet address requesting the report
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+        /// The name of the fiscal period.
+        period: String,
+    },
+    /// Retrieve the first-digit distribution and the anomaly statistics of the invoices of a fiscal period made of
+    /// whole months, for the engaged auditors.
+    GetAnomalyStatistics {
+        /// The wallet address requesting the statistics
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+        /// The name of the fiscal period.
+        period: String,
+    },
+    /// Retrieve the audit sample of a fiscal period and the invoices drawn, for the engaged auditors.
+    GetAuditSample {
+        /// The wallet address requesting the sample
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+        /// The name of the fiscal period.
+        period: String,
+    },
+    /// Retrieve the audit opinion issued on a fiscal period, for the owner and the engaged auditors.
+    GetAuditOpinion {
+        /// The wallet address requesting the opinion
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+        /// The name of the fiscal period.
+        period: String,
+    },
+    /// Retrieve the Merkle roots of the ledger and of every fiscal period, for the owner and the engaged auditors.
+    GetMerkleRoots {
+        /// The wallet address requesting the roots
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+    },
+    /// Retrieve the proofs that the latest version of an invoice is included in the Merkle roots of the ledger and of
+    /// its fiscal period, for the owner and the engaged auditors.
+    GetInclusionProof {
+        /// The wallet address requesting the proof
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+        /// The index of the invoice.
+        invoice_index: u32,
+        /// The number of leaves of the ledger root to prove against, the current root if not set.
+        leaf_count: Option<u32>,
+        /// The number of leaves of the period root to prove against, the current root if not set.
+        period_leaf_count: Option<u32>,
+    },
+    /// Retrieve the fiscal periods, for the owner and the engaged auditors.
+    GetFiscalPeriods {
+        /// The wallet address requesting the periods
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+    },
+    /// Retrieve every version of an invoice, from the original to the latest amendment.
+    GetInvoiceVersions {
+        /// The wallet address requesting the versions
+        wallet: Addr,
+        /// A permit to authenticate the query request.
+        permit: Permit,
+        /// The index of the invoice.
+        invoice_index: u32,
     },
 }
 
@@ -63,6 +486,12 @@ pub struct CountResponse {
     pub count: i32,
 }
 
+/// Response for the `GetCredibilityThresholds` query message.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct CredibilityThresholdsResponse {
+    pub thresholds: CredibilityThresholds,
+}
+
 /// Response for the `GetAll` query message.
 ///
 /// This response contains a vector of invoices associated with a wallet address.
@@ -70,3 +499,315 @@ pub struct CountResponse {
 pub struct InvoiceListResponse {
     pub vect_invoice: Vec<Invoice>,
 }
+
+/// Response for the `GetAccounts` query message.
+///
+/// This response contains a page of the chart of accounts and the total number of accounts.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct AccountListResponse {
+    pub accounts: Vec<Account>,
+    pub total: u32,
+}
+
+/// The postings of one account of the chart of accounts.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct AccountBalance {
+    pub account: String,
+    pub account_type: AccountType,
+    /// The sum of the debits posted to the account.
+    pub debit: Decimal,
+    /// The sum of the credits posted to the account.
+    pub credit: Decimal,
+}
+
+/// Response for the `GetTrialBalance` query message.
+///
+/// This response contains the accounts with postings and the total debits and credits, which are equal for a
+/// balanced ledger.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct TrialBalanceResponse {
+    pub balances: Vec<AccountBalance>,
+    pub total_debit: Decimal,
+    pub total_credit: Decimal,
+    /// The base currency of the amounts, if set.
+    pub currency: Option<String>,
+}
+
+/// Response for the `GetAccountBalance` query message.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct AccountBalanceResponse {
+    pub balance: AccountBalance,
+    /// The base currency of the amounts, if set.
+    pub currency: Option<String>,
+}
+
+/// Response for the `GetCurrencyConfig` query message.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct CurrencyConfigResponse {
+    /// The currency configuration, if the owner set one.
+    pub config: Option<CurrencyConfig>,
+}
+
+/// Response for the `GetExchangeRate` query message.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct ExchangeRateResponse {
+    pub rate: ExchangeRate,
+}
+
+/// Response for the `GetPayments` query message.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PaymentsResponse {
+    pub payments: Vec<Payment>,
+    pub paid_amount: Decimal,
+    /// The total amount less the credited and paid amounts, in the currency of the invoice.
+    pub outstanding: Decimal,
+    /// The settlement status on the day of the current block.
+    pub status: PaymentStatus,
+}
+
+/// An invoice with an outstanding balance.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct AgedPayable {
+    pub invoice_index: u32,
+    pub supplier: Option<String>,
+    pub due_date: String,
+    /// The number of days past the due date, zero when the invoice is not due yet.
+    pub days_overdue: u32,
+    /// The outstanding balance, in the base currency once it is set.
+    pub outstanding: Decimal,
+}
+
+/// Response for the `GetAgedPayables` query message.
+///
+/// This response contains the outstanding balances in the base currency, once it is set, totalled by age.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct AgedPayablesResponse {
+    pub as_of: String,
+    pub currency: Option<String>,
+    pub not_due: Decimal,
+    pub overdue_1_to_30: Decimal,
+    pub overdue_31_to_60: Decimal,
+    pub overdue_61_to_90: Decimal,
+    pub overdue_over_90: Decimal,
+    pub total: Decimal,
+    /// The invoices with an outstanding balance, by index.
+    pub payables: Vec<AgedPayable>,
+}
+
+/// Response for the `GetPurchaseOrder` query message.
+///
+/// This response contains the purchase order, its goods receipts and the match result of each of its invoices.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PurchaseOrderResponse {
+    pub order: PurchaseOrder,
+    pub receipts: Vec<GoodsReceipt>,
+    /// The match result of each invoice of `order.invoices`, in the same order.
+    pub match_results: Vec<Option<MatchResult>>,
+    pub tolerance: MatchTolerance,
+}
+
+/// Response for the `GetSuppliers` query message.
+///
+/// This response contains a page of the supplier registry and the total number of suppliers.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct SupplierListResponse {
+    pub suppliers: Vec<Supplier>,
+    pub total: u32,
+}
+
+/// Response for the `GetSupplierInvoices` query message.
+///
+/// This response contains the supplier, its totals and a page of its invoices with their indexes.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct SupplierInvoicesResponse {
+    pub supplier: Supplier,
+    pub totals: SupplierTotals,
+    pub invoice_indexes: Vec<u32>,
+    pub vect_invoice: Vec<Invoice>,
+}
+
+/// Response for the `GetTaxCodes` query message.
+///
+/// This response contains a page of the tax codes, the total number of tax codes and the rounding tolerance of the
+/// tax consistency check.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct TaxCodeListResponse {
+    pub tax_codes: Vec<TaxCode>,
+    pub total: u32,
+    pub tolerance: Decimal,
+}
+
+/// Response for the `GetVatReturn` query message.
+///
+/// This response contains the input VAT totals of the period by tax code and currency, sorted by both.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct VatReturnResponse {
+    pub period: String,
+    pub totals: Vec<VatTotals>,
+}
+
+/// Response for the `GetPendingApprovals` query message.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PendingApprovalsResponse {
+    /// The pending index of each invoice of `pending`.
+    pub pending_indexes: Vec<u32>,
+    pub pending: Vec<PendingInvoice>,
+    pub rules: Vec<ApprovalRule>,
+}
+
+/// The budget against the actual spend of an expense category over a fiscal period.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct BudgetLine {
+    pub category: ExpenseCategory,
+    pub budget: Option<Decimal>,
+    pub spent: Decimal,
+    /// Whether the spend exceeds the budget.
+    pub overrun: bool,
+}
+
+/// Response for the `GetBudgetReport` query message.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct BudgetReportResponse {
+    pub period: String,
+    /// The base currency of the amounts, if set.
+    pub currency: Option<String>,
+    /// A line per expense category, in creation order.
+    pub lines: Vec<BudgetLine>,
+}
+
+/// Response for the `GetAnomalyStatistics` query message.
+///
+/// The shares are fractions of `invoices`, the number of invoices and debit notes of the period that are not voided.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct AnomalyStatisticsResponse {
+    pub period: String,
+    pub invoices: u32,
+    /// The number of total amounts by first significant digit, from 1 to 9.
+    pub first_digits: Vec<u32>,
+    /// The share of each first digit, from 1 to 9.
+    pub first_digit_shares: Vec<Decimal>,
+    /// The share of each first digit expected by Benford's law, from 1 to 9.
+    pub benford_shares: Vec<Decimal>,
+    pub round_amounts: u32,
+    pub round_amount_share: Decimal,
+    pub weekend_dates: u32,
+    pub weekend_share: Decimal,
+    /// The number of invoices just below the threshold of an approval rule when sealed.
+    pub near_threshold: u32,
+    pub near_threshold_share: Decimal,
+}
+
+/// Response for the `GetAuditSample` query message.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct AuditSampleResponse {
+    pub sample: AuditSample,
+    /// The invoices drawn, in the order of `sample.invoices`.
+    pub vect_invoice: Vec<Invoice>,
+}
+
+/// Response for the `GetAuditOpinion` and `GetPublicAuditOpinion` query messages.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct AuditOpinionResponse {
+    pub opinion: AuditOpinion,
+}
+
+/// A field of a disclosed invoice.
+///
+/// Its `hash` is the SHA-256 of the hex-encoded salt followed by the value. The salt and the value are only given
+/// for the fields the grant reveals.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct DisclosedField {
+    pub field: DisclosureField,
+    pub value: Option<String>,
+    pub salt: Option<String>,
+    pub hash: String,
+}
+
+/// An invoice disclosed by a disclosure grant, with every field covered by its `line_hash` in `DisclosureField`
+/// order.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct DisclosedInvoice {
+    pub invoice_index: u32,
+    pub version: u32,
+    pub line_hash: String,
+    pub field_commitment: String,
+    pub voided: bool,
+    pub fields: Vec<DisclosedField>,
+}
+
+/// Response for the `GetDisclosedInvoices` query message.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct DisclosedInvoicesResponse {
+    pub invoices: Vec<DisclosedInvoice>,
+}
+
+/// Response for the `GetLedgerCommitment` query message.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct LedgerCommitmentResponse {
+    /// The hex-encoded Merkle root of the ledger, empty while no invoice is sealed.
+    pub root: String,
+    /// The number of invoices sealed, voided ones included.
+    pub invoice_count: u32,
+    /// The time an invoice version was last sealed, by `Add`, `ApproveInvoice` or `AmendInvoice`.
+    pub last_sealed_at: Option<Timestamp>,
+}
+
+/// Response for the `GetLedgerRootStatus` query message.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct LedgerRootStatusResponse {
+    /// The time the version whose leaf produced the root was sealed, or `None` when the ledger never had that root.
+    pub sealed_at: Option<Timestamp>,
+    /// Whether the root is the current root of the ledger.
+    pub current: bool,
+}
+
+/// The Merkle root of a fiscal period.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PeriodRoot {
+    pub period: String,
+    pub tree: MerkleTree,
+}
+
+/// Response for the `GetMerkleRoots` query message.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct MerkleRootsResponse {
+    pub ledger: MerkleTree,
+    /// The roots of the fiscal periods, in creation order.
+    pub periods: Vec<PeriodRoot>,
+}
+
+/// The proof that a leaf is included in a Merkle root.
+///
+/// Starting from the leaf hash, each sibling is hashed in turn with the current node, on the left when the bit of
+/// `leaf` at that level is set and on the right otherwise, as the SHA-256 of a one byte followed by both nodes.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct MerkleProof {
+    /// The position of the leaf.
+    pub leaf: u32,
+    /// The number of leaves of the tree.
+    pub leaf_count: u32,
+    /// The hex-encoded siblings, from the leaves up to the root.
+    pub siblings: Vec<String>,
+    /// The hex-encoded root.
+    pub root: String,
+}
+
+/// Response for the `GetInclusionProof` query message.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct InclusionProofResponse {
+    pub invoice_index: u32,
+    pub line_hash: String,
+    pub field_commitment: String,
+    /// The hex-encoded leaf: the SHA-256 of a zero byte followed by the `line_hash` and `field_commitment` strings.
+    pub leaf_hash: String,
+    pub ledger: MerkleProof,
+    /// The fiscal period the invoice was dated in when sealed, if any.
+    pub period: Option<String>,
+    pub period_proof: Option<MerkleProof>,
+}
+
+/// Response for the `GetFiscalPeriods` query message.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct FiscalPeriodListResponse {
+    pub periods: Vec<FiscalPeriod>,
+}
@@ -4,12 +4,177 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Storage};
-use cosmwasm_storage::{singleton, singleton_read, ReadonlySingleton, Singleton};
+use cosmwasm_std::{Addr, Binary, Decimal, Storage, Timestamp};
+use cosmwasm_storage::{
+    bucket, bucket_read, singleton, singleton_read, Bucket, ReadonlyBucket, ReadonlySingleton, Singleton,
+};
 
 /// The key used for storing the configuration in the contract's storage.
 pub static CONFIG_KEY: &[u8] = b"config";
 
+/// The namespace used for storing the superseded versions of amended invoices.
+pub static INVOICE_VERSIONS_KEY: &[u8] = b"invoice_versions";
+
+/// The key used for storing the credibility thresholds.
+pub static CREDIBILITY_THRESHOLDS_KEY: &[u8] = b"credibility_thresholds";
+
+/// The namespace used for storing the chart of accounts, keyed by account code.
+pub static ACCOUNTS_KEY: &[u8] = b"accounts";
+
+/// The namespace used for storing the account codes in creation order, for paging through the chart of accounts.
+pub static ACCOUNT_CODES_KEY: &[u8] = b"account_codes";
+
+/// The namespace used for storing the all-time posting totals of each account, keyed by account code.
+pub static ACCOUNT_TOTALS_KEY: &[u8] = b"account_totals";
+
+/// The namespace used for storing the monthly posting totals of each account, keyed by month and account code.
+pub static ACCOUNT_MONTH_TOTALS_KEY: &[u8] = b"account_month_totals";
+
+/// The key used for storing the first and last months with postings.
+pub static LEDGER_SPAN_KEY: &[u8] = b"ledger_span";
+
+/// The namespace used for storing the tax codes, keyed by code.
+pub static TAX_CODES_KEY: &[u8] = b"tax_codes";
+
+/// The namespace used for storing the tax codes in creation order.
+pub static TAX_CODE_INDEX_KEY: &[u8] = b"tax_code_index";
+
+/// The key used for storing the rounding tolerance of the tax consistency check.
+pub static TAX_TOLERANCE_KEY: &[u8] = b"tax_tolerance";
+
+/// The namespace used for storing the input VAT totals of each month, keyed by big-endian month index.
+pub static VAT_MONTHS_KEY: &[u8] = b"vat_months";
+
+/// The key used for storing the base currency and the exchange-rate oracle.
+pub static CURRENCY_CONFIG_KEY: &[u8] = b"currency_config";
+
+/// The namespace used for storing the exchange rates, keyed by currency pair and date.
+pub static EXCHANGE_RATES_KEY: &[u8] = b"exchange_rates";
+
+/// The namespace used for storing the suppliers, keyed by id.
+pub static SUPPLIERS_KEY: &[u8] = b"suppliers";
+
+/// The namespace used for storing the supplier ids in creation order.
+pub static SUPPLIER_IDS_KEY: &[u8] = b"supplier_ids";
+
+/// The namespace used for storing the invoice indexes of each supplier, keyed by supplier id and position.
+pub static SUPPLIER_INVOICES_KEY: &[u8] = b"supplier_invoices";
+
+/// The namespace used for storing the totals of each supplier, keyed by supplier id.
+pub static SUPPLIER_TOTALS_KEY: &[u8] = b"supplier_totals";
+
+/// The namespace used for storing the payments of each invoice, keyed by invoice index and position.
+pub static PAYMENTS_KEY: &[u8] = b"payments";
+
+/// The namespace used for storing the indexes of the invoices with an outstanding balance, by pages of consecutive
+/// invoice indexes keyed by big-endian page number.
+pub static OPEN_PAYABLES_KEY: &[u8] = b"open_payables";
+
+/// The namespace used for storing the SNIP-20 tokens accepted for payments, keyed by contract address.
+pub static TOKENS_KEY: &[u8] = b"tokens";
+
+/// The namespace used for storing the purchase orders, keyed by number.
+pub static PURCHASE_ORDERS_KEY: &[u8] = b"purchase_orders";
+
+/// The namespace used for storing the goods receipts, keyed by purchase order number and position.
+pub static GOODS_RECEIPTS_KEY: &[u8] = b"goods_receipts";
+
+/// The key used for storing the tolerances of the three-way match.
+pub static MATCH_TOLERANCE_KEY: &[u8] = b"match_tolerance";
+
+/// The namespace used for storing the expense categories, keyed by code.
+pub static EXPENSE_CATEGORIES_KEY: &[u8] = b"expense_categories";
+
+/// The namespace used for storing the expense category codes in creation order.
+pub static EXPENSE_CATEGORY_CODES_KEY: &[u8] = b"expense_category_codes";
+
+/// The namespace used for storing the budget and spend of each expense category, keyed by category and period.
+pub static CATEGORY_BUDGETS_KEY: &[u8] = b"category_budgets";
+
+/// The namespace used for storing the indexes of the invoices dated in each month, keyed by `month_index`.
+pub static MONTH_INVOICES_KEY: &[u8] = b"month_invoices";
+
+/// The namespace used for storing the anomaly counters of each month, keyed by big-endian month index.
+pub static ANOMALY_MONTHS_KEY: &[u8] = b"anomaly_months";
+
+/// The namespace used for storing the audit samples, keyed by fiscal period name.
+pub static AUDIT_SAMPLES_KEY: &[u8] = b"audit_samples";
+
+/// The namespace used for storing the audit opinions, keyed by fiscal period name.
+pub static AUDIT_OPINIONS_KEY: &[u8] = b"audit_opinions";
+
+/// The namespace used for storing the size and root of the Merkle trees, keyed by tree.
+pub static MERKLE_TREES_KEY: &[u8] = b"merkle_trees";
+
+/// The namespace used for storing the nodes of the Merkle trees, keyed by `merkle_node_key`.
+pub static MERKLE_NODES_KEY: &[u8] = b"merkle_nodes";
+
+/// The namespace used for storing every root the Merkle trees had, keyed by `merkle_root_key`.
+pub static MERKLE_ROOTS_KEY: &[u8] = b"merkle_roots";
+
+/// The namespace used for storing the time each root of the ledger Merkle tree was sealed, keyed by hex-encoded root.
+pub static LEDGER_ROOTS_KEY: &[u8] = b"ledger_roots";
+
+/// The tree of the whole ledger. The trees of the fiscal periods are keyed by period name, which is never empty.
+pub const LEDGER_TREE: &[u8] = b"";
+
+/// The namespace used for storing the fiscal periods, keyed by name.
+pub static FISCAL_PERIODS_KEY: &[u8] = b"fiscal_periods";
+
+/// The namespace used for storing the fiscal period names in creation order.
+pub static FISCAL_PERIOD_NAMES_KEY: &[u8] = b"fiscal_period_names";
+
+/// The namespace used for storing the addresses of the approvers, managed by the owner.
+pub static APPROVERS_KEY: &[u8] = b"approvers";
+
+/// The key used for storing the approval rules.
+pub static APPROVAL_RULES_KEY: &[u8] = b"approval_rules";
+
+/// The namespace used for storing the invoices waiting for approval, keyed by pending index.
+pub static PENDING_INVOICES_KEY: &[u8] = b"pending_invoices";
+
+/// The namespace used for storing the pending indexes of the invoices still waiting for approval, by pages of
+/// consecutive pending indexes keyed by big-endian page number.
+pub static OPEN_APPROVALS_KEY: &[u8] = b"open_approvals";
+
+/// The key used for storing the split-invoice detection rule.
+pub static SPLIT_RULE_KEY: &[u8] = b"split_rule";
+
+/// The namespace used for storing the disclosure grants, keyed by grantee address.
+pub static DISCLOSURE_GRANTS_KEY: &[u8] = b"disclosure_grants";
+
+/// The namespace used for storing the secret salt of each sealed invoice version, keyed by `invoice_version_key`.
+pub static DISCLOSURE_SALTS_KEY: &[u8] = b"disclosure_salts";
+
+/// The namespace used for storing the number of invoices assigned to each auditor engaged on at least one invoice.
+pub static AUDITORS_KEY: &[u8] = b"auditors";
+
+/// The audit state given to a newly sealed or amended invoice.
+pub const AUDIT_STATE_PENDING: &str = "Pending";
+/// The audit state of an invoice whose credibility is below the review threshold. Only its auditor can approve it.
+pub const AUDIT_STATE_MUST_REVIEW: &str = "MustReview";
+/// The audit state of an invoice whose credibility is high enough to be sealed without review.
+pub const AUDIT_STATE_SEALED: &str = "Sealed";
+/// The audit state of an invoice approved by its auditor.
+pub const AUDIT_STATE_APPROVED: &str = "Approved";
+/// The audit state of an invoice on which its auditor flagged an issue.
+pub const AUDIT_STATE_FLAGGED: &str = "Flagged";
+/// The audit state of an invoice for which its auditor requested a correction.
+pub const AUDIT_STATE_CORRECTION_REQUESTED: &str = "CorrectionRequested";
+
+/// The audit flag of an invoice whose `tax_amount` does not match its taxable lines times their tax rates.
+pub const AUDIT_FLAG_TAX_MISMATCH: &str = "tax_mismatch";
+/// The audit flag of an invoice that does not match its purchase order and goods receipts.
+pub const AUDIT_FLAG_PO_MISMATCH: &str = "po_mismatch";
+/// The audit flag of an invoice that pushed the spend of its expense category over the budget of its fiscal period.
+pub const AUDIT_FLAG_BUDGET_OVERRUN: &str = "budget_overrun";
+/// The audit flag of an invoice that belongs to a cluster of invoices from the same supplier, each below the split
+/// rule threshold, whose combined total reaches it within the rule window.
+pub const AUDIT_FLAG_SPLIT_INVOICE: &str = "split_invoice";
+
+/// The highest credibility score.
+pub const MAX_CREDIBILITY: u8 = 100;
+
 /// Represents the state of the secret pass manager contract, including a count and an owner.
 ///
 /// This structure is serialized and deserialized using serde and stored persistently in the blockchain.
@@ -19,14 +184,37 @@ pub struct State {
     pub count: i32,
     /// The address of the contract owner.
     pub owner: Addr,
+    /// The number of invoices sealed so far, also used as the index of the next invoice.
+    #[serde(default)]
+    pub invoice_count: u32,
+    /// The number of accounts in the chart of accounts.
+    #[serde(default)]
+    pub account_count: u32,
+    /// The number of fiscal periods.
+    #[serde(default)]
+    pub period_count: u32,
+    /// The number of tax codes.
+    #[serde(default)]
+    pub tax_code_count: u32,
+    /// The number of suppliers.
+    #[serde(default)]
+    pub supplier_count: u32,
+    /// The number of invoices submitted for approval, also used as the pending index of the next one.
+    #[serde(default)]
+    pub pending_count: u32,
+    /// The number of expense categories.
+    #[serde(default)]
+    pub category_count: u32,
+    /// The time of the block in which an invoice version was last sealed, by `Add`, an approval or an amendment.
+    #[serde(default)]
+    pub last_sealed_at: Option<Timestamp>,
 }
 
 /// Retrieves a mutable singleton handle for the contract's configuration.
 ///
 /// This function takes a mutable reference to a storage implementation and returns a Singleton handle that can be used
 /// to read and write the configuration data persistently in the blockchain.
-
-pub fn config(storage: &mut dyn Storage) -> Singleton<State> {
+pub fn config(storage: &mut dyn Storage) -> Singleton<'_, State> {
     singleton(storage, CONFIG_KEY)
 }
 
@@ -34,15 +222,80 @@ pub fn config(storage: &mut dyn Storage) -> Singleton<State> {
 ///
 /// This function takes a reference to a storage implementation and returns a ReadonlySingleton handle that can be used
 /// to read the configuration data persistently in the blockchain without being able to modify it.
-pub fn config_read(storage: &dyn Storage) -> ReadonlySingleton<State> {
+pub fn config_read(storage: &dyn Storage) -> ReadonlySingleton<'_, State> {
     singleton_read(storage, CONFIG_KEY)
 }
 
+/// A credibility score between 0 and `MAX_CREDIBILITY`, computed off-chain from the OCR accuracy and the manual edits.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq, PartialOrd, Ord, JsonSchema)]
+#[serde(transparent)]
+pub struct CredibilityScore(pub u8);
+
+/// The owner-configurable credibility thresholds that drive the initial audit state of an invoice.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct CredibilityThresholds {
+    /// Invoices scoring below this value must be reviewed and can only be approved by their auditor.
+    pub review_below: CredibilityScore,
+    /// Invoices scoring at least this value are sealed without review. `None` means every invoice is reviewed.
+    pub seal_from: Option<CredibilityScore>,
+}
+
+impl Default for CredibilityThresholds {
+    fn default() -> Self {
+        CredibilityThresholds {
+            review_below: CredibilityScore(50),
+            seal_from: None,
+        }
+    }
+}
+
+/// Retrieves a mutable singleton handle for the credibility thresholds.
+pub fn credibility_thresholds(storage: &mut dyn Storage) -> Singleton<'_, CredibilityThresholds> {
+    singleton(storage, CREDIBILITY_THRESHOLDS_KEY)
+}
+
+/// Retrieves a read-only singleton handle for the credibility thresholds.
+pub fn credibility_thresholds_read(storage: &dyn Storage) -> ReadonlySingleton<'_, CredibilityThresholds> {
+    singleton_read(storage, CREDIBILITY_THRESHOLDS_KEY)
+}
+
+/// The kind of accounting document recorded by an `Invoice`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentKind {
+    /// A purchase invoice.
+    #[default]
+    Invoice,
+    /// A credit note reducing the amount of an earlier invoice.
+    CreditNote,
+    /// A debit note adding to the amount of an earlier invoice.
+    DebitNote,
+}
+
+/// A double-entry posting of an invoice to the general ledger.
+///
+/// A line either debits or credits its account. Lines carrying a tax code are the taxable base of the invoice.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct JournalLine {
+    /// The code of the account the line is posted to.
+    pub account: String,
+    /// The debited amount, zero for a credit line.
+    pub debit: Decimal,
+    /// The credited amount, zero for a debit line.
+    pub credit: Decimal,
+    /// The tax code applied to the line when it is part of the taxable base.
+    #[serde(default)]
+    pub tax_code: Option<String>,
+    /// The cost centre the line is allocated to, if any.
+    #[serde(default)]
+    pub cost_centre: Option<String>,
+}
+
 /// Represents an invoice entry in the secret pass manager contract.
 ///
 /// This structure is serialized and deserialized using serde and stored persistently in the blockchain. Each invoice
 /// can be identified by an index.
-#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
 pub struct Invoice {
     pub invoice_number: String,
     pub date: String,
@@ -54,8 +307,103 @@ pub struct Invoice {
     pub doc_hash: String,
     pub line_hash: String,
     pub auditors: String,
-    pub credibility: String,
+    pub credibility: CredibilityScore,
     pub audit_state: String,
+    /// The version of the invoice, starting at 1 and incremented by each amendment. Set by the contract.
+    #[serde(default)]
+    pub version: u32,
+    /// The `line_hash` of the version this one amends, if any. Set by the contract.
+    #[serde(default)]
+    pub supersedes: Option<String>,
+    /// The kind of document, a purchase invoice unless stated otherwise.
+    #[serde(default)]
+    pub kind: DocumentKind,
+    /// The index of the original invoice a credit or debit note refers to.
+    #[serde(default)]
+    pub reference: Option<u32>,
+    /// The sum of the credit notes issued against this invoice. Set by the contract.
+    #[serde(default)]
+    pub credited_amount: Decimal,
+    /// Whether the invoice was voided. A voided invoice is kept for audit purposes. Set by the contract.
+    #[serde(default)]
+    pub voided: bool,
+    /// The reason given when the invoice was voided. Set by the contract.
+    #[serde(default)]
+    pub void_reason: Option<String>,
+    /// The height of the block in which this version was sealed. Set by the contract.
+    #[serde(default)]
+    pub sealed_at_height: u64,
+    /// The time of the block in which this version was sealed. Set by the contract.
+    #[serde(default)]
+    pub sealed_at_time: Timestamp,
+    /// The address that sealed this version. Set by the contract.
+    #[serde(default)]
+    pub sealed_by: Option<Addr>,
+    /// The approvers who approved the invoice before it was sealed, when an approval rule applied. Set by the contract.
+    #[serde(default)]
+    pub approvals: Vec<Addr>,
+    /// Whether the amount was just below the threshold of an approval rule when sealed. Set by the contract.
+    #[serde(default)]
+    pub near_threshold: bool,
+    /// The positions of this version in the Merkle trees of the ledger and of its fiscal period. Set by the contract.
+    #[serde(default)]
+    pub ledger_leaf: Option<LedgerLeaf>,
+    /// The hex-encoded SHA-256 of the concatenated hex-encoded salted hashes of the fields covered by `line_hash`, in
+    /// `DisclosureField` order, against which a selective disclosure is verified. Set by the contract.
+    #[serde(default)]
+    pub field_commitment: String,
+    /// The journal lines posting the invoice to the general ledger. They are included in the `line_hash`.
+    #[serde(default)]
+    pub lines: Vec<JournalLine>,
+    /// The day the invoice is due, as `YYYY-MM-DD`. Invoices without a due date are due on their `date`.
+    #[serde(default)]
+    pub due_date: Option<String>,
+    /// The amount paid so far, in the currency of the invoice.
+    #[serde(default)]
+    pub paid_amount: Decimal,
+    /// The number of payments recorded for the invoice.
+    #[serde(default)]
+    pub payment_count: u32,
+    /// The number of the purchase order of the invoice, if any. It cannot change once the invoice is sealed.
+    #[serde(default)]
+    pub purchase_order: Option<String>,
+    /// The items invoiced, matched against the purchase order and its goods receipts.
+    #[serde(default)]
+    pub items: Vec<ItemLine>,
+    /// The result of the three-way match of an invoice with a purchase order, kept up to date as goods are received.
+    #[serde(default)]
+    pub match_result: Option<MatchResult>,
+    /// The code of the expense category of the invoice, if any.
+    #[serde(default)]
+    pub category: Option<String>,
+    /// The fiscal period whose budget of the expense category the invoice was spent against when sealed, if any. Set by
+    /// the contract.
+    #[serde(default)]
+    pub budget_period: Option<String>,
+    /// The id of the supplier in the supplier registry, if any. It cannot change once the invoice is sealed.
+    #[serde(default)]
+    pub supplier: Option<String>,
+    /// The rate from `currency` to the base currency on `date`, once a base currency is set.
+    #[serde(default)]
+    pub exchange_rate: Option<Decimal>,
+    /// The `total_amount` in the base currency, once a base currency is set.
+    #[serde(default)]
+    pub base_total_amount: Option<Decimal>,
+    /// The `tax_amount` in the base currency, once a base currency is set.
+    #[serde(default)]
+    pub base_tax_amount: Option<Decimal>,
+    /// The taxable base and input VAT of each tax code of the journal lines, computed with the rates in force when the
+    /// invoice was sealed.
+    #[serde(default)]
+    pub tax_breakdown: Vec<TaxBreakdown>,
+    /// The reasons why the contract flagged the invoice for audit, such as `AUDIT_FLAG_TAX_MISMATCH`.
+    #[serde(default)]
+    pub audit_flags: Vec<String>,
+}
+
+/// Builds the storage key of the latest version of an invoice from its index.
+pub fn invoice_key(index: u32) -> Vec<u8> {
+    index.to_string().into_bytes()
 }
 
 /// Retrieves a mutable singleton handle for an invoice based on an index.
@@ -74,8 +422,997 @@ pub fn config_invoice_read<'a>(storage: &'a dyn Storage, index: &[u8]) -> Readon
     singleton_read(storage, index)
 }
 
+/// Builds the storage key of a superseded invoice version from the invoice index and the version number.
+pub fn invoice_version_key(index: u32, version: u32) -> Vec<u8> {
+    [index.to_be_bytes(), version.to_be_bytes()].concat()
+}
+
+/// Retrieves a mutable bucket handle for the superseded versions of amended invoices.
+///
+/// Each version is stored under `invoice_version_key` so that earlier versions stay readable after an amendment.
+pub fn invoice_versions(storage: &mut dyn Storage) -> Bucket<'_, Invoice> {
+    bucket(storage, INVOICE_VERSIONS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the superseded versions of amended invoices.
+pub fn invoice_versions_read(storage: &dyn Storage) -> ReadonlyBucket<'_, Invoice> {
+    bucket_read(storage, INVOICE_VERSIONS_KEY)
+}
+
 /// A constant prefix used for storing revoked permits.
 ///
 /// This prefix is intended to be used in conjunction with a key-value store to manage and track revoked permissions
 /// within the contract's state.
-pub const PREFIX_REVOKED_PERMITS: &str = "revoked_permits";
\ No newline at end of file
+pub const PREFIX_REVOKED_PERMITS: &str = "revoked_permits";
+
+/// The type of an account of the chart of accounts.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountType {
+    Asset,
+    Liability,
+    Equity,
+    Revenue,
+    Expense,
+}
+
+/// An account of the owner-managed chart of accounts.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Account {
+    /// The account code referenced by journal lines.
+    pub code: String,
+    /// The name of the account.
+    pub name: String,
+    /// The type of the account.
+    pub account_type: AccountType,
+    /// Whether journal lines can still be posted to the account.
+    pub active: bool,
+}
+
+/// Retrieves a mutable bucket handle for the chart of accounts, keyed by account code.
+pub fn accounts(storage: &mut dyn Storage) -> Bucket<'_, Account> {
+    bucket(storage, ACCOUNTS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the chart of accounts, keyed by account code.
+pub fn accounts_read(storage: &dyn Storage) -> ReadonlyBucket<'_, Account> {
+    bucket_read(storage, ACCOUNTS_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the account codes, keyed by their big-endian creation index.
+pub fn account_codes(storage: &mut dyn Storage) -> Bucket<'_, String> {
+    bucket(storage, ACCOUNT_CODES_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the account codes, keyed by their big-endian creation index.
+pub fn account_codes_read(storage: &dyn Storage) -> ReadonlyBucket<'_, String> {
+    bucket_read(storage, ACCOUNT_CODES_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the number of invoices assigned to each engaged auditor, keyed by address.
+pub fn auditors(storage: &mut dyn Storage) -> Bucket<'_, u32> {
+    bucket(storage, AUDITORS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the number of invoices assigned to each engaged auditor, keyed by address.
+pub fn auditors_read(storage: &dyn Storage) -> ReadonlyBucket<'_, u32> {
+    bucket_read(storage, AUDITORS_KEY)
+}
+
+/// The running totals of the journal lines posted to an account.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct AccountTotals {
+    /// The sum of the debits posted to the account.
+    pub debit: Decimal,
+    /// The sum of the credits posted to the account.
+    pub credit: Decimal,
+}
+
+/// The first and last months with postings, as `year * 12 + month - 1`.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct LedgerSpan {
+    pub first_month: u32,
+    pub last_month: u32,
+}
+
+/// Builds the storage key of the monthly totals of an account.
+///
+/// The month comes first as a fixed-size big-endian number so that keys cannot collide.
+pub fn account_month_key(month: u32, code: &str) -> Vec<u8> {
+    [&month.to_be_bytes(), code.as_bytes()].concat()
+}
+
+/// Retrieves a mutable bucket handle for the all-time totals of each account, keyed by account code.
+pub fn account_totals(storage: &mut dyn Storage) -> Bucket<'_, AccountTotals> {
+    bucket(storage, ACCOUNT_TOTALS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the all-time totals of each account, keyed by account code.
+pub fn account_totals_read(storage: &dyn Storage) -> ReadonlyBucket<'_, AccountTotals> {
+    bucket_read(storage, ACCOUNT_TOTALS_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the monthly totals of each account, keyed by `account_month_key`.
+pub fn account_month_totals(storage: &mut dyn Storage) -> Bucket<'_, AccountTotals> {
+    bucket(storage, ACCOUNT_MONTH_TOTALS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the monthly totals of each account, keyed by `account_month_key`.
+pub fn account_month_totals_read(storage: &dyn Storage) -> ReadonlyBucket<'_, AccountTotals> {
+    bucket_read(storage, ACCOUNT_MONTH_TOTALS_KEY)
+}
+
+/// Retrieves a mutable singleton handle for the span of months with postings.
+pub fn ledger_span(storage: &mut dyn Storage) -> Singleton<'_, LedgerSpan> {
+    singleton(storage, LEDGER_SPAN_KEY)
+}
+
+/// Retrieves a read-only singleton handle for the span of months with postings.
+pub fn ledger_span_read(storage: &dyn Storage) -> ReadonlySingleton<'_, LedgerSpan> {
+    singleton_read(storage, LEDGER_SPAN_KEY)
+}
+
+/// The base currency of the ledger and the address allowed to feed exchange rates besides the owner.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct CurrencyConfig {
+    /// The ISO-4217 code of the base currency.
+    pub base_currency: String,
+    /// The exchange-rate oracle, if any.
+    pub oracle: Option<Addr>,
+}
+
+/// Retrieves a mutable singleton handle for the currency configuration.
+pub fn currency_config(storage: &mut dyn Storage) -> Singleton<'_, CurrencyConfig> {
+    singleton(storage, CURRENCY_CONFIG_KEY)
+}
+
+/// Retrieves a read-only singleton handle for the currency configuration.
+pub fn currency_config_read(storage: &dyn Storage) -> ReadonlySingleton<'_, CurrencyConfig> {
+    singleton_read(storage, CURRENCY_CONFIG_KEY)
+}
+
+/// The rate converting one unit of `from` into `to` on `date`.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct ExchangeRate {
+    pub from: String,
+    pub to: String,
+    /// The day the rate applies to, as `YYYY-MM-DD`.
+    pub date: String,
+    pub rate: Decimal,
+    /// The owner or oracle who recorded the rate.
+    pub recorded_by: Addr,
+}
+
+/// Builds the key of an exchange rate from its three-letter currency codes and its date.
+pub fn exchange_rate_key(from: &str, to: &str, date: &str) -> Vec<u8> {
+    [from.as_bytes(), to.as_bytes(), date.as_bytes()].concat()
+}
+
+/// Retrieves a mutable bucket handle for the exchange rates, keyed by `exchange_rate_key`.
+pub fn exchange_rates(storage: &mut dyn Storage) -> Bucket<'_, ExchangeRate> {
+    bucket(storage, EXCHANGE_RATES_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the exchange rates, keyed by `exchange_rate_key`.
+pub fn exchange_rates_read(storage: &dyn Storage) -> ReadonlyBucket<'_, ExchangeRate> {
+    bucket_read(storage, EXCHANGE_RATES_KEY)
+}
+
+/// A quantity of an item at a unit price, on a purchase order or an invoice.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct ItemLine {
+    /// The reference of the item.
+    pub item: String,
+    pub quantity: Decimal,
+    pub unit_price: Decimal,
+}
+
+/// A quantity of an item received.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct ItemQuantity {
+    /// The reference of the item.
+    pub item: String,
+    pub quantity: Decimal,
+}
+
+/// A purchase order.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct PurchaseOrder {
+    /// The unique number referenced by invoices.
+    pub number: String,
+    /// The id of the supplier in the supplier registry, if any.
+    pub supplier: Option<String>,
+    /// The ISO-4217 code of the currency of the unit prices.
+    pub currency: String,
+    /// The day of the order, as `YYYY-MM-DD`.
+    pub date: String,
+    /// The items ordered.
+    pub lines: Vec<ItemLine>,
+    /// The quantities received so far by item, kept by the contract.
+    #[serde(default)]
+    pub received: Vec<ItemQuantity>,
+    /// The number of goods receipts recorded, kept by the contract.
+    #[serde(default)]
+    pub receipt_count: u32,
+    /// The indexes of the invoices linked to the order, kept by the contract.
+    #[serde(default)]
+    pub invoices: Vec<u32>,
+}
+
+/// A goods receipt against a purchase order.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct GoodsReceipt {
+    /// The day the goods were received, as `YYYY-MM-DD`.
+    pub date: String,
+    /// The quantities received.
+    pub lines: Vec<ItemQuantity>,
+    /// The address that recorded the receipt.
+    pub recorded_by: Addr,
+}
+
+/// The relative tolerances of the three-way match, such as `0.02` for 2%.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct MatchTolerance {
+    /// The tolerance on the quantities invoiced over the quantities ordered and received.
+    pub quantity: Decimal,
+    /// The tolerance on the unit prices and on the invoiced amount over the value of the goods received.
+    pub amount: Decimal,
+}
+
+/// The result of the three-way match of an invoice with its purchase order and goods receipts.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct MatchResult {
+    /// Whether the invoice matches within the tolerances. Invoices that do not match cannot be approved.
+    pub matched: bool,
+    /// Why the invoice does not match.
+    pub reasons: Vec<String>,
+}
+
+/// Retrieves a mutable bucket handle for the purchase orders, keyed by number.
+pub fn purchase_orders(storage: &mut dyn Storage) -> Bucket<'_, PurchaseOrder> {
+    bucket(storage, PURCHASE_ORDERS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the purchase orders, keyed by number.
+pub fn purchase_orders_read(storage: &dyn Storage) -> ReadonlyBucket<'_, PurchaseOrder> {
+    bucket_read(storage, PURCHASE_ORDERS_KEY)
+}
+
+/// Builds the key of the goods receipt of a purchase order at the given position.
+pub fn goods_receipt_key(order: &str, position: u32) -> Vec<u8> {
+    [order.as_bytes(), &position.to_be_bytes()].concat()
+}
+
+/// Retrieves a mutable bucket handle for the goods receipts, keyed by `goods_receipt_key`.
+pub fn goods_receipts(storage: &mut dyn Storage) -> Bucket<'_, GoodsReceipt> {
+    bucket(storage, GOODS_RECEIPTS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the goods receipts, keyed by `goods_receipt_key`.
+pub fn goods_receipts_read(storage: &dyn Storage) -> ReadonlyBucket<'_, GoodsReceipt> {
+    bucket_read(storage, GOODS_RECEIPTS_KEY)
+}
+
+/// Retrieves a mutable singleton handle for the tolerances of the three-way match.
+pub fn match_tolerance(storage: &mut dyn Storage) -> Singleton<'_, MatchTolerance> {
+    singleton(storage, MATCH_TOLERANCE_KEY)
+}
+
+/// Retrieves a read-only singleton handle for the tolerances of the three-way match.
+pub fn match_tolerance_read(storage: &dyn Storage) -> ReadonlySingleton<'_, MatchTolerance> {
+    singleton_read(storage, MATCH_TOLERANCE_KEY)
+}
+
+/// How a payment was made.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentMethod {
+    BankTransfer,
+    Card,
+    Cash,
+    Cheque,
+    /// A SNIP-20 token transfer forwarded to the supplier by the contract.
+    Token,
+    Other,
+}
+
+/// A payment of an invoice.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Payment {
+    /// The amount paid, in the currency of the invoice.
+    pub amount: Decimal,
+    /// The day of the payment, as `YYYY-MM-DD`.
+    pub date: String,
+    pub method: PaymentMethod,
+    /// The SHA-256 hash of the bank or transaction reference of the payment.
+    pub reference_hash: String,
+    /// The address that recorded the payment.
+    pub recorded_by: Addr,
+    /// The height of the block in which the payment was recorded.
+    pub recorded_at_height: u64,
+    /// The SNIP-20 token contract of a `Token` payment.
+    #[serde(default)]
+    pub token: Option<Addr>,
+}
+
+/// The settlement status of an invoice, derived from its outstanding balance and due date.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentStatus {
+    Unpaid,
+    PartiallyPaid,
+    Paid,
+    /// The invoice is past its due date with an outstanding balance.
+    Overdue,
+}
+
+/// A SNIP-20 token accepted to pay invoices.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct AcceptedToken {
+    /// The address of the token contract.
+    pub contract: Addr,
+    /// The code hash of the token contract.
+    pub code_hash: String,
+    /// The ISO-4217 code of the currency the token is pegged to. It only pays invoices in that currency.
+    pub currency: String,
+    /// The number of decimals of the token.
+    pub decimals: u8,
+}
+
+/// Retrieves a mutable bucket handle for the accepted tokens, keyed by contract address.
+pub fn tokens(storage: &mut dyn Storage) -> Bucket<'_, AcceptedToken> {
+    bucket(storage, TOKENS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the accepted tokens, keyed by contract address.
+pub fn tokens_read(storage: &dyn Storage) -> ReadonlyBucket<'_, AcceptedToken> {
+    bucket_read(storage, TOKENS_KEY)
+}
+
+/// Builds the key of the payment of an invoice at the given position.
+pub fn payment_key(invoice_index: u32, position: u32) -> Vec<u8> {
+    [invoice_index.to_be_bytes(), position.to_be_bytes()].concat()
+}
+
+/// Retrieves a mutable bucket handle for the payments, keyed by `payment_key`.
+pub fn payments(storage: &mut dyn Storage) -> Bucket<'_, Payment> {
+    bucket(storage, PAYMENTS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the payments, keyed by `payment_key`.
+pub fn payments_read(storage: &dyn Storage) -> ReadonlyBucket<'_, Payment> {
+    bucket_read(storage, PAYMENTS_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the sorted indexes of the invoices with an outstanding balance, keyed by
+/// big-endian page number.
+pub fn open_payables(storage: &mut dyn Storage) -> Bucket<'_, Vec<u32>> {
+    bucket(storage, OPEN_PAYABLES_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the sorted indexes of the invoices with an outstanding balance, keyed by
+/// big-endian page number.
+pub fn open_payables_read(storage: &dyn Storage) -> ReadonlyBucket<'_, Vec<u32>> {
+    bucket_read(storage, OPEN_PAYABLES_KEY)
+}
+
+/// The status of a supplier.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SupplierStatus {
+    /// New invoices can reference the supplier.
+    Active,
+    /// New invoices and amendments referencing the supplier are rejected.
+    Inactive,
+}
+
+/// A supplier of the supplier registry.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Supplier {
+    /// The id referenced by invoices.
+    pub id: String,
+    pub legal_name: String,
+    /// The VAT identification number, if the supplier is registered for VAT.
+    pub vat_number: Option<String>,
+    /// The ISO-3166 alpha-2 code of the country of the supplier.
+    pub country: String,
+    /// The SHA-256 hash of the IBAN of the supplier, so that it can be checked without being disclosed.
+    pub iban_hash: Option<String>,
+    pub status: SupplierStatus,
+    /// The address receiving the SNIP-20 payments of the supplier, if it accepts them.
+    #[serde(default)]
+    pub address: Option<Addr>,
+}
+
+/// The totals of the invoices of a supplier, in the base currency once it is set.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct SupplierTotals {
+    /// The number of invoices and notes ever recorded for the supplier, voided ones included.
+    pub invoice_count: u32,
+    /// The total amount of the invoices and debit notes that are not voided.
+    pub total_amount: Decimal,
+    /// The tax amount of the invoices and debit notes that are not voided.
+    pub tax_amount: Decimal,
+    /// The total amount of the credit notes that are not voided.
+    pub credited_amount: Decimal,
+}
+
+/// Retrieves a mutable bucket handle for the suppliers, keyed by id.
+pub fn suppliers(storage: &mut dyn Storage) -> Bucket<'_, Supplier> {
+    bucket(storage, SUPPLIERS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the suppliers, keyed by id.
+pub fn suppliers_read(storage: &dyn Storage) -> ReadonlyBucket<'_, Supplier> {
+    bucket_read(storage, SUPPLIERS_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the supplier ids, keyed by their big-endian creation index.
+pub fn supplier_ids(storage: &mut dyn Storage) -> Bucket<'_, String> {
+    bucket(storage, SUPPLIER_IDS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the supplier ids, keyed by their big-endian creation index.
+pub fn supplier_ids_read(storage: &dyn Storage) -> ReadonlyBucket<'_, String> {
+    bucket_read(storage, SUPPLIER_IDS_KEY)
+}
+
+/// Builds the key of the invoice of a supplier at the given position.
+pub fn supplier_invoice_key(supplier: &str, position: u32) -> Vec<u8> {
+    [supplier.as_bytes(), &position.to_be_bytes()].concat()
+}
+
+/// Retrieves a mutable bucket handle for the invoice indexes of each supplier, keyed by `supplier_invoice_key`.
+pub fn supplier_invoices(storage: &mut dyn Storage) -> Bucket<'_, u32> {
+    bucket(storage, SUPPLIER_INVOICES_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the invoice indexes of each supplier, keyed by `supplier_invoice_key`.
+pub fn supplier_invoices_read(storage: &dyn Storage) -> ReadonlyBucket<'_, u32> {
+    bucket_read(storage, SUPPLIER_INVOICES_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the totals of each supplier, keyed by supplier id.
+pub fn supplier_totals(storage: &mut dyn Storage) -> Bucket<'_, SupplierTotals> {
+    bucket(storage, SUPPLIER_TOTALS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the totals of each supplier, keyed by supplier id.
+pub fn supplier_totals_read(storage: &dyn Storage) -> ReadonlyBucket<'_, SupplierTotals> {
+    bucket_read(storage, SUPPLIER_TOTALS_KEY)
+}
+
+/// A tax code that journal lines can be tagged with.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct TaxCode {
+    /// The code referenced by journal lines, such as `VAT20`.
+    pub code: String,
+    /// The rate applied to the taxable base, such as `0.2`.
+    pub rate: Decimal,
+    /// The jurisdiction levying the tax, such as `FR`.
+    pub jurisdiction: String,
+    /// Whether journal lines can still be tagged with the code.
+    pub active: bool,
+}
+
+/// The taxable base and input VAT of one tax code on one invoice.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct TaxBreakdown {
+    /// The tax code of the journal lines.
+    pub tax_code: String,
+    /// The sum of the debits and credits of the journal lines tagged with the tax code.
+    pub base: Decimal,
+    /// The taxable base times the rate of the tax code.
+    pub tax: Decimal,
+}
+
+/// The input VAT of one tax code and currency over one month.
+///
+/// Credit notes are totalled apart, since amounts cannot be negative; the deductible VAT is `tax - credited_tax`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct VatTotals {
+    pub tax_code: String,
+    pub currency: String,
+    /// The taxable base of the invoices and debit notes.
+    pub base: Decimal,
+    /// The input VAT of the invoices and debit notes.
+    pub tax: Decimal,
+    /// The taxable base of the credit notes.
+    pub credited_base: Decimal,
+    /// The input VAT of the credit notes.
+    pub credited_tax: Decimal,
+    /// The indexes of the invoices and notes contributing to the totals.
+    pub invoices: Vec<u32>,
+}
+
+/// Retrieves a mutable bucket handle for the input VAT totals of each month, keyed by big-endian month index.
+pub fn vat_months(storage: &mut dyn Storage) -> Bucket<'_, Vec<VatTotals>> {
+    bucket(storage, VAT_MONTHS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the input VAT totals of each month, keyed by big-endian month index.
+pub fn vat_months_read(storage: &dyn Storage) -> ReadonlyBucket<'_, Vec<VatTotals>> {
+    bucket_read(storage, VAT_MONTHS_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the tax codes, keyed by code.
+pub fn tax_codes(storage: &mut dyn Storage) -> Bucket<'_, TaxCode> {
+    bucket(storage, TAX_CODES_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the tax codes, keyed by code.
+pub fn tax_codes_read(storage: &dyn Storage) -> ReadonlyBucket<'_, TaxCode> {
+    bucket_read(storage, TAX_CODES_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the tax codes, keyed by their big-endian creation index.
+pub fn tax_code_index(storage: &mut dyn Storage) -> Bucket<'_, String> {
+    bucket(storage, TAX_CODE_INDEX_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the tax codes, keyed by their big-endian creation index.
+pub fn tax_code_index_read(storage: &dyn Storage) -> ReadonlyBucket<'_, String> {
+    bucket_read(storage, TAX_CODE_INDEX_KEY)
+}
+
+/// Retrieves a mutable singleton handle for the rounding tolerance of the tax consistency check.
+pub fn tax_tolerance(storage: &mut dyn Storage) -> Singleton<'_, Decimal> {
+    singleton(storage, TAX_TOLERANCE_KEY)
+}
+
+/// Retrieves a read-only singleton handle for the rounding tolerance of the tax consistency check.
+pub fn tax_tolerance_read(storage: &dyn Storage) -> ReadonlySingleton<'_, Decimal> {
+    singleton_read(storage, TAX_TOLERANCE_KEY)
+}
+
+/// The status of a fiscal period.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PeriodStatus {
+    /// Invoices dated in the period can be added, amended and voided.
+    Open,
+    /// The period is locked: invoices dated in it are rejected until it is reopened.
+    Closed,
+    /// An auditor issued an opinion on the period: it is locked for good and cannot be reopened.
+    Audited,
+}
+
+/// A recorded reopening of a closed fiscal period.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PeriodReopen {
+    /// The approver who reopened the period.
+    pub reopened_by: Addr,
+    /// Why the period was reopened.
+    pub reason: String,
+    /// The time of the block in which the period was reopened.
+    pub reopened_at: Timestamp,
+}
+
+/// A fiscal period, from `start` to `end` inclusive.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct FiscalPeriod {
+    /// The unique name of the period, such as `FY2024`.
+    pub name: String,
+    /// The first day of the period, as `YYYY-MM-DD`.
+    pub start: String,
+    /// The last day of the period, as `YYYY-MM-DD`.
+    pub end: String,
+    /// Whether the period is open or closed.
+    pub status: PeriodStatus,
+    /// Whether closing the period also requires the sign-off of an auditor.
+    pub require_auditor: bool,
+    /// The approver who signed off the closing, if any.
+    pub approver_signoff: Option<Addr>,
+    /// The auditor who signed off the closing, if any.
+    pub auditor_signoff: Option<Addr>,
+    /// The time of the block in which the period was last closed.
+    pub closed_at: Option<Timestamp>,
+    /// Every reopening of the period, oldest first.
+    pub reopens: Vec<PeriodReopen>,
+}
+
+/// Retrieves a mutable bucket handle for the fiscal periods, keyed by name.
+pub fn fiscal_periods(storage: &mut dyn Storage) -> Bucket<'_, FiscalPeriod> {
+    bucket(storage, FISCAL_PERIODS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the fiscal periods, keyed by name.
+pub fn fiscal_periods_read(storage: &dyn Storage) -> ReadonlyBucket<'_, FiscalPeriod> {
+    bucket_read(storage, FISCAL_PERIODS_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the fiscal period names, keyed by their big-endian creation index.
+pub fn fiscal_period_names(storage: &mut dyn Storage) -> Bucket<'_, String> {
+    bucket(storage, FISCAL_PERIOD_NAMES_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the fiscal period names, keyed by their big-endian creation index.
+pub fn fiscal_period_names_read(storage: &dyn Storage) -> ReadonlyBucket<'_, String> {
+    bucket_read(storage, FISCAL_PERIOD_NAMES_KEY)
+}
+
+/// An approval rule: invoices above the threshold need approvals from distinct approvers before they are sealed.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct ApprovalRule {
+    /// The total amount, in the base currency once set, above which the rule applies.
+    pub threshold: Decimal,
+    /// The number of distinct approvers required.
+    pub approvals: u32,
+}
+
+/// The split-invoice detection rule: invoices from the same supplier, each below the threshold, whose combined total
+/// reaches it within the window are flagged for audit.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct SplitRule {
+    /// The number of days, counted from the date of an invoice in both directions, within which invoices are combined.
+    pub window_days: u32,
+    /// The combined total amount, in the base currency once set, that a cluster must reach.
+    pub threshold: Decimal,
+}
+
+/// An invoice submitted by the owner and waiting for the approvals required by the approval rules.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PendingInvoice {
+    /// The invoice, sealed once approved.
+    pub invoice: Invoice,
+    /// The number of distinct approvers required when it was submitted.
+    pub required_approvals: u32,
+    /// The approvers who approved it so far.
+    pub approvals: Vec<Addr>,
+    /// The address that submitted it.
+    pub submitted_by: Addr,
+    /// The block height at which it was submitted.
+    pub submitted_at_height: u64,
+    /// The index of the invoice once sealed.
+    pub invoice_index: Option<u32>,
+    /// The approver who rejected it, or the owner who withdrew it, instead of sealing it.
+    pub rejected_by: Option<Addr>,
+    /// Why it was rejected or withdrawn.
+    pub rejection_reason: Option<String>,
+    /// The index of the sealed invoice it amends, when it is an amendment rather than a new invoice.
+    pub amends: Option<u32>,
+}
+
+/// An expense category of the owner-managed list of categories.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct ExpenseCategory {
+    /// The code referenced by invoices.
+    pub code: String,
+    /// The name of the category.
+    pub name: String,
+    /// Whether invoices can still be tagged with the category.
+    pub active: bool,
+}
+
+/// The budget of an expense category over a fiscal period and the spend recorded against it.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct CategoryBudget {
+    /// The budget, in the base currency once set. No budget is set when `None`.
+    pub budget: Option<Decimal>,
+    /// The net amounts of the invoices of the category dated in the period, less its credit notes.
+    pub spent: Decimal,
+}
+
+/// Retrieves a mutable bucket handle for the expense categories, keyed by code.
+pub fn expense_categories(storage: &mut dyn Storage) -> Bucket<'_, ExpenseCategory> {
+    bucket(storage, EXPENSE_CATEGORIES_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the expense categories, keyed by code.
+pub fn expense_categories_read(storage: &dyn Storage) -> ReadonlyBucket<'_, ExpenseCategory> {
+    bucket_read(storage, EXPENSE_CATEGORIES_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the expense category codes, keyed by creation index.
+pub fn expense_category_codes(storage: &mut dyn Storage) -> Bucket<'_, String> {
+    bucket(storage, EXPENSE_CATEGORY_CODES_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the expense category codes, keyed by creation index.
+pub fn expense_category_codes_read(storage: &dyn Storage) -> ReadonlyBucket<'_, String> {
+    bucket_read(storage, EXPENSE_CATEGORY_CODES_KEY)
+}
+
+/// Builds the key of the budget of an expense category over a fiscal period.
+pub fn category_budget_key(category: &str, period: &str) -> Vec<u8> {
+    [category.as_bytes(), &[0], period.as_bytes()].concat()
+}
+
+/// Retrieves a mutable bucket handle for the budgets of the expense categories, keyed by `category_budget_key`.
+pub fn category_budgets(storage: &mut dyn Storage) -> Bucket<'_, CategoryBudget> {
+    bucket(storage, CATEGORY_BUDGETS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the budgets of the expense categories, keyed by `category_budget_key`.
+pub fn category_budgets_read(storage: &dyn Storage) -> ReadonlyBucket<'_, CategoryBudget> {
+    bucket_read(storage, CATEGORY_BUDGETS_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the sorted indexes of the invoices dated in each month, keyed by the
+/// big-endian `month_index`.
+pub fn month_invoices(storage: &mut dyn Storage) -> Bucket<'_, Vec<u32>> {
+    bucket(storage, MONTH_INVOICES_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the sorted indexes of the invoices dated in each month, keyed by the
+/// big-endian `month_index`.
+pub fn month_invoices_read(storage: &dyn Storage) -> ReadonlyBucket<'_, Vec<u32>> {
+    bucket_read(storage, MONTH_INVOICES_KEY)
+}
+
+/// The counters of the anomaly tests run by auditors over the invoices and debit notes of one month.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct AnomalyCounters {
+    /// The number of invoices and debit notes counted.
+    pub invoices: u32,
+    /// The number of total amounts by first significant digit, from 1 to 9.
+    pub first_digits: Vec<u32>,
+    /// The number of total amounts that are whole multiples of 100.
+    pub round_amounts: u32,
+    /// The number of invoices dated on a Saturday or a Sunday.
+    pub weekend_dates: u32,
+    /// The number of invoices just below the threshold of an approval rule when sealed.
+    pub near_threshold: u32,
+}
+
+/// Retrieves a mutable bucket handle for the anomaly counters of each month, keyed by big-endian month index.
+pub fn anomaly_months(storage: &mut dyn Storage) -> Bucket<'_, AnomalyCounters> {
+    bucket(storage, ANOMALY_MONTHS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the anomaly counters of each month, keyed by big-endian month index.
+pub fn anomaly_months_read(storage: &dyn Storage) -> ReadonlyBucket<'_, AnomalyCounters> {
+    bucket_read(storage, ANOMALY_MONTHS_KEY)
+}
+
+/// A sample of the invoices of a fiscal period drawn by an auditor from the block randomness. It cannot be redrawn.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct AuditSample {
+    /// The name of the fiscal period.
+    pub period: String,
+    /// The number of invoices drawn from each stratum.
+    pub size: u32,
+    /// The ascending amount boundaries splitting the population into strata, in the base currency once set.
+    pub strata: Vec<Decimal>,
+    /// Whether invoices were drawn with a probability proportional to their amount.
+    pub monetary_unit: bool,
+    /// The hex-encoded seed the sample was drawn from, derived from the block randomness and the period.
+    pub seed: String,
+    /// The number of invoices the sample was drawn from.
+    pub population: u32,
+    /// The indexes of the invoices drawn, in ascending order.
+    pub invoices: Vec<u32>,
+    /// The auditor who drew the sample.
+    pub drawn_by: Addr,
+    /// The block height at which the sample was drawn.
+    pub drawn_at_height: u64,
+}
+
+/// The type of the opinion an auditor issues on a fiscal period.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OpinionType {
+    /// The accounts of the period give a true and fair view.
+    Unqualified,
+    /// The accounts give a true and fair view except for the matters described in the report.
+    Qualified,
+    /// The accounts do not give a true and fair view.
+    Adverse,
+    /// The auditor could not obtain enough evidence to form an opinion.
+    Disclaimer,
+}
+
+/// The opinion an auditor signed off on a fiscal period, which locks the period for good.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct AuditOpinion {
+    /// The name of the fiscal period.
+    pub period: String,
+    /// The type of the opinion.
+    pub opinion: OpinionType,
+    /// The SHA-256 hash of the audit report document, hex-encoded.
+    pub report_hash: String,
+    /// The hex-encoded SHA-256 commitment over the index, the `line_hash` and the voided flag of every invoice in
+    /// scope, in ascending index order.
+    pub commitment: String,
+    /// The indexes of the invoices in scope: every invoice and note dated in the period, voided ones included.
+    pub invoices: Vec<u32>,
+    /// Whether anyone can retrieve the opinion without a permit.
+    pub public: bool,
+    /// The auditor who issued the opinion.
+    pub issued_by: Addr,
+    /// The block height at which the opinion was issued.
+    pub issued_at_height: u64,
+    /// The time of the block in which the opinion was issued.
+    pub issued_at: Timestamp,
+}
+
+/// Retrieves a mutable bucket handle for the audit samples, keyed by fiscal period name.
+pub fn audit_samples(storage: &mut dyn Storage) -> Bucket<'_, AuditSample> {
+    bucket(storage, AUDIT_SAMPLES_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the audit samples, keyed by fiscal period name.
+pub fn audit_samples_read(storage: &dyn Storage) -> ReadonlyBucket<'_, AuditSample> {
+    bucket_read(storage, AUDIT_SAMPLES_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the audit opinions, keyed by fiscal period name.
+pub fn audit_opinions(storage: &mut dyn Storage) -> Bucket<'_, AuditOpinion> {
+    bucket(storage, AUDIT_OPINIONS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the audit opinions, keyed by fiscal period name.
+pub fn audit_opinions_read(storage: &dyn Storage) -> ReadonlyBucket<'_, AuditOpinion> {
+    bucket_read(storage, AUDIT_OPINIONS_KEY)
+}
+
+/// The positions of a sealed invoice version in the Merkle trees it was appended to.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct LedgerLeaf {
+    /// The position of the leaf in the tree of the whole ledger.
+    pub leaf: u32,
+    /// The fiscal period the version was dated in when sealed, if any. A period added afterwards does not hold it.
+    pub period: Option<String>,
+    /// The position of the leaf in the tree of that fiscal period.
+    pub period_leaf: Option<u32>,
+}
+
+/// The size and root of an append-only Merkle tree over sealed `line_hash` values.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct MerkleTree {
+    /// The number of leaves appended so far.
+    pub leaf_count: u32,
+    /// The hex-encoded root, empty while the tree has no leaves.
+    pub root: String,
+}
+
+/// Returns the key of a node of a Merkle tree: the tree, a zero byte, the level from the leaves and the big-endian
+/// position in the level.
+pub fn merkle_node_key(tree: &[u8], level: u8, position: u32) -> Vec<u8> {
+    [tree, &[0, level], &position.to_be_bytes()].concat()
+}
+
+/// Returns the key of a root of a Merkle tree: the tree, a zero byte and the big-endian number of leaves it covers.
+pub fn merkle_root_key(tree: &[u8], leaf_count: u32) -> Vec<u8> {
+    [tree, &[0], &leaf_count.to_be_bytes()].concat()
+}
+
+/// Retrieves a mutable bucket handle for the size and root of the Merkle trees, keyed by tree.
+pub fn merkle_trees(storage: &mut dyn Storage) -> Bucket<'_, MerkleTree> {
+    bucket(storage, MERKLE_TREES_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the size and root of the Merkle trees, keyed by tree.
+pub fn merkle_trees_read(storage: &dyn Storage) -> ReadonlyBucket<'_, MerkleTree> {
+    bucket_read(storage, MERKLE_TREES_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the nodes of the Merkle trees, keyed by `merkle_node_key`.
+pub fn merkle_nodes(storage: &mut dyn Storage) -> Bucket<'_, Binary> {
+    bucket(storage, MERKLE_NODES_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the nodes of the Merkle trees, keyed by `merkle_node_key`.
+pub fn merkle_nodes_read(storage: &dyn Storage) -> ReadonlyBucket<'_, Binary> {
+    bucket_read(storage, MERKLE_NODES_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the hex-encoded roots of the Merkle trees, keyed by `merkle_root_key`.
+pub fn merkle_roots(storage: &mut dyn Storage) -> Bucket<'_, String> {
+    bucket(storage, MERKLE_ROOTS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the hex-encoded roots of the Merkle trees, keyed by `merkle_root_key`.
+pub fn merkle_roots_read(storage: &dyn Storage) -> ReadonlyBucket<'_, String> {
+    bucket_read(storage, MERKLE_ROOTS_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the time each root of the ledger Merkle tree was sealed, keyed by root.
+pub fn ledger_roots(storage: &mut dyn Storage) -> Bucket<'_, Timestamp> {
+    bucket(storage, LEDGER_ROOTS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the time each root of the ledger Merkle tree was sealed, keyed by root.
+pub fn ledger_roots_read(storage: &dyn Storage) -> ReadonlyBucket<'_, Timestamp> {
+    bucket_read(storage, LEDGER_ROOTS_KEY)
+}
+
+/// Retrieves a mutable singleton handle for the approval rules.
+pub fn approval_rules(storage: &mut dyn Storage) -> Singleton<'_, Vec<ApprovalRule>> {
+    singleton(storage, APPROVAL_RULES_KEY)
+}
+
+/// Retrieves a read-only singleton handle for the approval rules.
+pub fn approval_rules_read(storage: &dyn Storage) -> ReadonlySingleton<'_, Vec<ApprovalRule>> {
+    singleton_read(storage, APPROVAL_RULES_KEY)
+}
+
+/// The fields of an invoice covered by its `line_hash`, which a disclosure grant reveals or replaces by their salted
+/// hashes, in commitment order.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DisclosureField {
+    InvoiceNumber,
+    Date,
+    ClientName,
+    Description,
+    TotalAmount,
+    TaxAmount,
+    Currency,
+    DocHash,
+    /// The JSON-encoded journal lines.
+    Lines,
+    /// The JSON-encoded item lines.
+    Items,
+}
+
+/// A disclosure grant: the fields of the invoices an address may see in clear.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct DisclosureGrant {
+    /// The fields revealed. The other fields are replaced by their salted hashes.
+    pub fields: Vec<DisclosureField>,
+    /// The indexes of the invoices disclosed, or every invoice when `None`.
+    pub invoices: Option<Vec<u32>>,
+}
+
+/// Retrieves a mutable bucket handle for the disclosure grants, keyed by grantee address.
+pub fn disclosure_grants(storage: &mut dyn Storage) -> Bucket<'_, DisclosureGrant> {
+    bucket(storage, DISCLOSURE_GRANTS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the disclosure grants, keyed by grantee address.
+pub fn disclosure_grants_read(storage: &dyn Storage) -> ReadonlyBucket<'_, DisclosureGrant> {
+    bucket_read(storage, DISCLOSURE_GRANTS_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the salts of the sealed invoice versions, keyed by `invoice_version_key`.
+pub fn disclosure_salts(storage: &mut dyn Storage) -> Bucket<'_, Binary> {
+    bucket(storage, DISCLOSURE_SALTS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the salts of the sealed invoice versions, keyed by `invoice_version_key`.
+pub fn disclosure_salts_read(storage: &dyn Storage) -> ReadonlyBucket<'_, Binary> {
+    bucket_read(storage, DISCLOSURE_SALTS_KEY)
+}
+
+/// Retrieves a mutable singleton handle for the split-invoice detection rule.
+pub fn split_rule(storage: &mut dyn Storage) -> Singleton<'_, SplitRule> {
+    singleton(storage, SPLIT_RULE_KEY)
+}
+
+/// Retrieves a read-only singleton handle for the split-invoice detection rule.
+pub fn split_rule_read(storage: &dyn Storage) -> ReadonlySingleton<'_, SplitRule> {
+    singleton_read(storage, SPLIT_RULE_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the invoices submitted for approval, keyed by pending index.
+pub fn pending_invoices(storage: &mut dyn Storage) -> Bucket<'_, PendingInvoice> {
+    bucket(storage, PENDING_INVOICES_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the invoices submitted for approval, keyed by pending index.
+pub fn pending_invoices_read(storage: &dyn Storage) -> ReadonlyBucket<'_, PendingInvoice> {
+    bucket_read(storage, PENDING_INVOICES_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the sorted pending indexes of the invoices waiting for approval, keyed by
+/// big-endian page number.
+pub fn open_approvals(storage: &mut dyn Storage) -> Bucket<'_, Vec<u32>> {
+    bucket(storage, OPEN_APPROVALS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the sorted pending indexes of the invoices waiting for approval, keyed by
+/// big-endian page number.
+pub fn open_approvals_read(storage: &dyn Storage) -> ReadonlyBucket<'_, Vec<u32>> {
+    bucket_read(storage, OPEN_APPROVALS_KEY)
+}
+
+/// Retrieves a mutable bucket handle for the approvers, keyed by address.
+pub fn approvers(storage: &mut dyn Storage) -> Bucket<'_, bool> {
+    bucket(storage, APPROVERS_KEY)
+}
+
+/// Retrieves a read-only bucket handle for the approvers, keyed by address.
+pub fn approvers_read(storage: &dyn Storage) -> ReadonlyBucket<'_, bool> {
+    bucket_read(storage, APPROVERS_KEY)
+}
@@ -2,6 +2,7 @@ import React, { useState } from 'react'
 import FileUpload from './FileUpload'
 import PDFViewer from './PDFViewer'
 import ocrService from './OCRService'
+import { createFingerprint } from './CreateFingerprint'
 import { Wallet, SecretNetworkClient } from "secretjs";
 import { add_invoice, get_all_invoices, update_auditor } from './contract'
 import { config } from './config'
@@ -157,8 +158,10 @@ const App: React.FC = () => {
   }
 
   const sealOnBC = async () => {
-    if (!apiResponse) return
+    if (!apiResponse || !uploadedFile) return
     try {
+      // The contract only seals invoices carrying the SHA-256 fingerprint of their document
+      const docHash = await createFingerprint(uploadedFile)
       const invoice = {
         invoice_number: apiResponse.invoice_number,
         date: apiResponse.date,
@@ -167,10 +170,10 @@ const App: React.FC = () => {
         total_amount: apiResponse.total_amount.toString(),
         tax_amount: apiResponse.tax_amount.toString(),
         currency: apiResponse.currency,
-        doc_hash: '', // Add appropriate value
-        line_hash: '', // Add appropriate value
+        doc_hash: docHash,
+        line_hash: '', // Computed by the contract
         auditors: '', // Add appropriate value
-        credibility: credibilityScore?.toString() || '',
+        credibility: Math.round(credibilityScore ?? 0),
         audit_state: '', // Add appropriate value
       }
 
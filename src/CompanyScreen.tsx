@@ -272,7 +272,7 @@ const CompanyScreen: React.FC = () => {
         doc_hash: fingerprint,
         line_hash: line_hash,
         auditors: '',
-        credibility: credibilityScore.toString(),
+        credibility: Math.round(Number(credibilityScore)),
         audit_state: '',
       };
   
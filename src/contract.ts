@@ -12,7 +12,7 @@ interface Invoice {
   doc_hash: string;
   line_hash: string;
   auditors: string;
-  credibility: string;
+  credibility: number;
   audit_state: string;
 }
 